@@ -0,0 +1,306 @@
+// Crash-safe checkpoints for long scans. Every directory that completes is appended to a log
+// for its scan root as one JSON line listing its children, and at most every SYNC_INTERVAL the
+// log is synced to disk together with the directories still being scanned. A later scan of the
+// same root reads the log back and takes completed directories from it instead of walking them
+// again. A line torn by a crash is cut off on load; the log is removed once a scan completes.
+// A directory's mtime only changes when entries are added, removed or renamed in it, so a file
+// that grew or shrank in place since the interrupted scan keeps its old size on resume.
+// The log is locked while a scan writes it, so a second scan of the same root runs without one.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::osname;
+use crate::progress;
+use crate::spill::SpillEntry;
+use crate::tree::{NodeId, ScanTree};
+
+const CHECKPOINT_VERSION: u32 = 1;
+const SYNC_INTERVAL: Duration = Duration::from_secs(30);
+
+// First line of a log; a log is only resumed by a scan that could have written it
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Header {
+    version: u32,
+    root: String,
+    max_depth: usize,
+}
+
+// One completed directory; `p` is its path below the scan root with '/' separators
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DirectoryRecord {
+    #[serde(rename = "p")]
+    pub path: String,
+    #[serde(rename = "m")]
+    pub mtime: i64, // Of the directory itself; a directory changed since is scanned again
+    #[serde(rename = "h", default, skip_serializing_if = "Option::is_none")]
+    pub hidden_entries: Option<u64>, // Past the depth limit: measured, not walked
+    #[serde(rename = "e")]
+    pub entries: Vec<SpillEntry>,
+}
+
+// Written at every sync: the directories that were still being scanned. Their records from an
+// earlier run, if any, were found stale by that scan, so a resume doesn't take them
+#[derive(Debug, Serialize, Deserialize)]
+struct Pending {
+    pending: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Line {
+    Directory(DirectoryRecord),
+    Pending(Pending),
+}
+
+// What a resumed scan found in the log
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Resumed {
+    pub directories: usize, // Completed directories available to skip
+    pub pending: usize, // Directories the interrupted scan was in the middle of
+}
+
+struct Writer {
+    file: BufWriter<File>,
+    synced: Instant,
+}
+
+pub struct Checkpoint {
+    path: PathBuf,
+    completed: Mutex<HashMap<String, DirectoryRecord>>, // Left by the interrupted scan, taken as used
+    resumed: Option<Resumed>,
+    open: Mutex<HashSet<NodeId>>, // Directories being scanned now
+    writer: Mutex<Writer>,
+}
+
+impl Checkpoint {
+    // Resume the log of an earlier scan of `root`, or start a new one
+    pub fn open(root: &Path, max_depth: usize) -> io::Result<Self> {
+        let canonical = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+        let header = Header {
+            version: CHECKPOINT_VERSION,
+            root: osname::encode_path(&canonical),
+            max_depth,
+        };
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        header.root.hash(&mut hasher);
+        let path = progress::cache_dir().join(format!("storviz-checkpoint-{:016x}.jsonl", hasher.finish()));
+        Self::open_at(path, &header)
+    }
+
+    fn open_at(path: PathBuf, header: &Header) -> io::Result<Self> {
+        let mut file = OpenOptions::new().create(true).read(true).write(true).truncate(false).open(&path)?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => return Err(io::Error::other("another scan of this root is using the checkpoint")),
+            Err(TryLockError::Error(e)) => return Err(e),
+        }
+
+        let (completed, pending, valid_length) = read_log(&file, header)?;
+        let resumed = (!completed.is_empty()).then_some(Resumed { directories: completed.len(), pending: pending.len() });
+
+        if valid_length == 0 {
+            file.set_len(0)?;
+            file.seek(SeekFrom::Start(0))?;
+            file.write_all(&serde_json::to_vec(header).map_err(io::Error::other)?)?;
+            file.write_all(b"\n")?;
+        } else {
+            // Cut off whatever the crash left half-written
+            file.set_len(valid_length)?;
+            file.seek(SeekFrom::End(0))?;
+        }
+
+        if let Some(resumed) = resumed {
+            println!("♻️ Resuming from checkpoint: {} completed directories, {} pending", resumed.directories, resumed.pending);
+            for key in pending.iter().take(3) {
+                println!("   ↳ interrupted in /{}", key);
+            }
+        }
+        Ok(Self {
+            path,
+            completed: Mutex::new(completed),
+            resumed,
+            open: Mutex::new(HashSet::new()),
+            writer: Mutex::new(Writer { file: BufWriter::new(file), synced: Instant::now() }),
+        })
+    }
+
+    pub fn resumed(&self) -> Option<Resumed> {
+        self.resumed
+    }
+
+    // The record of a directory the interrupted scan completed, if it hasn't changed since
+    pub fn take(&self, key: &str, mtime: i64) -> Option<DirectoryRecord> {
+        let mut completed = self.completed.lock().ok()?;
+        if completed.is_empty() {
+            return None;
+        }
+        completed.remove(key).filter(|record| record.mtime == mtime)
+    }
+
+    pub fn open_directory(&self, id: NodeId) {
+        if let Ok(mut open) = self.open.lock() {
+            open.insert(id);
+        }
+    }
+
+    // Append a directory completed by `record`, syncing the log if the last sync is long enough
+    // ago. Only the pending list needs the tree, and only for as long as it takes to list it
+    pub fn complete_directory(&self, id: NodeId, record: DirectoryRecord, tree: &Mutex<ScanTree>) -> io::Result<()> {
+        if let Ok(mut open) = self.open.lock() {
+            open.remove(&id);
+        }
+        let mut writer = self.writer.lock().map_err(|_| io::Error::other("checkpoint lock poisoned"))?;
+        writer.file.write_all(&serde_json::to_vec(&record).map_err(io::Error::other)?)?;
+        writer.file.write_all(b"\n")?;
+        if writer.synced.elapsed() >= SYNC_INTERVAL {
+            self.sync_locked(&mut writer, tree)?;
+        }
+        Ok(())
+    }
+
+    // Write the pending directories and make everything so far durable
+    pub fn sync(&self, tree: &Mutex<ScanTree>) -> io::Result<()> {
+        let mut writer = self.writer.lock().map_err(|_| io::Error::other("checkpoint lock poisoned"))?;
+        self.sync_locked(&mut writer, tree)
+    }
+
+    fn sync_locked(&self, writer: &mut Writer, tree: &Mutex<ScanTree>) -> io::Result<()> {
+        let open: Vec<NodeId> = self.open.lock().map(|open| open.iter().copied().collect()).unwrap_or_default();
+        let pending = {
+            let tree = tree.lock().map_err(|_| io::Error::other("tree lock poisoned"))?;
+            Pending { pending: open.into_iter().map(|id| key(&tree, id)).collect() }
+        };
+        writer.file.write_all(&serde_json::to_vec(&pending).map_err(io::Error::other)?)?;
+        writer.file.write_all(b"\n")?;
+        writer.file.flush()?;
+        writer.file.get_ref().sync_data()?;
+        writer.synced = Instant::now();
+        Ok(())
+    }
+
+    // The scan completed, so there is nothing left to resume
+    pub fn remove(&self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            eprintln!("❌ Failed to remove checkpoint {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+// A completed directory's record, listing its children as the tree has them now
+pub fn record(tree: &ScanTree, id: NodeId, hidden_entries: Option<u64>) -> DirectoryRecord {
+    DirectoryRecord {
+        path: key(tree, id),
+        mtime: tree.node(id).meta.mtime,
+        hidden_entries,
+        entries: tree.children(id).map(|child| SpillEntry::from_node(tree, child)).collect(),
+    }
+}
+
+// Records by path, the last pending list and the length of the intact part of the log; a log
+// written for another root or depth limit counts as empty
+fn read_log(file: &File, header: &Header) -> io::Result<(HashMap<String, DirectoryRecord>, Vec<String>, u64)> {
+    let mut reader = BufReader::new(file);
+    let mut line = String::new();
+    let mut completed = HashMap::new();
+    let mut pending = Vec::new();
+
+    let mut valid_length = reader.read_line(&mut line)? as u64;
+    if !line.ends_with('\n') || serde_json::from_str::<Header>(&line).ok().as_ref() != Some(header) {
+        return Ok((completed, pending, 0));
+    }
+
+    loop {
+        line.clear();
+        let read = reader.read_line(&mut line)?;
+        if read == 0 || !line.ends_with('\n') {
+            break;
+        }
+        match serde_json::from_str::<Line>(&line) {
+            Ok(Line::Directory(record)) => {
+                completed.insert(record.path.clone(), record);
+            }
+            Ok(Line::Pending(list)) => {
+                // Rescanned because they changed; a record written after this replaces the old one
+                for key in &list.pending {
+                    completed.remove(key);
+                }
+                pending = list.pending;
+            }
+            Err(_) => break,
+        }
+        valid_length += read as u64;
+    }
+    Ok((completed, pending, valid_length))
+}
+
+// Path of a node below the scan root, as used in the log
+pub fn key(tree: &ScanTree, id: NodeId) -> String {
+    let mut names = Vec::new();
+    let mut current = id;
+    while let Some(parent) = tree.parent(current) {
+        names.push(tree.name(current));
+        current = parent;
+    }
+    names.reverse();
+    names.join("/")
+}
+
+pub fn child_key(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", parent, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::{EntryMeta, NO_NODE};
+
+    #[test]
+    fn log_round_trips_and_torn_lines_are_cut_off() {
+        let path = std::env::temp_dir().join(format!("storviz-test-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let header = Header { version: CHECKPOINT_VERSION, root: "/scan".to_string(), max_depth: 8 };
+
+        let mut tree = ScanTree::new("/scan");
+        let root = tree.add_node(NO_NODE, "scan", true, 0, EntryMeta::default()).unwrap();
+        let a = tree.add_node(root, "a", true, 0, EntryMeta::default()).unwrap();
+        tree.add_node(a, "file.txt", false, 4096, EntryMeta::default()).unwrap();
+        let b = tree.add_node(root, "b", true, 0, EntryMeta::default()).unwrap();
+        let tree = Mutex::new(tree);
+
+        let checkpoint = Checkpoint::open_at(path.clone(), &header).unwrap();
+        assert!(checkpoint.resumed().is_none());
+        assert!(Checkpoint::open_at(path.clone(), &header).is_err(), "a second scan must not share the log");
+        for id in [a, b] {
+            let record = record(&tree.lock().unwrap(), id, None);
+            checkpoint.complete_directory(id, record, &tree).unwrap();
+        }
+        // `b` is being scanned again, so its earlier record no longer counts
+        checkpoint.open_directory(b);
+        checkpoint.sync(&tree).unwrap();
+        drop(checkpoint);
+
+        let intact = fs::metadata(&path).unwrap().len();
+        OpenOptions::new().append(true).open(&path).unwrap().write_all(b"{\"p\":\"c\",\"m\":0,\"e\":[").unwrap();
+
+        let checkpoint = Checkpoint::open_at(path.clone(), &header).unwrap();
+        let resumed = checkpoint.resumed().unwrap();
+        assert_eq!((resumed.directories, resumed.pending), (1, 1));
+        assert_eq!(fs::metadata(&path).unwrap().len(), intact);
+        assert!(checkpoint.take("b", 0).is_none());
+        let record = checkpoint.take("a", 0).unwrap();
+        assert_eq!(record.entries.len(), 1);
+        assert_eq!((record.entries[0].name.as_str(), record.entries[0].size), ("file.txt", 4096));
+        checkpoint.remove();
+    }
+}
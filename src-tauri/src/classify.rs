@@ -0,0 +1,385 @@
+// File type classification: extension lookup first, then magic-byte sniffing of the
+// first bytes for files with no (or an unknown) extension, or for every file when asked to.
+
+use serde::{Deserialize, Serialize};
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::stats::{HistogramBucket, SizeHistogram, SizeStats, SizeSummary};
+
+// Enough to reach the tar header magic at offset 257
+const SNIFF_BYTES: usize = 512;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(u8)]
+pub enum Category {
+    #[default]
+    Other,
+    Video,
+    Image,
+    Audio,
+    Archive,
+    DiskImage,
+    VmImage,
+    Package,
+    Database,
+    Code,
+    Document,
+    Executable,
+    Font,
+    Log,
+}
+
+impl Category {
+    pub const ALL: [Category; 14] = [
+        Category::Other,
+        Category::Video,
+        Category::Image,
+        Category::Audio,
+        Category::Archive,
+        Category::DiskImage,
+        Category::VmImage,
+        Category::Package,
+        Category::Database,
+        Category::Code,
+        Category::Document,
+        Category::Executable,
+        Category::Font,
+        Category::Log,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Category::Other => "other",
+            Category::Video => "video",
+            Category::Image => "image",
+            Category::Audio => "audio",
+            Category::Archive => "archive",
+            Category::DiskImage => "disk_image",
+            Category::VmImage => "vm_image",
+            Category::Package => "package",
+            Category::Database => "database",
+            Category::Code => "code",
+            Category::Document => "document",
+            Category::Executable => "executable",
+            Category::Font => "font",
+            Category::Log => "log",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Category> {
+        Category::ALL.into_iter().find(|category| category.as_str().eq_ignore_ascii_case(name))
+    }
+}
+
+// When to read file contents: never, only when the extension says nothing, or always.
+// Reading opens every file it applies to, so scans only do it when asked
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SniffMode {
+    #[default]
+    Off,
+    Unknown,
+    All,
+}
+
+// Longer suffixes, digit-only ones (split archives, rotated logs) and ones with other
+// characters are treated as no extension
+const MAX_EXTENSION_LENGTH: usize = 10;
+
+pub fn extension_of(name: &str) -> Option<String> {
+    match name.rsplit_once('.') {
+        Some((stem, ext))
+            if !stem.is_empty()
+                && !ext.is_empty()
+                && ext.len() <= MAX_EXTENSION_LENGTH
+                && ext.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+                && !ext.bytes().all(|b| b.is_ascii_digit()) =>
+        {
+            Some(ext.to_ascii_lowercase())
+        }
+        _ => None,
+    }
+}
+
+pub fn classify(path: &Path, name: &str, mode: SniffMode) -> Category {
+    let by_extension = extension_of(name).map_or(Category::Other, |ext| category_for_extension(&ext));
+
+    let sniff = match mode {
+        SniffMode::Off => false,
+        SniffMode::Unknown => by_extension == Category::Other,
+        SniffMode::All => true,
+    };
+    if !sniff {
+        return by_extension;
+    }
+
+    match sniff_file(path) {
+        // Containers like ZIP or RIFF are shared by many formats; a known extension is more specific
+        Some(Category::Archive) if by_extension != Category::Other => by_extension,
+        Some(category) => category,
+        None => by_extension,
+    }
+}
+
+fn sniff_file(path: &Path) -> Option<Category> {
+    let mut header = [0u8; SNIFF_BYTES];
+    let mut file = File::open(path).ok()?;
+    let mut length = 0;
+    while length < header.len() {
+        match file.read(&mut header[length..]) {
+            Ok(0) => break,
+            Ok(read) => length += read,
+            Err(_) => break,
+        }
+    }
+    sniff(&header[..length])
+}
+
+pub fn sniff(bytes: &[u8]) -> Option<Category> {
+    let starts = |magic: &[u8]| bytes.starts_with(magic);
+    let at = |offset: usize, magic: &[u8]| bytes.len() >= offset + magic.len() && &bytes[offset..offset + magic.len()] == magic;
+
+    // ISO base media (MP4, MOV, HEIC, AVIF, M4A): "ftyp" at offset 4, brand decides the kind
+    if at(4, b"ftyp") {
+        let brand = bytes.get(8..12).unwrap_or_default();
+        return Some(match brand {
+            b"heic" | b"heix" | b"mif1" | b"msf1" | b"avif" | b"avis" => Category::Image,
+            b"M4A " | b"M4B " | b"M4P " => Category::Audio,
+            _ => Category::Video,
+        });
+    }
+    if starts(b"RIFF") {
+        return match bytes.get(8..12) {
+            Some(b"WEBP") => Some(Category::Image),
+            Some(b"WAVE") => Some(Category::Audio),
+            Some(b"AVI ") => Some(Category::Video),
+            _ => None,
+        };
+    }
+
+    let category = if starts(b"\x89PNG\r\n\x1a\n")
+        || starts(b"\xff\xd8\xff")
+        || starts(b"GIF87a")
+        || starts(b"GIF89a")
+        || starts(b"II*\0")
+        || starts(b"MM\0*")
+        || starts(b"8BPS")
+    {
+        Category::Image
+    } else if starts(b"\x1a\x45\xdf\xa3") || starts(b"FLV\x01") || starts(b"\x00\x00\x01\xba") {
+        Category::Video
+    } else if starts(b"ID3") || starts(b"fLaC") || starts(b"OggS") || starts(b"\xff\xfb") || starts(b"\xff\xf3") {
+        Category::Audio
+    } else if starts(b"QFI\xfb") || starts(b"KDMV") || starts(b"# Disk DescriptorFile") || starts(b"vhdxfile") || starts(b"conectix") || at(64, b"\x7f\x10\xda\xbe") {
+        Category::VmImage
+    } else if starts(b"SQLite format 3\0") {
+        Category::Database
+    } else if starts(b"PK\x03\x04")
+        || starts(b"\x1f\x8b")
+        || starts(b"BZh")
+        || starts(b"\xfd7zXZ\0")
+        || starts(b"7z\xbc\xaf\x27\x1c")
+        || starts(b"Rar!\x1a\x07")
+        || starts(b"\x28\xb5\x2f\xfd")
+        || at(257, b"ustar")
+    {
+        Category::Archive
+    } else if starts(b"\x7fELF")
+        || starts(b"MZ")
+        || starts(b"\xfe\xed\xfa\xce")
+        || starts(b"\xfe\xed\xfa\xcf")
+        || starts(b"\xce\xfa\xed\xfe")
+        || starts(b"\xcf\xfa\xed\xfe")
+        || starts(b"\0asm")
+    {
+        Category::Executable
+    } else if starts(b"%PDF") || starts(b"{\\rtf") || starts(b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1") {
+        Category::Document
+    } else if starts(b"wOFF") || starts(b"wOF2") || starts(b"OTTO") || starts(b"\0\x01\0\0\0") {
+        Category::Font
+    } else if starts(b"#!") {
+        Category::Code
+    } else {
+        return None;
+    };
+
+    Some(category)
+}
+
+pub fn category_for_extension(ext: &str) -> Category {
+    match ext {
+        "mp4" | "m4v" | "avi" | "mkv" | "mov" | "wmv" | "flv" | "webm" | "mpg" | "mpeg" | "mpe" | "m2v" | "3gp" | "3g2"
+        | "ogv" | "vob" | "divx" | "mxf" | "m2ts" | "mts" | "f4v" | "rm" | "rmvb" | "asf" | "qt" | "y4m" => {
+            Category::Video
+        }
+        "jpg" | "jpeg" | "jpe" | "jfif" | "png" | "gif" | "bmp" | "webp" | "ico" | "tiff" | "tif" | "heic" | "heif"
+        | "avif" | "svg" | "svgz" | "psd" | "psb" | "xcf" | "kra" | "raw" | "cr2" | "cr3" | "nef" | "arw" | "dng"
+        | "orf" | "rw2" | "raf" => Category::Image,
+        "mp3" | "aac" | "m4a" | "ogg" | "oga" | "opus" | "wma" | "flac" | "ape" | "alac" | "wav" | "aiff" | "aif"
+        | "mid" | "midi" | "dsf" | "dff" | "amr" => Category::Audio,
+        "zip" | "rar" | "7z" | "tar" | "gz" | "gzip" | "bz2" | "xz" | "lz" | "lzma" | "zst" | "tgz" | "tbz2" | "txz"
+        | "cab" | "lzh" | "arj" | "jar" | "war" => Category::Archive,
+        "iso" | "img" | "dmg" | "toast" | "cue" | "mdf" | "nrg" | "sparseimage" | "sparsebundle" => Category::DiskImage,
+        "vmdk" | "vdi" | "vhd" | "vhdx" | "qcow" | "qcow2" | "ova" | "ovf" | "hdd" | "vmem" | "vmsn" | "nvram" => {
+            Category::VmImage
+        }
+        "pkg" | "deb" | "rpm" | "apk" | "msi" | "msix" | "appx" | "snap" | "flatpak" | "appimage" | "ipa" | "xip" => {
+            Category::Package
+        }
+        "sqlite" | "sqlite3" | "db" | "db3" | "mdb" | "accdb" | "ibd" | "frm" | "myd" | "myi" | "ldf" | "ndf" | "dbf"
+        | "realm" | "leveldb" | "rdb" | "sql" => Category::Database,
+        "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "vue" | "svelte" | "html" | "htm" | "css" | "scss" | "sass"
+        | "less" | "py" | "pyi" | "ipynb" | "java" | "kt" | "kts" | "scala" | "groovy" | "gradle" | "c" | "h" | "cpp"
+        | "cc" | "cxx" | "hpp" | "hh" | "m" | "mm" | "cs" | "vb" | "fs" | "rs" | "go" | "zig" | "nim" | "php" | "rb"
+        | "pl" | "pm" | "lua" | "r" | "sh" | "bash" | "zsh" | "fish" | "bat" | "cmd" | "ps1" | "hs" | "ml" | "swift"
+        | "dart" | "ex" | "exs" | "erl" | "clj" | "json" | "yaml" | "yml" | "toml" | "xml" | "ini" | "cfg" | "conf" => {
+            Category::Code
+        }
+        "pdf" | "doc" | "docx" | "odt" | "rtf" | "txt" | "md" | "markdown" | "rst" | "tex" | "epub" | "mobi" | "xls"
+        | "xlsx" | "ods" | "csv" | "tsv" | "ppt" | "pptx" | "odp" | "key" | "pages" | "numbers" | "eml" | "msg" => {
+            Category::Document
+        }
+        "exe" | "dll" | "so" | "dylib" | "sys" | "bin" | "elf" | "o" | "a" | "lib" | "class" | "pyc" | "wasm" | "node" => {
+            Category::Executable
+        }
+        "ttf" | "otf" | "woff" | "woff2" | "eot" | "ttc" | "fon" => Category::Font,
+        "log" | "out" | "err" | "trace" | "journal" => Category::Log,
+        _ => Category::Other,
+    }
+}
+
+// Distinct extensions kept apart; the ones seen after that are counted together
+const MAX_EXTENSIONS: usize = 1000;
+pub const OTHER_EXTENSIONS: &str = "*";
+
+// Size statistics per category and per extension, plus a histogram over all files,
+// accumulated while scanning
+#[derive(Default)]
+pub struct TypeStats {
+    categories: HashMap<Category, SizeStats>,
+    extensions: HashMap<String, SizeStats>,
+    histogram: SizeHistogram,
+}
+
+impl TypeStats {
+    pub fn add(&mut self, category: Category, extension: Option<String>, size: u64, path: &str) {
+        self.categories.entry(category).or_default().add_file(size, || path.to_string());
+        let extension = self.extension_key(extension);
+        self.extensions.entry(extension).or_default().add_file(size, || path.to_string());
+        self.histogram.add(size);
+    }
+
+    fn extension_key(&self, extension: Option<String>) -> String {
+        let extension = extension.unwrap_or_default();
+        if self.extensions.len() >= MAX_EXTENSIONS && !self.extensions.contains_key(&extension) {
+            OTHER_EXTENSIONS.to_string()
+        } else {
+            extension
+        }
+    }
+
+    // Credit a finished directory with the bytes of each type among its direct file children
+    pub fn add_directory(&mut self, files: impl Iterator<Item = (Category, Option<String>, u64)>, path: impl Fn() -> String) {
+        let mut categories: HashMap<Category, u64> = HashMap::new();
+        let mut extensions: HashMap<String, u64> = HashMap::new();
+        for (category, extension, size) in files {
+            *categories.entry(category).or_default() += size;
+            *extensions.entry(self.extension_key(extension)).or_default() += size;
+        }
+
+        // The path is built at most once, and only if the directory makes it into a list
+        let directory_path = OnceCell::new();
+        let directory_path = || directory_path.get_or_init(&path).clone();
+        for (category, bytes) in categories {
+            if let Some(stats) = self.categories.get_mut(&category) {
+                stats.add_directory(bytes, directory_path);
+            }
+        }
+        for (extension, bytes) in extensions {
+            if let Some(stats) = self.extensions.get_mut(&extension) {
+                stats.add_directory(bytes, directory_path);
+            }
+        }
+    }
+
+    pub fn breakdown(&self) -> TypeBreakdown {
+        let mut categories: Vec<CategoryTotals> = self
+            .categories
+            .iter()
+            .map(|(&category, stats)| CategoryTotals { category, stats: stats.summary() })
+            .collect();
+        categories.sort_by_key(|totals| std::cmp::Reverse(totals.stats.bytes));
+
+        let mut extensions: Vec<ExtensionTotals> = self
+            .extensions
+            .iter()
+            .map(|(extension, stats)| ExtensionTotals {
+                extension: extension.clone(),
+                category: category_for_extension(extension),
+                stats: stats.summary(),
+            })
+            .collect();
+        extensions.sort_by_key(|totals| std::cmp::Reverse(totals.stats.bytes));
+
+        TypeBreakdown { categories, extensions, size_histogram: self.histogram.octaves() }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CategoryTotals {
+    pub category: Category,
+    #[serde(flatten)]
+    pub stats: SizeSummary,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ExtensionTotals {
+    pub extension: String, // Empty for files without an extension, OTHER_EXTENSIONS past the cap
+    pub category: Category,
+    #[serde(flatten)]
+    pub stats: SizeSummary,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TypeBreakdown {
+    pub categories: Vec<CategoryTotals>,
+    pub extensions: Vec<ExtensionTotals>,
+    pub size_histogram: Vec<HistogramBucket>, // Log-scale, over all files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extension_of_skips_numeric_and_odd_suffixes() {
+        assert_eq!(extension_of("Movie.MKV").as_deref(), Some("mkv"));
+        assert_eq!(extension_of("archive.7z").as_deref(), Some("7z"));
+        assert_eq!(extension_of("backup.001"), None);
+        assert_eq!(extension_of("notes.a very long suffix"), None);
+        assert_eq!(extension_of("report.averyveryverylongone"), None);
+        assert_eq!(extension_of(".bashrc"), None);
+        assert_eq!(extension_of("trailing."), None);
+    }
+
+    #[test]
+    fn extensions_past_the_cap_are_folded() {
+        let mut stats = TypeStats::default();
+        for i in 0..MAX_EXTENSIONS + 5 {
+            stats.add(Category::Other, Some(format!("x{}", i)), 10, "/f");
+        }
+        stats.add(Category::Code, Some("x0".to_string()), 5, "/g");
+
+        let breakdown = stats.breakdown();
+        assert_eq!(breakdown.extensions.len(), MAX_EXTENSIONS + 1);
+        let other = breakdown.extensions.iter().find(|totals| totals.extension == OTHER_EXTENSIONS).unwrap();
+        assert_eq!(other.stats.bytes, 50);
+        let first = breakdown.extensions.iter().find(|totals| totals.extension == "x0").unwrap();
+        assert_eq!(first.stats.bytes, 15);
+    }
+}
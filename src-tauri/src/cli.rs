@@ -0,0 +1,194 @@
+// Command-line entry points, handled before any window is created:
+//   storviz report <DIR | FILE> [-o report.html] [--title TITLE] [--depth N] [--locale zh-Hant|en-US|ja-JP] [--import [ncdu|du]] [LIMITS]
+//   storviz render <DIR | FILE> [-o treemap.svg|png] [--node PATH] [--kind treemap|sunburst] [--width W] [--height H] [--depth N] [--no-labels] [--import [ncdu|du]] [LIMITS]
+// LIMITS: [--max-time SECONDS] [--max-entries N] [--max-ops N] [--low-priority] [--checkpoint]
+// Anything else (including no arguments) starts the app as usual.
+
+use std::path::PathBuf;
+
+use crate::import::ImportFormat;
+use crate::layout::LayoutKind;
+use crate::limits::ScanLimits;
+use crate::query;
+use crate::render::{self, RenderOptions};
+use crate::report::{ReportLocale, ReportOptions};
+use crate::tree::ROOT_NODE;
+
+const USAGE: &str = "用法:
+  storviz report <資料夾 | 匯入檔> [選項]
+    -o, --output <檔案>    報告輸出位置 (預設 storviz-report.html)
+    --title <標題>         報告標題
+    --depth <N>            樹狀圖保留的層數
+    --locale <語言>        報告語言：zh-Hant、en-US 或 ja-JP (預設 zh-Hant)
+    --import [ncdu|du]     將路徑視為 ncdu 匯出檔或 du -ab 輸出 (未指定格式時自動判斷)
+    --max-time <秒>        掃描時間上限，到達後以目前結果產生報告
+    --max-entries <N>      掃描項目數上限
+    --max-ops <N>          每秒最多的檔案系統操作數 (stat / 讀取資料夾)
+    --low-priority         以較低的 CPU 與 I/O 優先權掃描
+    --checkpoint           記錄掃描進度，中斷後再次掃描同一路徑時從上次的進度繼續
+  storviz render <資料夾 | 匯入檔> [選項]
+    -o, --output <檔案>    圖片輸出位置，副檔名 .svg 或 .png (預設 storviz-treemap.svg)
+    --node <路徑>          只畫這個子資料夾 (絕對路徑或相對於掃描根目錄)
+    --kind <類型>          treemap 或 sunburst
+    --width <像素>         圖片寬度
+    --height <像素>        圖片高度
+    --depth <N>            顯示的層數
+    --no-labels            不顯示名稱
+    --import [ncdu|du]     同 report
+    --max-time, --max-entries, --max-ops, --low-priority, --checkpoint  同 report";
+
+// Exit code when a subcommand ran, None to start the app
+pub fn run() -> Option<i32> {
+    let mut args = std::env::args().skip(1);
+    let command = args.next()?;
+    if !matches!(command.as_str(), "report" | "render" | "help" | "--help" | "-h") {
+        return None;
+    }
+    attach_console();
+
+    let result = match command.as_str() {
+        "report" => report(args.collect()),
+        "render" => render(args.collect()),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => unreachable!(),
+    };
+
+    match result {
+        Ok(()) => Some(0),
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            Some(1)
+        }
+    }
+}
+
+// Release builds are GUI programs on Windows and start without a console, so output would go
+// nowhere; write to the console of the shell that started us instead
+#[cfg(windows)]
+fn attach_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+fn report(args: Vec<String>) -> Result<(), String> {
+    let mut target: Option<String> = None;
+    let mut output = PathBuf::from("storviz-report.html");
+    let mut options = ReportOptions::default();
+    let mut import: Option<ImportFormat> = None;
+    let mut limits = ScanLimits::default();
+    let mut checkpoint = false;
+
+    let mut args = args.into_iter().peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => output = PathBuf::from(value(&mut args, &arg)?),
+            "--title" => options.title = Some(value(&mut args, &arg)?),
+            "--depth" => {
+                let depth = value(&mut args, &arg)?;
+                options.max_depth = Some(depth.parse().map_err(|_| format!("無效的層數: {}", depth))?);
+            }
+            "--locale" => {
+                let locale = value(&mut args, &arg)?;
+                options.locale = ReportLocale::parse(&locale).ok_or_else(|| format!("不支援的語言: {}", locale))?;
+            }
+            "--import" => import = Some(import_format(&mut args)),
+            "--max-time" => limits.max_duration_ms = Some(number::<u64>(&mut args, &arg)? * 1000),
+            "--max-entries" => limits.max_entries = Some(number(&mut args, &arg)?),
+            "--max-ops" => limits.max_ops_per_second = Some(number(&mut args, &arg)?),
+            "--low-priority" => limits.low_priority = true,
+            "--checkpoint" => checkpoint = true,
+            _ if arg.starts_with('-') => return Err(format!("未知的選項: {}\n{}", arg, USAGE)),
+            _ => target = Some(arg),
+        }
+    }
+
+    let target = target.ok_or_else(|| format!("缺少要分析的路徑\n{}", USAGE))?;
+    println!("🔍 Scanning {}", target);
+    let scan = crate::run_headless(&target, import, limits, checkpoint)?;
+    crate::write_report(&scan, &output, &options)
+}
+
+fn render(args: Vec<String>) -> Result<(), String> {
+    let mut target: Option<String> = None;
+    let mut output = PathBuf::from("storviz-treemap.svg");
+    let mut node: Option<String> = None;
+    let mut options = RenderOptions::default();
+    let mut import: Option<ImportFormat> = None;
+    let mut limits = ScanLimits::default();
+    let mut checkpoint = false;
+
+    let mut args = args.into_iter().peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => output = PathBuf::from(value(&mut args, &arg)?),
+            "--node" => node = Some(value(&mut args, &arg)?),
+            "--kind" => {
+                options.kind = match value(&mut args, &arg)?.as_str() {
+                    "treemap" => LayoutKind::Treemap,
+                    "sunburst" => LayoutKind::Sunburst,
+                    other => return Err(format!("未知的圖表類型: {}", other)),
+                }
+            }
+            "--width" => options.width = number(&mut args, &arg)?,
+            "--height" => options.height = number(&mut args, &arg)?,
+            "--depth" => options.max_depth = number(&mut args, &arg)?,
+            "--no-labels" => options.labels = false,
+            "--import" => import = Some(import_format(&mut args)),
+            "--max-time" => limits.max_duration_ms = Some(number::<u64>(&mut args, &arg)? * 1000),
+            "--max-entries" => limits.max_entries = Some(number(&mut args, &arg)?),
+            "--max-ops" => limits.max_ops_per_second = Some(number(&mut args, &arg)?),
+            "--low-priority" => limits.low_priority = true,
+            "--checkpoint" => checkpoint = true,
+            _ if arg.starts_with('-') => return Err(format!("未知的選項: {}\n{}", arg, USAGE)),
+            _ => target = Some(arg),
+        }
+    }
+
+    let target = target.ok_or_else(|| format!("缺少要分析的路徑\n{}", USAGE))?;
+    println!("🔍 Scanning {}", target);
+    let scan = crate::run_headless(&target, import, limits, checkpoint)?;
+    let mut tree = scan.tree.lock().map_err(|e| e.to_string())?;
+
+    let id = match node {
+        Some(node) => {
+            let path = tree.root_path().join(&node);
+            query::resolve_path(&mut tree, scan.spill_index(), &path).ok_or_else(|| format!("掃描結果中找不到: {}", node))?
+        }
+        None => ROOT_NODE,
+    };
+    let pending = render::render(&mut tree, scan.spill_index(), id, &output, &options)?;
+    drop(tree);
+    pending.finish()
+}
+
+// `--import` takes an optional format; anything else after it is left for the next option
+fn import_format(args: &mut std::iter::Peekable<impl Iterator<Item = String>>) -> ImportFormat {
+    let format = match args.peek().map(String::as_str) {
+        Some("ncdu") => ImportFormat::Ncdu,
+        Some("du") => ImportFormat::Du,
+        _ => return ImportFormat::Auto,
+    };
+    args.next();
+    format
+}
+
+fn number<T: std::str::FromStr>(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<T, String> {
+    let text = value(args, flag)?;
+    text.parse().map_err(|_| format!("{} 需要一個數字: {}", flag, text))
+}
+
+fn value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<String, String> {
+    args.next().ok_or_else(|| format!("{} 需要一個值", flag))
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Arc, Mutex};
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt;
+
+// Spinning disks get a single reader so directory reads stay sequential
+const ROTATIONAL_THREADS: usize = 1;
+
+#[derive(Clone, Copy, Debug)]
+pub struct DeviceProfile {
+    pub rotational: bool,
+    pub threads: usize,
+}
+
+// A dedicated I/O pool for one physical device
+pub struct DevicePool {
+    pub profile: DeviceProfile,
+    pool: rayon::ThreadPool,
+}
+
+impl DevicePool {
+    // Queue work on this device's pool; from one of its own threads it runs before older work
+    pub fn spawn(&self, op: impl FnOnce() + Send + 'static) {
+        self.pool.spawn(op)
+    }
+}
+
+// Lazily created pools, one per backing device seen during a scan
+#[derive(Default)]
+pub struct DevicePools {
+    pools: Mutex<HashMap<u64, Arc<DevicePool>>>,
+    low_priority: bool, // Scan threads run with lowered CPU and I/O priority
+}
+
+impl DevicePools {
+    pub fn with_low_priority(low_priority: bool) -> Self {
+        Self { low_priority, ..Self::default() }
+    }
+
+    pub fn for_metadata(&self, metadata: &fs::Metadata) -> Arc<DevicePool> {
+        self.for_device(device_id(metadata))
+    }
+
+    pub fn for_device(&self, id: u64) -> Arc<DevicePool> {
+        let mut pools = self.pools.lock().unwrap();
+        if let Some(pool) = pools.get(&id) {
+            return pool.clone();
+        }
+
+        let profile = detect_profile(id);
+        println!("💽 Device {}: rotational={}, threads={}", format_device_id(id), profile.rotational, profile.threads);
+
+        let mut builder = rayon::ThreadPoolBuilder::new()
+            .num_threads(profile.threads)
+            .thread_name(move |i| format!("scan-dev{}-{}", id, i));
+        if self.low_priority {
+            builder = builder.start_handler(|_| crate::limits::lower_thread_priority());
+        }
+        let pool = builder
+            .build()
+            .unwrap_or_else(|_| rayon::ThreadPoolBuilder::new().build().expect("failed to build scan pool"));
+
+        let pool = Arc::new(DevicePool { profile, pool });
+        pools.insert(id, pool.clone());
+        pool
+    }
+}
+
+#[cfg(unix)]
+pub fn device_id(metadata: &fs::Metadata) -> u64 {
+    metadata.dev()
+}
+
+#[cfg(not(unix))]
+pub fn device_id(_metadata: &fs::Metadata) -> u64 {
+    0
+}
+
+fn detect_profile(id: u64) -> DeviceProfile {
+    let rotational = is_rotational(id);
+    let threads = if rotational {
+        ROTATIONAL_THREADS
+    } else {
+        std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4)
+    };
+
+    DeviceProfile { rotational, threads }
+}
+
+// Linux encodes dev_t as glibc does: 12-bit major and 20-bit minor split across the word
+#[cfg(target_os = "linux")]
+fn split_device_id(id: u64) -> (u64, u64) {
+    let major = ((id >> 8) & 0xfff) | ((id >> 32) & !0xfff);
+    let minor = (id & 0xff) | ((id >> 12) & !0xff);
+    (major, minor)
+}
+
+#[cfg(target_os = "linux")]
+fn format_device_id(id: u64) -> String {
+    let (major, minor) = split_device_id(id);
+    format!("{}:{}", major, minor)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn format_device_id(id: u64) -> String {
+    id.to_string()
+}
+
+#[cfg(target_os = "linux")]
+fn is_rotational(id: u64) -> bool {
+    let (major, minor) = split_device_id(id);
+    let base = std::path::PathBuf::from(format!("/sys/dev/block/{}:{}", major, minor));
+
+    // Partitions have no queue directory of their own; the parent disk does
+    for candidate in [base.join("queue/rotational"), base.join("../queue/rotational")] {
+        if let Ok(value) = fs::read_to_string(&candidate) {
+            return value.trim() == "1";
+        }
+    }
+
+    false
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_rotational(_id: u64) -> bool {
+    false
+}
+
+// Space and inodes in use on the filesystem holding a path, straight from statvfs
+#[derive(Clone, Copy, Debug)]
+pub struct VolumeUsage {
+    pub used_bytes: u64,
+    pub used_inodes: u64,
+}
+
+#[cfg(unix)]
+pub fn volume_usage(path: &std::path::Path) -> Option<VolumeUsage> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+
+    // SAFETY: c_path is NUL-terminated and stats is a live local of the right type
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stats) } != 0 {
+        return None;
+    }
+
+    let block_size = stats.f_frsize as u64;
+    Some(VolumeUsage {
+        used_bytes: (stats.f_blocks as u64).saturating_sub(stats.f_bfree as u64) * block_size,
+        used_inodes: (stats.f_files as u64).saturating_sub(stats.f_ffree as u64),
+    })
+}
+
+#[cfg(not(unix))]
+pub fn volume_usage(_path: &std::path::Path) -> Option<VolumeUsage> {
+    None
+}
+
+// A directory is a mount point when it sits on a different device than its parent
+pub fn is_mount_point(path: &std::path::Path) -> bool {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return false;
+    };
+    match path.parent().and_then(|parent| fs::metadata(parent).ok()) {
+        Some(parent) => device_id(&parent) != device_id(&metadata),
+        None => true, // The filesystem root
+    }
+}
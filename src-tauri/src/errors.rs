@@ -0,0 +1,32 @@
+use serde::Serialize;
+
+// Entries listed in an error log; the total keeps counting past it
+const MAX_LISTED_ERRORS: usize = 1000;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ScanError {
+    pub path: String,
+    pub message: String,
+}
+
+// Paths that couldn't be read during a scan (permissions, vanished files, ...)
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ErrorLog {
+    pub total: u64,
+    pub entries: Vec<ScanError>,
+}
+
+impl ErrorLog {
+    // False once the listing is full and only the total grows
+    pub fn record(&mut self, path: &str, message: impl ToString) -> bool {
+        self.total += 1;
+        if self.entries.len() >= MAX_LISTED_ERRORS {
+            return false;
+        }
+        self.entries.push(ScanError {
+            path: path.to_string(),
+            message: message.to_string(),
+        });
+        true
+    }
+}
@@ -0,0 +1,198 @@
+// Fast-estimate mode: directories with very many entries are scanned from a random sample and
+// their size and entry count extrapolated. Each directory carries the variance of its estimate,
+// so ancestors can combine them into 95% confidence intervals.
+
+use serde::{Deserialize, Serialize};
+
+const Z_95: f64 = 1.96;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EstimateOptions {
+    #[serde(default = "default_threshold")]
+    pub threshold: usize, // Directories with more entries than this are sampled
+    #[serde(default = "default_sample_size")]
+    pub sample_size: usize, // Entries scanned in a sampled directory
+    #[serde(default)]
+    pub refine: bool, // Rescan the skipped entries afterwards for exact values
+}
+
+fn default_threshold() -> usize {
+    2000
+}
+
+fn default_sample_size() -> usize {
+    500
+}
+
+// Statistics kept per directory in estimate mode
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Spread {
+    pub estimated: bool, // Sampled itself or somewhere below
+    pub entries: f64, // Entries below the directory, extrapolated
+    pub size_variance: f64,
+    pub entries_variance: f64,
+}
+
+// An extrapolated size and entry count with their 95% confidence intervals
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Estimate {
+    pub size: u64,
+    pub size_low: u64,
+    pub size_high: u64,
+    pub entries: u64,
+    pub entries_low: u64,
+    pub entries_high: u64,
+}
+
+impl Estimate {
+    pub fn new(size: u64, spread: &Spread) -> Self {
+        let size_margin = Z_95 * spread.size_variance.sqrt();
+        let entries_margin = Z_95 * spread.entries_variance.sqrt();
+        Self {
+            size,
+            size_low: (size as f64 - size_margin).max(0.0) as u64,
+            size_high: (size as f64 + size_margin) as u64,
+            entries: spread.entries.round() as u64,
+            entries_low: (spread.entries - entries_margin).max(0.0) as u64,
+            entries_high: (spread.entries + entries_margin).round() as u64,
+        }
+    }
+}
+
+// One scanned child of a directory: its size and spread (default for files)
+pub struct ChildStats {
+    pub size: u64,
+    pub apparent_size: u64,
+    pub spread: Spread,
+}
+
+// Combine the scanned children of a directory; `sampling` is (entries listed, entries scanned)
+// for a sampled directory. Returns the size, apparent size and spread of the directory.
+pub fn combine(children: &[ChildStats], sampling: Option<(usize, usize)>) -> (u64, u64, Spread) {
+    let size: f64 = children.iter().map(|child| child.size as f64).sum();
+    let apparent_size: f64 = children.iter().map(|child| child.apparent_size as f64).sum();
+    let entries: f64 = children.iter().map(|child| 1.0 + child.spread.entries).sum();
+    let size_variance: f64 = children.iter().map(|child| child.spread.size_variance).sum();
+    let entries_variance: f64 = children.iter().map(|child| child.spread.entries_variance).sum();
+    let estimated = children.iter().any(|child| child.spread.estimated);
+
+    let Some((total, scanned)) = sampling.filter(|&(total, scanned)| scanned > 0 && scanned < total) else {
+        return (size as u64, apparent_size as u64, Spread { estimated, entries, size_variance, entries_variance });
+    };
+
+    // Expand the sample to the whole directory: the children's own uncertainty scales with the
+    // expansion, plus the sampling variance of a total with the finite population correction
+    let (n, k) = (total as f64, scanned as f64);
+    let scale = n / k;
+    let correction = n * n * (1.0 - k / n) / k;
+    let size_sample_variance = sample_variance(children.iter().map(|child| child.size as f64));
+    let entries_sample_variance = sample_variance(children.iter().map(|child| child.spread.entries));
+
+    let spread = Spread {
+        estimated: true,
+        // Entries directly inside are known from the listing; only what's below them is extrapolated
+        entries: n + scale * (entries - k),
+        size_variance: scale * scale * size_variance + correction * size_sample_variance,
+        entries_variance: scale * scale * entries_variance + correction * entries_sample_variance,
+    };
+    ((size * scale) as u64, (apparent_size * scale) as u64, spread)
+}
+
+fn sample_variance(values: impl Iterator<Item = f64> + Clone) -> f64 {
+    let count = values.clone().count();
+    if count < 2 {
+        return 0.0;
+    }
+    let mean = values.clone().sum::<f64>() / count as f64;
+    values.map(|value| (value - mean) * (value - mean)).sum::<f64>() / (count - 1) as f64
+}
+
+// Pick `count` of `len` indices uniformly (partial Fisher-Yates with a seeded xorshift, so
+// rescanning the same directory picks the same sample)
+pub fn sample_indices(len: usize, count: usize, seed: u64) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..len).collect();
+    let mut state = seed | 1;
+    for i in 0..count.min(len) {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let j = i + (state % (len - i) as u64) as usize;
+        indices.swap(i, j);
+    }
+    indices.truncate(count.min(len));
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(size: u64) -> ChildStats {
+        ChildStats { size, apparent_size: size / 2, spread: Spread::default() }
+    }
+
+    #[test]
+    fn unsampled_directories_are_exact() {
+        let children = [file(100), file(300)];
+        for sampling in [None, Some((2, 2))] {
+            let (size, apparent_size, spread) = combine(&children, sampling);
+            assert_eq!((size, apparent_size), (400, 200));
+            assert!(!spread.estimated);
+            assert_eq!(spread.entries, 2.0);
+            assert_eq!((spread.size_variance, spread.entries_variance), (0.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn samples_expand_to_the_whole_directory() {
+        // 4 of 10 files: mean 250, sample variance 50000 / 3
+        let children = [file(100), file(200), file(300), file(400)];
+        let (size, apparent_size, spread) = combine(&children, Some((10, 4)));
+        assert_eq!((size, apparent_size), (2500, 1250));
+        assert!(spread.estimated);
+        assert_eq!(spread.entries, 10.0);
+        let expected = 10.0 * 10.0 * (1.0 - 0.4) / 4.0 * (50000.0 / 3.0);
+        assert!((spread.size_variance - expected).abs() < 1e-6);
+
+        let estimate = Estimate::new(size, &spread);
+        assert!(estimate.size_low < 2500 && estimate.size_high > 2500);
+        assert_eq!((estimate.entries, estimate.entries_low, estimate.entries_high), (10, 10, 10));
+    }
+
+    #[test]
+    fn intervals_stay_above_zero() {
+        let spread = Spread { estimated: true, entries: 1.0, size_variance: 1e6, entries_variance: 100.0 };
+        let estimate = Estimate::new(10, &spread);
+        assert_eq!((estimate.size_low, estimate.entries_low), (0, 0));
+        assert_eq!(estimate.size_high, 10 + 1960);
+    }
+
+    #[test]
+    fn sample_indices_are_distinct_and_repeatable() {
+        let sample = sample_indices(1000, 50, 42);
+        assert_eq!(sample, sample_indices(1000, 50, 42));
+        let mut sorted = sample.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert_eq!(sorted.len(), 50);
+        assert!(sorted.iter().all(|&index| index < 1000));
+        assert_eq!(sample_indices(5, 50, 42).len(), 5);
+    }
+
+    #[test]
+    fn intervals_cover_the_true_size_about_95_percent_of_the_time() {
+        let sizes: Vec<u64> = (0..2000u64).map(|i| (i * 7919 % 1000 + 1) * 4096).collect();
+        let truth: u64 = sizes.iter().sum();
+        let trials = 400;
+        let covered = (0..trials)
+            .filter(|&seed| {
+                let children: Vec<ChildStats> = sample_indices(sizes.len(), 200, seed * 2654435761 + 1).into_iter().map(|index| file(sizes[index])).collect();
+                let (size, _, spread) = combine(&children, Some((sizes.len(), children.len())));
+                let estimate = Estimate::new(size, &spread);
+                estimate.size_low <= truth && truth <= estimate.size_high
+            })
+            .count();
+        let coverage = covered as f64 / trials as f64;
+        assert!((0.9..=0.99).contains(&coverage), "coverage {}", coverage);
+    }
+}
@@ -0,0 +1,464 @@
+// Export the retained scan tree to nested JSON, flat CSV or the ncdu JSON format.
+// Entries are written while walking the tree (spilled subtrees are read back from the
+// index one record at a time), so memory use doesn't grow with the size of the export.
+
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::classify;
+use crate::filter::with_child_path;
+use crate::fold;
+use crate::osname;
+use crate::owner;
+use crate::spill::{IndexReader, SpillEntry};
+use crate::tree::{EntryMeta, NodeId, ScanTree, ROOT_NODE};
+
+const EXPORT_VERSION: u32 = 1;
+const WRITE_BUFFER: usize = 1 << 20;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+    Ncdu,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Redaction {
+    #[default]
+    None,
+    Root,  // Hide the scanned path; everything is relative to "."
+    Names, // Also replace every name with a stable hash, keeping the extension
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ExportOptions {
+    #[serde(default)]
+    pub format: ExportFormat,
+    #[serde(default)]
+    pub max_depth: Option<usize>, // Deeper entries are folded into their ancestor at this depth
+    #[serde(default)]
+    pub min_size: u64, // Smaller entries are left out; their bytes still count towards the parent, and ncdu gets one entry per directory for them
+    #[serde(default)]
+    pub redact: Redaction,
+    #[serde(default)]
+    pub node_id: Option<NodeId>, // Export only this subtree
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportSummary {
+    pub path: String,
+    pub entries: u64, // Entries written to the file
+    pub bytes: u64,   // Size of the written file
+}
+
+// One entry being exported, in memory or still in the spill index
+struct Visit<'a> {
+    id: Option<NodeId>,
+    name: &'a str,
+    size: u64,
+    is_directory: bool,
+    meta: EntryMeta,
+    spill_offset: Option<u64>,
+}
+
+// Children of a written directory that were left out for being below min_size
+#[derive(Default)]
+struct Dropped {
+    count: u64,
+    size: u64,
+    apparent_size: u64,
+}
+
+impl Dropped {
+    fn add(&mut self, size: u64, apparent_size: u64) {
+        self.count += 1;
+        self.size += size;
+        self.apparent_size += apparent_size;
+    }
+}
+
+struct ExportWalk<'a, W: Write> {
+    tree: &'a ScanTree,
+    index: Option<IndexReader>,
+    options: &'a ExportOptions,
+    out: W,
+    owners: HashMap<u32, String>,
+    entries: u64,
+}
+
+impl<W: Write> ExportWalk<'_, W> {
+    // Returns the number of entries in the subtree and whether this entry was written.
+    // Subtrees are always walked in full so directory counts stay exact below the depth limit.
+    fn visit(&mut self, entry: Visit, path: &mut String, depth: usize, emit: bool, separator: bool) -> Result<(u64, bool), String> {
+        let emit = emit && (depth == 0 || entry.size >= self.options.min_size);
+        let expand = emit && entry.is_directory && self.options.max_depth.is_none_or(|max| depth < max);
+
+        if emit {
+            self.open(&entry, expand, separator).map_err(|e| e.to_string())?;
+        }
+
+        let mut count = 1;
+        if entry.is_directory {
+            // In ncdu arrays the directory's own info object comes before the first child
+            let mut wrote_child = self.options.format == ExportFormat::Ncdu;
+            let mut dropped = Dropped::default();
+            if let Some(offset) = entry.spill_offset {
+                for child in self.read_spilled(offset)? {
+                    let (child_count, written) = self.visit_spilled(&child, path, depth + 1, expand, wrote_child)?;
+                    count += child_count;
+                    wrote_child |= written;
+                    if expand && !written {
+                        dropped.add(child.size, child.apparent_size);
+                    }
+                }
+            } else if let Some(id) = entry.id {
+                let tree = self.tree;
+                for child in tree.children(id) {
+                    let (child_count, written) = self.visit_node(child, path, depth + 1, expand, wrote_child)?;
+                    count += child_count;
+                    wrote_child |= written;
+                    if expand && !written {
+                        let node = tree.node(child);
+                        dropped.add(node.size, node.meta.apparent_size);
+                    }
+                }
+            }
+            if dropped.count > 0 {
+                self.write_dropped(&dropped).map_err(|e| e.to_string())?;
+            }
+        }
+
+        if emit {
+            self.close(&entry, path, expand, count).map_err(|e| e.to_string())?;
+            self.entries += 1;
+        }
+        Ok((count, emit))
+    }
+
+    fn visit_node(&mut self, id: NodeId, path: &mut String, depth: usize, emit: bool, separator: bool) -> Result<(u64, bool), String> {
+        let tree = self.tree;
+        let node = tree.node(id);
+        let name = self.shown_name(tree.name(id));
+        let visit = Visit {
+            id: Some(id),
+            name: &name,
+            size: node.size,
+            is_directory: node.is_directory,
+            meta: node.meta,
+            spill_offset: tree.spilled_offset(id),
+        };
+        with_child_path(path, &name, |path| self.visit(visit, path, depth, emit, separator))
+    }
+
+    fn visit_spilled(&mut self, entry: &SpillEntry, path: &mut String, depth: usize, emit: bool, separator: bool) -> Result<(u64, bool), String> {
+        let name = self.shown_name(&entry.name);
+        let visit = Visit {
+            id: None,
+            name: &name,
+            size: entry.size,
+            is_directory: entry.is_directory,
+            meta: entry.meta(),
+            spill_offset: entry.offset,
+        };
+        with_child_path(path, &name, |path| self.visit(visit, path, depth, emit, separator))
+    }
+
+    fn read_spilled(&mut self, offset: u64) -> Result<Vec<SpillEntry>, String> {
+        let index = self.index.as_mut().ok_or("Spill index not available")?;
+        index.read(offset).map_err(|e| e.to_string())
+    }
+
+    fn shown_name(&self, name: &str) -> String {
+        if self.options.redact != Redaction::Names {
+            return name.to_string();
+        }
+        let mut hasher = DefaultHasher::new();
+        name.hash(&mut hasher);
+        match classify::extension_of(name) {
+            Some(extension) => format!("{:016x}.{}", hasher.finish(), extension),
+            None => format!("{:016x}", hasher.finish()),
+        }
+    }
+
+    fn owner(&mut self, uid: u32) -> &str {
+        self.owners.entry(uid).or_insert_with(|| owner::name_for_uid(uid))
+    }
+
+    // Everything about an entry that is known before its children are walked
+    fn open(&mut self, entry: &Visit, expand: bool, separator: bool) -> io::Result<()> {
+        match self.options.format {
+            ExportFormat::Csv => Ok(()),
+            ExportFormat::Json => {
+                let owner = self.owner(entry.meta.uid).to_string();
+                if separator {
+                    self.out.write_all(b",")?;
+                }
+                write!(self.out, "{{\"name\":{},\"size\":{},\"apparent_size\":{},\"mtime\":{},\"owner\":{},\"is_directory\":{}",
+                    json_string(entry.name), entry.size, entry.meta.apparent_size, entry.meta.mtime, json_string(&owner), entry.is_directory)?;
+                if expand {
+                    self.out.write_all(b",\"children\":[")?;
+                }
+                Ok(())
+            }
+            ExportFormat::Ncdu => {
+                if separator {
+                    self.out.write_all(b",")?;
+                }
+                if entry.is_directory {
+                    self.out.write_all(b"[")?;
+                }
+                // Like ncdu itself, names are written as the OS bytes, valid UTF-8 or not
+                self.out.write_all(b"{\"name\":")?;
+                write_json_bytes(&mut self.out, &osname::to_bytes(entry.name))?;
+                write!(self.out, ",\"mtime\":{},\"uid\":{}", entry.meta.mtime, entry.meta.uid)?;
+                // ncdu sums directories itself, so only files and folded directories carry sizes
+                if !expand {
+                    write!(self.out, ",\"asize\":{},\"dsize\":{}", entry.meta.apparent_size, entry.size)?;
+                }
+                self.out.write_all(b"}")
+            }
+        }
+    }
+
+    // ncdu sums a directory from its children, so the ones left out are written as one entry
+    fn write_dropped(&mut self, dropped: &Dropped) -> io::Result<()> {
+        if self.options.format != ExportFormat::Ncdu {
+            return Ok(());
+        }
+        write!(self.out, ",{{\"name\":{},\"asize\":{},\"dsize\":{}}}",
+            json_string(&fold::folded_label(dropped.count)), dropped.apparent_size, dropped.size)
+    }
+
+    // Everything that needs the finished walk of the children
+    fn close(&mut self, entry: &Visit, path: &str, expand: bool, count: u64) -> io::Result<()> {
+        match self.options.format {
+            ExportFormat::Csv => {
+                let mtime = format_timestamp(entry.meta.mtime);
+                let owner = self.owner(entry.meta.uid).to_string();
+                writeln!(self.out, "{},{},{},{},{},{}", csv_field(path), entry.size, entry.meta.apparent_size, count, mtime, csv_field(&owner))
+            }
+            ExportFormat::Json => {
+                if expand {
+                    self.out.write_all(b"]")?;
+                }
+                if entry.is_directory {
+                    write!(self.out, ",\"count\":{}", count)?;
+                }
+                self.out.write_all(b"}")
+            }
+            ExportFormat::Ncdu => {
+                if entry.is_directory {
+                    self.out.write_all(b"]")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn json_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+// A JSON string of raw bytes: only quotes, backslashes and control characters are escaped
+fn write_json_bytes(out: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    out.write_all(b"\"")?;
+    for &byte in bytes {
+        match byte {
+            b'"' => out.write_all(b"\\\"")?,
+            b'\\' => out.write_all(b"\\\\")?,
+            0..=0x1f => write!(out, "\\u{:04x}", byte)?,
+            _ => out.write_all(&[byte])?,
+        }
+    }
+    out.write_all(b"\"")
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// RFC 3339 in UTC; empty when the time is unknown
+fn format_timestamp(seconds: i64) -> String {
+    if seconds == 0 {
+        return String::new();
+    }
+
+    // Civil date from days (Howard Hinnant's algorithm)
+    let days = seconds.div_euclid(86400);
+    let time = seconds.rem_euclid(86400);
+    let z = days + 719468;
+    let era = if z >= 0 { z } else { z - 146096 } / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, time / 3600, time / 60 % 60, time % 60)
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// An export whose entries have all been written; finish() flushes and renames it, and needs
+// no access to the tree
+pub struct PendingExport {
+    out: BufWriter<File>,
+    partial: PathBuf,
+    destination: PathBuf,
+    entries: u64,
+}
+
+impl PendingExport {
+    pub fn finish(self) -> Result<ExportSummary, String> {
+        let PendingExport { mut out, partial, destination, entries } = self;
+        if let Err(e) = out.flush() {
+            let _ = fs::remove_file(&partial);
+            return Err(e.to_string());
+        }
+        drop(out);
+
+        fs::rename(&partial, &destination).map_err(|e| e.to_string())?;
+        let bytes = fs::metadata(&destination).map(|m| m.len()).unwrap_or(0);
+        println!("📤 Exported {} entries to {} ({} bytes)", entries, destination.display(), bytes);
+
+        Ok(ExportSummary {
+            path: destination.to_string_lossy().to_string(),
+            entries,
+            bytes,
+        })
+    }
+}
+
+// Write the tree (or one subtree) to `destination`. The file is written under a temporary
+// name and renamed by finish(), so an interrupted export never looks like a finished one.
+pub fn export(tree: &ScanTree, index_path: Option<&Path>, destination: &Path, options: &ExportOptions) -> Result<PendingExport, String> {
+    let start = options.node_id.unwrap_or(ROOT_NODE);
+    if !tree.contains(start) {
+        return Err(format!("Unknown node: {}", start));
+    }
+    let index = match index_path {
+        Some(index_path) => Some(IndexReader::open(index_path).map_err(|e| e.to_string())?),
+        None => None,
+    };
+
+    let mut partial = destination.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+    let file = File::create(&partial).map_err(|e| format!("無法建立匯出檔 {}: {}", partial.display(), e))?;
+
+    let mut walk = ExportWalk {
+        tree,
+        index,
+        options,
+        out: BufWriter::with_capacity(WRITE_BUFFER, file),
+        owners: HashMap::new(),
+        entries: 0,
+    };
+
+    if let Err(e) = write_export(&mut walk, start) {
+        drop(walk.out);
+        let _ = fs::remove_file(&partial);
+        return Err(e);
+    }
+
+    Ok(PendingExport {
+        out: walk.out,
+        partial,
+        destination: destination.to_path_buf(),
+        entries: walk.entries,
+    })
+}
+
+fn write_export<W: Write>(walk: &mut ExportWalk<W>, start: NodeId) -> Result<(), String> {
+    let tree = walk.tree;
+    let mut path = match walk.options.redact {
+        Redaction::None => osname::encode_path(&tree.path(start)),
+        Redaction::Root | Redaction::Names => ".".to_string(),
+    };
+    // ncdu wants the full path as the root's name
+    let name = match walk.options.format {
+        ExportFormat::Ncdu => path.clone(),
+        _ if walk.options.redact == Redaction::None => tree.name(start).to_string(),
+        _ => ".".to_string(),
+    };
+    let node = tree.node(start);
+    let root = Visit {
+        id: Some(start),
+        name: &name,
+        size: node.size,
+        is_directory: node.is_directory,
+        meta: node.meta,
+        spill_offset: tree.spilled_offset(start),
+    };
+
+    let io = |e: io::Error| e.to_string();
+    match walk.options.format {
+        ExportFormat::Json => {
+            write!(walk.out, "{{\"format\":\"storviz\",\"version\":{},\"root\":{},\"exported_at\":{},\"tree\":",
+                EXPORT_VERSION, json_string(&path), unix_now()).map_err(io)?;
+            walk.visit(root, &mut path, 0, true, false)?;
+            walk.out.write_all(b"}\n").map_err(io)
+        }
+        ExportFormat::Csv => {
+            walk.out.write_all(b"path,size,apparent_size,count,mtime,owner\n").map_err(io)?;
+            walk.visit(root, &mut path, 0, true, false).map(|_| ())
+        }
+        ExportFormat::Ncdu => {
+            write!(walk.out, "[1,2,{{\"progname\":\"storviz\",\"progver\":{},\"timestamp\":{}}},",
+                json_string(env!("CARGO_PKG_VERSION")), unix_now()).map_err(io)?;
+            walk.visit(root, &mut path, 0, true, false)?;
+            walk.out.write_all(b"]\n").map_err(io)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ncdu_keeps_the_bytes_of_entries_below_min_size() {
+        let mut tree = ScanTree::new("/scan");
+        let root = tree.add_node(crate::tree::NO_NODE, "scan", true, 110, EntryMeta::default()).unwrap();
+        for (name, size) in [("big", 100), ("small", 4), ("tiny", 6)] {
+            let meta = EntryMeta { apparent_size: size, ..EntryMeta::default() };
+            tree.add_node(root, name, false, size, meta).unwrap();
+        }
+
+        let destination = std::env::temp_dir().join(format!("storviz-test-{}.ncdu", std::process::id()));
+        let options = ExportOptions { format: ExportFormat::Ncdu, min_size: 50, ..ExportOptions::default() };
+        let summary = export(&tree, None, &destination, &options).unwrap().finish().unwrap();
+        let text = fs::read_to_string(&destination).unwrap();
+        let _ = fs::remove_file(&destination);
+
+        assert_eq!(summary.entries, 2);
+        let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+        let listing = value[3].as_array().unwrap();
+        assert_eq!(listing.len(), 3);
+        assert_eq!(listing[1]["dsize"], 100);
+        assert_eq!(listing[2]["name"], fold::folded_label(2));
+        assert_eq!(listing[2]["dsize"], 10);
+        assert_eq!(listing[2]["asize"], 10);
+    }
+}
@@ -0,0 +1,700 @@
+// Filter expressions over the retained scan tree, e.g.
+//   size>1G ext:mkv,mp4 mtime<2024-01-01 owner:alice path:*/cache/* category:video
+// Terms are separated by spaces and all have to match; a leading '-' negates a term.
+// Values with spaces are quoted, e.g. name:"My Notes.txt" or path:'*/Program Files/*'.
+// Only files are matched unless a `type:dir` or `type:any` term says otherwise.
+
+use serde::Serialize;
+use std::path::Path;
+
+use crate::classify::Category;
+use crate::osname;
+use crate::owner;
+use crate::spill::{IndexReader, SpillEntry};
+use crate::tree::{Children, EntryMeta, NodeId, ScanTree, ROOT_NODE};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    fn test<T: PartialOrd>(self, value: T, bound: T) -> bool {
+        match self {
+            Comparison::Less => value < bound,
+            Comparison::LessOrEqual => value <= bound,
+            Comparison::Equal => value == bound,
+            Comparison::GreaterOrEqual => value >= bound,
+            Comparison::Greater => value > bound,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum EntryType {
+    File,
+    Directory,
+    Any,
+}
+
+#[derive(Clone, Debug)]
+enum Predicate {
+    Size(Comparison, u64),
+    Mtime(Comparison, i64),
+    Extension(Vec<String>),
+    Owner(u32),
+    Path(String),
+    Name(String),
+    Category(Category),
+}
+
+#[derive(Clone, Debug)]
+struct Term {
+    negated: bool,
+    predicate: Predicate,
+}
+
+// The attributes a filter looks at, whether the entry is in memory or in the spill index
+pub struct EntryRef<'a> {
+    pub name: &'a str,
+    pub path: &'a str,
+    pub size: u64,
+    pub is_directory: bool,
+    pub meta: EntryMeta,
+}
+
+#[derive(Clone, Debug)]
+pub struct Filter {
+    terms: Vec<Term>,
+    entry_type: EntryType,
+}
+
+impl Filter {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let mut terms = Vec::new();
+        let mut entry_type = EntryType::File;
+
+        for token in tokenize(expression)? {
+            let token = token.as_str();
+            let (negated, token) = match token.strip_prefix('-') {
+                Some(rest) => (true, rest),
+                None => (false, token),
+            };
+
+            let split = token
+                .find([':', '<', '>', '='])
+                .ok_or_else(|| format!("Invalid filter term: {}", token))?;
+            let (key, rest) = token.split_at(split);
+
+            let predicate = match key.to_ascii_lowercase().as_str() {
+                "size" => {
+                    let (comparison, value) = parse_comparison(rest)?;
+                    Predicate::Size(comparison, parse_size(value)?)
+                }
+                "mtime" => {
+                    let (comparison, value) = parse_comparison(rest)?;
+                    Predicate::Mtime(comparison, parse_date(value)?)
+                }
+                "ext" => Predicate::Extension(
+                    value_of(key, rest)?
+                        .split(',')
+                        .filter(|ext| !ext.is_empty())
+                        .map(|ext| ext.trim_start_matches('.').to_lowercase())
+                        .collect(),
+                ),
+                "owner" => {
+                    let name = value_of(key, rest)?;
+                    Predicate::Owner(owner::uid_for_name(name).ok_or_else(|| format!("Unknown owner: {}", name))?)
+                }
+                "path" => Predicate::Path(value_of(key, rest)?.to_string()),
+                "name" => Predicate::Name(value_of(key, rest)?.to_string()),
+                "category" | "cat" => {
+                    let name = value_of(key, rest)?;
+                    Predicate::Category(Category::parse(name).ok_or_else(|| format!("Unknown category: {}", name))?)
+                }
+                "type" => {
+                    entry_type = match value_of(key, rest)? {
+                        "file" | "f" => EntryType::File,
+                        "dir" | "d" | "directory" => EntryType::Directory,
+                        "any" | "all" => EntryType::Any,
+                        other => return Err(format!("Unknown type: {}", other)),
+                    };
+                    continue;
+                }
+                other => return Err(format!("Unknown filter key: {}", other)),
+            };
+
+            terms.push(Term { negated, predicate });
+        }
+
+        Ok(Self { terms, entry_type })
+    }
+
+    pub fn matches(&self, entry: &EntryRef) -> bool {
+        let type_matches = match self.entry_type {
+            EntryType::File => !entry.is_directory,
+            EntryType::Directory => entry.is_directory,
+            EntryType::Any => true,
+        };
+
+        type_matches && self.terms.iter().all(|term| term.negated != term.predicate.matches(entry))
+    }
+}
+
+impl Predicate {
+    fn matches(&self, entry: &EntryRef) -> bool {
+        match self {
+            Predicate::Size(comparison, bound) => comparison.test(entry.size, *bound),
+            Predicate::Mtime(comparison, bound) => comparison.test(entry.meta.mtime, *bound),
+            Predicate::Extension(extensions) => match entry.name.rsplit_once('.') {
+                Some((stem, ext)) if !stem.is_empty() => extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)),
+                _ => false,
+            },
+            Predicate::Owner(uid) => entry.meta.uid == *uid,
+            Predicate::Path(pattern) => glob_match(pattern, entry.path),
+            Predicate::Name(pattern) => glob_match(pattern, entry.name),
+            Predicate::Category(category) => !entry.is_directory && entry.meta.category == *category,
+        }
+    }
+}
+
+// Split on whitespace outside quotes and drop the quotes; inside double quotes a backslash
+// escapes the next character
+fn tokenize(expression: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut in_token = false;
+    let mut chars = expression.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' | '\'' => {
+                in_token = true;
+                loop {
+                    match chars.next() {
+                        Some(end) if end == c => break,
+                        Some('\\') if c == '"' => token.push(chars.next().ok_or("Unterminated quote in filter")?),
+                        Some(other) => token.push(other),
+                        None => return Err("Unterminated quote in filter".to_string()),
+                    }
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut token));
+                    in_token = false;
+                }
+            }
+            c => {
+                in_token = true;
+                token.push(c);
+            }
+        }
+    }
+    if in_token {
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn value_of<'a>(key: &str, rest: &'a str) -> Result<&'a str, String> {
+    rest.strip_prefix(':')
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| format!("Expected {}:<value>", key))
+}
+
+fn parse_comparison(rest: &str) -> Result<(Comparison, &str), String> {
+    let rest = rest.strip_prefix(':').unwrap_or(rest);
+    let (comparison, value) = if let Some(value) = rest.strip_prefix(">=") {
+        (Comparison::GreaterOrEqual, value)
+    } else if let Some(value) = rest.strip_prefix("<=") {
+        (Comparison::LessOrEqual, value)
+    } else if let Some(value) = rest.strip_prefix('>') {
+        (Comparison::Greater, value)
+    } else if let Some(value) = rest.strip_prefix('<') {
+        (Comparison::Less, value)
+    } else if let Some(value) = rest.strip_prefix('=') {
+        (Comparison::Equal, value)
+    } else {
+        (Comparison::Equal, rest)
+    };
+
+    if value.is_empty() {
+        return Err(format!("Missing value in '{}'", rest));
+    }
+    Ok((comparison, value))
+}
+
+// Sizes accept binary units: 512, 10K, 1.5G, 2TB, 4GiB
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let upper = value.to_ascii_uppercase();
+    let trimmed = upper.trim_end_matches('B').trim_end_matches('I');
+    let split = trimmed.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+
+    let multiplier: u64 = match unit {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        "P" => 1 << 50,
+        _ => return Err(format!("Invalid size: {}", value)),
+    };
+    let number: f64 = number.parse().map_err(|_| format!("Invalid size: {}", value))?;
+    Ok((number * multiplier as f64) as u64)
+}
+
+// Dates are YYYY-MM-DD in UTC, turned into seconds since the epoch
+pub fn parse_date(value: &str) -> Result<i64, String> {
+    let invalid = || format!("Invalid date (expected YYYY-MM-DD): {}", value);
+    let mut parts = value.splitn(3, '-');
+    let year: i64 = parts.next().and_then(|p| p.parse().ok()).ok_or_else(invalid)?;
+    let month: i64 = parts.next().and_then(|p| p.parse().ok()).ok_or_else(invalid)?;
+    let day: i64 = parts.next().and_then(|p| p.parse().ok()).ok_or_else(invalid)?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return Err(invalid());
+    }
+
+    // Days from civil date (Howard Hinnant's algorithm)
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    Ok(days * 86400)
+}
+
+// Shell-style wildcard match where '*' also crosses '/' and '?' matches one character
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FilterMatch {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<NodeId>,
+    pub path: String,
+    pub size: u64,
+    pub is_directory: bool,
+}
+
+// Filtered tree in the compact wire format; directory sizes are re-summed from matches only
+#[derive(Clone, Debug, Serialize)]
+pub struct FilteredNode {
+    #[serde(rename = "i", skip_serializing_if = "Option::is_none")]
+    pub id: Option<NodeId>,
+    #[serde(rename = "n")]
+    pub name: String,
+    #[serde(rename = "s")]
+    pub size: u64,
+    #[serde(rename = "c", skip_serializing_if = "Option::is_none")]
+    pub children: Option<Vec<FilteredNode>>,
+    #[serde(rename = "d")]
+    pub is_directory: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FilterResult {
+    pub matched_count: u64,
+    pub matched_size: u64,
+    pub matches: Vec<FilterMatch>,
+    pub truncated: bool, // More matches exist than were listed
+    pub tree: FilteredNode,
+}
+
+// Children of a directory still to be visited, from memory or from the spill index
+enum Pending<'a> {
+    Nodes(Children<'a>),
+    Spilled(std::vec::IntoIter<SpillEntry>),
+}
+
+// A directory on the walk's stack; its node is only built if it ends up holding listed matches
+struct Frame<'a> {
+    id: Option<NodeId>,
+    name: String,
+    path_length: usize, // Length of the path before this directory's name was appended
+    pending: Pending<'a>,
+    children: Vec<FilteredNode>,
+    size: u64, // Every match below, listed or not
+    matched: bool,
+    listed: bool, // Holds a listed match, so its node counts against the limit
+}
+
+// One entry being visited, in memory or still in the spill index
+struct Visit<'a> {
+    id: Option<NodeId>,
+    name: &'a str,
+    size: u64,
+    is_directory: bool,
+    meta: EntryMeta,
+    spill_offset: Option<u64>,
+}
+
+struct FilterWalk<'a> {
+    filter: &'a Filter,
+    tree: &'a ScanTree,
+    index: Option<IndexReader>,
+    limit: usize, // Nodes in the filtered tree, directories included
+    nodes: usize,
+    matched_count: u64,
+    matched_size: u64,
+    matches: Vec<FilterMatch>,
+    stack: Vec<Frame<'a>>,
+}
+
+impl<'a> FilterWalk<'a> {
+    // Visit one entry whose name is already on `path`. A match is taken whole, including
+    // everything below it; other directories are pushed to be walked.
+    fn visit(&mut self, entry: Visit, path: &mut String, path_length: usize) -> Result<(), String> {
+        let view = EntryRef {
+            name: entry.name,
+            path,
+            size: entry.size,
+            is_directory: entry.is_directory,
+            meta: entry.meta,
+        };
+
+        if self.filter.matches(&view) {
+            self.matched_count += 1;
+            self.matched_size += entry.size;
+            let node = self.list(&entry, path);
+            if let Some(parent) = self.stack.last_mut() {
+                parent.size += entry.size;
+                parent.matched = true;
+                parent.children.extend(node);
+            }
+            path.truncate(path_length);
+            return Ok(());
+        }
+
+        if !entry.is_directory {
+            path.truncate(path_length);
+            return Ok(());
+        }
+        let pending = if let Some(offset) = entry.spill_offset {
+            let index = self.index.as_mut().ok_or("Spill index not available")?;
+            Pending::Spilled(index.read(offset).map_err(|e| e.to_string())?.into_iter())
+        } else if let Some(id) = entry.id {
+            Pending::Nodes(self.tree.children(id))
+        } else {
+            Pending::Spilled(Vec::new().into_iter())
+        };
+        self.stack.push(Frame {
+            id: entry.id,
+            name: entry.name.to_string(),
+            path_length,
+            pending,
+            children: Vec::new(),
+            size: 0,
+            matched: false,
+            listed: false,
+        });
+        Ok(())
+    }
+
+    // Add a match to the list and build its node, if the limit leaves room for it and for the
+    // directories above it that aren't in the filtered tree yet
+    fn list(&mut self, entry: &Visit, path: &str) -> Option<FilteredNode> {
+        let unlisted = self.stack.iter().rev().take_while(|frame| !frame.listed).count();
+        if self.nodes + 1 + unlisted > self.limit {
+            return None;
+        }
+        self.nodes += 1 + unlisted;
+        for frame in self.stack.iter_mut().rev().take(unlisted) {
+            frame.listed = true;
+        }
+
+        self.matches.push(FilterMatch {
+            id: entry.id,
+            path: path.to_string(),
+            size: entry.size,
+            is_directory: entry.is_directory,
+        });
+        Some(FilteredNode {
+            id: entry.id,
+            name: entry.name.to_string(),
+            size: entry.size,
+            children: entry.is_directory.then(Vec::new),
+            is_directory: entry.is_directory,
+        })
+    }
+
+    // Depth-first over the stack; a finished directory folds its matches into its parent and
+    // returns the filtered tree once the root is done
+    fn run(&mut self, path: &mut String) -> Result<Option<FilteredNode>, String> {
+        loop {
+            let Some(frame) = self.stack.last_mut() else {
+                return Ok(None);
+            };
+            let path_length = path.len();
+            match &mut frame.pending {
+                Pending::Nodes(children) => {
+                    if let Some(child) = children.next() {
+                        let tree = self.tree;
+                        let node = tree.node(child);
+                        let visit = Visit {
+                            id: Some(child),
+                            name: tree.name(child),
+                            size: node.size,
+                            is_directory: node.is_directory,
+                            meta: node.meta,
+                            spill_offset: tree.spilled_offset(child),
+                        };
+                        push_child_path(path, visit.name);
+                        self.visit(visit, path, path_length)?;
+                        continue;
+                    }
+                }
+                Pending::Spilled(entries) => {
+                    if let Some(entry) = entries.next() {
+                        let visit = Visit {
+                            id: None,
+                            name: &entry.name,
+                            size: entry.size,
+                            is_directory: entry.is_directory,
+                            meta: entry.meta(),
+                            spill_offset: entry.offset,
+                        };
+                        push_child_path(path, &entry.name);
+                        self.visit(visit, path, path_length)?;
+                        continue;
+                    }
+                }
+            }
+
+            let frame = self.stack.pop().expect("frame checked above");
+            path.truncate(frame.path_length);
+            let node = frame.listed.then_some(FilteredNode {
+                id: frame.id,
+                name: frame.name,
+                size: frame.size,
+                children: Some(frame.children),
+                is_directory: true,
+            });
+            match self.stack.last_mut() {
+                Some(parent) => {
+                    if frame.matched {
+                        parent.size += frame.size;
+                        parent.matched = true;
+                    }
+                    parent.children.extend(node);
+                }
+                None => return Ok(node),
+            }
+        }
+    }
+}
+
+fn push_child_path(path: &mut String, name: &str) {
+    if !path.ends_with(std::path::MAIN_SEPARATOR) {
+        path.push(std::path::MAIN_SEPARATOR);
+    }
+    path.push_str(name);
+}
+
+pub fn with_child_path<R>(path: &mut String, name: &str, visit: impl FnOnce(&mut String) -> R) -> R {
+    let length = path.len();
+    push_child_path(path, name);
+    let result = visit(path);
+    path.truncate(length);
+    result
+}
+
+// Evaluate a filter over the whole tree, descending into spilled subtrees through the index.
+// At most `limit` nodes are built for the filtered tree and its match list; matches past that
+// still count towards the totals and directory sizes.
+pub fn run(tree: &ScanTree, index_path: Option<&Path>, filter: &Filter, limit: usize) -> Result<FilterResult, String> {
+    let index = match index_path {
+        Some(index_path) => Some(IndexReader::open(index_path).map_err(|e| e.to_string())?),
+        None => None,
+    };
+
+    let mut walk = FilterWalk {
+        filter,
+        tree,
+        index,
+        limit,
+        nodes: 0,
+        matched_count: 0,
+        matched_size: 0,
+        matches: Vec::new(),
+        stack: Vec::new(),
+    };
+
+    let root = tree.node(ROOT_NODE);
+    let mut path = osname::encode_path(tree.root_path());
+    let visit = Visit {
+        id: Some(ROOT_NODE),
+        name: tree.name(ROOT_NODE),
+        size: root.size,
+        is_directory: root.is_directory,
+        meta: root.meta,
+        spill_offset: tree.spilled_offset(ROOT_NODE),
+    };
+    let root_length = path.len();
+    walk.visit(visit, &mut path, root_length)?;
+    let filtered = if walk.stack.is_empty() {
+        // The root matched (or is a file that didn't): the list holds it if anything does
+        walk.matches.first().map(|_| FilteredNode {
+            id: Some(ROOT_NODE),
+            name: tree.name(ROOT_NODE).to_string(),
+            size: root.size,
+            children: root.is_directory.then(Vec::new),
+            is_directory: root.is_directory,
+        })
+    } else {
+        walk.run(&mut path)?
+    };
+    let filtered = filtered.unwrap_or_else(|| FilteredNode {
+        id: Some(ROOT_NODE),
+        name: tree.name(ROOT_NODE).to_string(),
+        size: walk.matched_size,
+        children: Some(Vec::new()),
+        is_directory: true,
+    });
+
+    Ok(FilterResult {
+        matched_count: walk.matched_count,
+        matched_size: walk.matched_size,
+        truncated: walk.matched_count > walk.matches.len() as u64,
+        matches: walk.matches,
+        tree: filtered,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::NO_NODE;
+
+    fn entry<'a>(name: &'a str, path: &'a str, size: u64) -> EntryRef<'a> {
+        EntryRef { name, path, size, is_directory: false, meta: EntryMeta::default() }
+    }
+
+    fn sample_tree() -> ScanTree {
+        let mut tree = ScanTree::new("/scan");
+        let add = |tree: &mut ScanTree, parent, name: &str, is_directory, size| {
+            tree.add_node(parent, name, is_directory, size, EntryMeta::default()).unwrap()
+        };
+        let root = add(&mut tree, NO_NODE, "scan", true, 0);
+        let docs = add(&mut tree, root, "My Documents", true, 0);
+        add(&mut tree, docs, "report final.pdf", false, 3000);
+        add(&mut tree, docs, "notes.txt", false, 10);
+        let media = add(&mut tree, root, "media", true, 0);
+        for i in 0..5 {
+            add(&mut tree, media, &format!("clip{}.mp4", i), false, 1000);
+        }
+        tree
+    }
+
+    #[test]
+    fn tokenize_handles_quotes_and_escapes() {
+        assert_eq!(tokenize(r#"size>1G  name:"a b.txt" -path:'*/x y/*'"#).unwrap(), ["size>1G", "name:a b.txt", "-path:*/x y/*"]);
+        assert_eq!(tokenize(r#"name:"say \"hi\"""#).unwrap(), [r#"name:say "hi""#]);
+        assert_eq!(tokenize(r#"name:'C:\dir'"#).unwrap(), [r"name:C:\dir"]);
+        assert_eq!(tokenize(r#"name:"""#).unwrap(), ["name:"]);
+        assert!(tokenize(r#"name:"open"#).is_err());
+    }
+
+    #[test]
+    fn parse_rejects_bad_terms() {
+        assert!(Filter::parse("bogus:1").is_err());
+        assert!(Filter::parse("size").is_err());
+        assert!(Filter::parse("size>").is_err());
+        assert!(Filter::parse("type:sock").is_err());
+        assert!(Filter::parse(r#"name:"""#).is_err());
+    }
+
+    #[test]
+    fn quoted_names_match_spaces() {
+        let filter = Filter::parse(r#"name:"report final.pdf""#).unwrap();
+        assert!(filter.matches(&entry("report final.pdf", "/scan/report final.pdf", 1)));
+        assert!(!filter.matches(&entry("report", "/scan/report", 1)));
+
+        let filter = Filter::parse("-ext:txt size>=1K").unwrap();
+        assert!(filter.matches(&entry("a.pdf", "/a.pdf", 2048)));
+        assert!(!filter.matches(&entry("a.txt", "/a.txt", 2048)));
+        assert!(!filter.matches(&entry("a.pdf", "/a.pdf", 10)));
+    }
+
+    #[test]
+    fn sizes_dates_and_globs() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("10K").unwrap(), 10 << 10);
+        assert_eq!(parse_size("1.5G").unwrap(), 3 << 29);
+        assert_eq!(parse_size("4GiB").unwrap(), 4 << 30);
+        assert_eq!(parse_size("2TB").unwrap(), 2 << 40);
+        assert!(parse_size("12X").is_err());
+
+        assert_eq!(parse_date("1970-01-02").unwrap(), 86400);
+        assert_eq!(parse_date("2024-03-01").unwrap() / 86400, 19783);
+        assert!(parse_date("2024-13-01").is_err());
+
+        assert!(glob_match("*/cache/*", "/home/u/cache/x"));
+        assert!(glob_match("a?c", "abc"));
+        assert!(!glob_match("a?c", "ac"));
+        assert!(glob_match("*.tar.*", "x.tar.gz"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_matches_paths_with_spaces() {
+        let tree = sample_tree();
+        let filter = Filter::parse(r#"path:"*/My Documents/*""#).unwrap();
+        let result = run(&tree, None, &filter, 100).unwrap();
+        assert_eq!(result.matched_count, 2);
+        assert_eq!(result.matched_size, 3010);
+        assert!(!result.truncated);
+        let docs = &result.tree.children.as_ref().unwrap()[0];
+        assert_eq!(docs.name, "My Documents");
+        assert_eq!(docs.children.as_ref().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn limit_caps_built_nodes_but_not_totals() {
+        let tree = sample_tree();
+        let filter = Filter::parse("ext:mp4").unwrap();
+        let result = run(&tree, None, &filter, 4).unwrap();
+        assert_eq!(result.matched_count, 5);
+        assert_eq!(result.matched_size, 5000);
+        assert!(result.truncated);
+        // Root and media take two of the four nodes
+        assert_eq!(result.matches.len(), 2);
+        assert_eq!(result.tree.size, 5000);
+        let media = &result.tree.children.as_ref().unwrap()[0];
+        assert_eq!(media.size, 5000);
+        assert_eq!(media.children.as_ref().unwrap().len(), 2);
+
+        let result = run(&tree, None, &filter, 0).unwrap();
+        assert!(result.matches.is_empty());
+        assert_eq!(result.tree.size, 5000);
+    }
+}
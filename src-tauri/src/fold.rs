@@ -0,0 +1,72 @@
+// Folding of tiny children: entries below a size threshold (absolute, or a fraction of their
+// directory) are sent as one synthetic "N smaller items" node per directory, carrying their total
+// size and count. Sorted by size, the folded entries are exactly the tail of the children list,
+// so the bucket can be expanded later with get_node_children at its offset and `bucket` set.
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+use crate::tree::{NodeId, ScanTree};
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct FoldOptions {
+    #[serde(default)]
+    pub min_size: u64, // Children smaller than this many bytes are folded
+    #[serde(default)]
+    pub min_fraction: f64, // ... or smaller than this fraction of their directory
+    #[serde(default = "default_min_items")]
+    pub min_items: usize, // Fewer qualifying children than this are left as they are
+}
+
+fn default_min_items() -> usize {
+    2
+}
+
+// The synthetic node standing in for a directory's folded children
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Folded {
+    pub parent: NodeId,
+    pub count: u64,
+    pub size: u64,
+    pub offset: usize, // Position of the first folded child when sorted by size
+}
+
+impl Folded {
+    pub fn label(&self) -> String {
+        folded_label(self.count)
+    }
+}
+
+pub fn folded_label(count: u64) -> String {
+    format!("{} 個較小項目", count)
+}
+
+impl FoldOptions {
+    fn threshold(&self, parent_size: u64) -> u64 {
+        self.min_size.max((parent_size as f64 * self.min_fraction) as u64)
+    }
+
+    // Children to send as they are (largest first) and the bucket for the rest, if any
+    pub fn split(&self, tree: &ScanTree, id: NodeId) -> (Vec<NodeId>, Option<Folded>) {
+        let mut children: Vec<NodeId> = tree.children(id).collect();
+        let threshold = self.threshold(tree.node(id).size);
+        if threshold == 0 {
+            return (children, None);
+        }
+
+        children.sort_by_key(|&child| Reverse(tree.node(child).size));
+        let kept = children.partition_point(|&child| tree.node(child).size >= threshold);
+        if children.len() - kept < self.min_items.max(1) {
+            return (children, None);
+        }
+
+        let folded = Folded {
+            parent: id,
+            count: (children.len() - kept) as u64,
+            size: children[kept..].iter().map(|&child| tree.node(child).size).sum(),
+            offset: kept,
+        };
+        children.truncate(kept);
+        (children, Some(folded))
+    }
+}
@@ -0,0 +1,433 @@
+// Import of `ncdu -o` exports and `du -ab` output. Entries are handed to the same sink
+// the live scan fills, so imported results stream to the UI and can be queried like a scan.
+// Both readers work incrementally, without loading the whole input into memory.
+
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+
+use crate::osname;
+use crate::tree::{EntryMeta, NodeId, NO_NODE};
+
+// Enough of the end of a `du` listing to hold its last line (the scanned root)
+const DU_TAIL_BYTES: u64 = 64 * 1024;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    #[default]
+    Auto,
+    Ncdu,
+    Du,
+}
+
+pub trait ImportSink {
+    // `parent` is NO_NODE for the root, whose name is the full path that was scanned.
+    // Names come in the escaped form of osname, like the names of a live scan.
+    fn add_entry(&mut self, parent: NodeId, name: &str, is_directory: bool, size: u64, meta: EntryMeta) -> Result<NodeId, String>;
+    // Called once all children are in; the directory's own allocation comes on top of theirs
+    fn finish_directory(&mut self, id: NodeId, depth: usize, own_size: u64, own_apparent_size: u64);
+    fn is_cancelled(&self) -> bool;
+}
+
+// Read an import file into the sink and return the root node
+pub fn import(path: &Path, format: ImportFormat, sink: &mut impl ImportSink) -> Result<NodeId, String> {
+    let file = File::open(path).map_err(|e| format!("無法開啟 {}: {}", path.display(), e))?;
+    let mut reader = BufReader::new(file);
+
+    let format = match format {
+        ImportFormat::Auto => detect(&mut reader).map_err(|e| e.to_string())?,
+        format => format,
+    };
+    match format {
+        ImportFormat::Ncdu => import_ncdu(reader, sink),
+        _ => import_du(path, reader, sink),
+    }
+}
+
+// ncdu exports are a JSON array; du output starts with a size
+fn detect(reader: &mut BufReader<File>) -> io::Result<ImportFormat> {
+    let buffer = reader.fill_buf()?;
+    match buffer.iter().find(|byte| !byte.is_ascii_whitespace()) {
+        Some(b'[') => Ok(ImportFormat::Ncdu),
+        _ => Ok(ImportFormat::Du),
+    }
+}
+
+// -------- ncdu --------
+
+// The info object of an ncdu entry; directories also carry their own allocation
+#[derive(Deserialize)]
+struct NcduInfo {
+    #[serde(deserialize_with = "raw_name")]
+    name: Vec<u8>, // ncdu writes names as the bytes it got from the OS, not always UTF-8
+    #[serde(default)]
+    asize: u64,
+    #[serde(default)]
+    dsize: u64,
+    #[serde(default)]
+    mtime: i64,
+    #[serde(default)]
+    uid: u32,
+    #[serde(default)]
+    dev: Option<u64>,
+    #[serde(default)]
+    ino: u64,
+    #[serde(default)]
+    hlnkc: bool,
+}
+
+// serde_json hands strings over as bytes without checking them for UTF-8
+fn raw_name<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    struct RawName;
+
+    impl Visitor<'_> for RawName {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a file name")
+        }
+
+        fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Vec<u8>, E> {
+            Ok(bytes.to_vec())
+        }
+
+        fn visit_str<E: de::Error>(self, text: &str) -> Result<Vec<u8>, E> {
+            Ok(text.as_bytes().to_vec())
+        }
+    }
+
+    deserializer.deserialize_bytes(RawName)
+}
+
+impl NcduInfo {
+    fn name(&self) -> String {
+        osname::encode(&osname::from_bytes(&self.name)).into_owned()
+    }
+
+    fn meta(&self, apparent_size: u64) -> EntryMeta {
+        EntryMeta {
+            mtime: self.mtime,
+            uid: self.uid,
+            apparent_size,
+            ..EntryMeta::default()
+        }
+    }
+}
+
+struct NcduImport<'s, S: ImportSink> {
+    sink: &'s mut S,
+    links: HashSet<(u64, u64)>, // (device, inode) of hard-linked files already counted
+}
+
+fn import_ncdu<S: ImportSink>(reader: impl Read, sink: &mut S) -> Result<NodeId, String> {
+    let mut import = NcduImport { sink, links: HashSet::new() };
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    Document(&mut import)
+        .deserialize(&mut deserializer)
+        .map_err(|e| format!("無法解析 ncdu 匯出檔: {}", e))
+}
+
+// [major version, minor version, {metadata}, root directory]
+struct Document<'a, 's, S: ImportSink>(&'a mut NcduImport<'s, S>);
+
+impl<'de, S: ImportSink> DeserializeSeed<'de> for Document<'_, '_, S> {
+    type Value = NodeId;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<NodeId, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, S: ImportSink> Visitor<'de> for Document<'_, '_, S> {
+    type Value = NodeId;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an ncdu export")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<NodeId, A::Error> {
+        let major: u64 = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &"an ncdu export"))?;
+        if major != 1 {
+            return Err(de::Error::custom(format!("unsupported ncdu export version {}", major)));
+        }
+        seq.next_element::<IgnoredAny>()?; // Minor version
+        seq.next_element::<IgnoredAny>()?; // Program metadata
+
+        let root = Entry { import: self.0, parent: NO_NODE, depth: 0, device: 0 };
+        let root = seq.next_element_seed(root)?.ok_or_else(|| de::Error::invalid_length(3, &"an ncdu export"))?;
+        while seq.next_element::<IgnoredAny>()?.is_some() {}
+        Ok(root)
+    }
+}
+
+// A file ({info}) or a directory ([{info}, children...])
+struct Entry<'a, 's, S: ImportSink> {
+    import: &'a mut NcduImport<'s, S>,
+    parent: NodeId,
+    depth: usize,
+    device: u64,
+}
+
+impl<'de, S: ImportSink> DeserializeSeed<'de> for Entry<'_, '_, S> {
+    type Value = NodeId;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<NodeId, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, S: ImportSink> Visitor<'de> for Entry<'_, '_, S> {
+    type Value = NodeId;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an ncdu file object or directory array")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<NodeId, A::Error> {
+        if self.import.sink.is_cancelled() {
+            return Err(de::Error::custom("import cancelled"));
+        }
+        let info = NcduInfo::deserialize(de::value::MapAccessDeserializer::new(map))?;
+
+        // Hard links are listed once per path; like the live scan, only the first one counts
+        let duplicate = info.hlnkc && !self.import.links.insert((self.device, info.ino));
+        let (size, apparent_size) = if duplicate { (0, 0) } else { (info.dsize, info.asize) };
+        self.import.sink.add_entry(self.parent, &info.name(), false, size, info.meta(apparent_size)).map_err(de::Error::custom)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<NodeId, A::Error> {
+        let info: NcduInfo = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &"a directory info object"))?;
+        let device = info.dev.unwrap_or(self.device);
+        let id = self.import.sink.add_entry(self.parent, &info.name(), true, 0, info.meta(0)).map_err(de::Error::custom)?;
+
+        loop {
+            let child = Entry { import: &mut *self.import, parent: id, depth: self.depth + 1, device };
+            if seq.next_element_seed(child)?.is_none() {
+                break;
+            }
+        }
+
+        self.import.sink.finish_directory(id, self.depth, info.dsize, info.asize);
+        Ok(id)
+    }
+}
+
+// -------- du --------
+
+struct OpenDirectory {
+    name: String,
+    id: NodeId,
+    children: u64, // Bytes of the children listed so far
+}
+
+// Paths are kept as bytes, since du prints them as the OS returned them
+fn parse_du_line(line: &[u8]) -> Option<(u64, &[u8])> {
+    let tab = line.iter().position(|&byte| byte == b'\t')?;
+    let size = std::str::from_utf8(&line[..tab]).ok()?;
+    Some((size.trim().parse().ok()?, &line[tab + 1..]))
+}
+
+fn trim_line_end(line: &[u8]) -> &[u8] {
+    let end = line.iter().rposition(|&byte| byte != b'\n' && byte != b'\r').map_or(0, |last| last + 1);
+    &line[..end]
+}
+
+// du lists the root it was given last
+fn du_root(path: &Path) -> Result<(u64, PathBuf), String> {
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    let length = file.metadata().map_err(|e| e.to_string())?.len();
+    file.seek(SeekFrom::Start(length.saturating_sub(DU_TAIL_BYTES))).map_err(|e| e.to_string())?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail).map_err(|e| e.to_string())?;
+
+    let last = tail
+        .split(|&byte| byte == b'\n')
+        .map(trim_line_end)
+        .rev()
+        .find(|line| !line.trim_ascii().is_empty())
+        .ok_or("du 輸出為空")?;
+    let (size, root) = parse_du_line(last).ok_or_else(|| format!("無法解析 du 輸出: {}", String::from_utf8_lossy(last)))?;
+    Ok((size, PathBuf::from(osname::from_bytes(root).into_owned())))
+}
+
+// Output of `du -ab` (or `du -b --all`): "SIZE<TAB>PATH", children before their parent.
+// du only reports apparent sizes, so they stand in for disk usage too. An entry is a
+// directory when something is listed below it; empty directories look like empty files.
+fn import_du(path: &Path, mut reader: BufReader<File>, sink: &mut impl ImportSink) -> Result<NodeId, String> {
+    let (_, root_path) = du_root(path)?;
+    let root = sink.add_entry(NO_NODE, &osname::encode_path(&root_path), true, 0, EntryMeta::default())?;
+    let mut open = vec![OpenDirectory { name: String::new(), id: root, children: 0 }];
+
+    let mut buffer = Vec::new();
+    let mut line_number = 0;
+    loop {
+        buffer.clear();
+        if reader.read_until(b'\n', &mut buffer).map_err(|e| e.to_string())? == 0 {
+            break;
+        }
+        line_number += 1;
+        if sink.is_cancelled() {
+            return Err("Import cancelled".to_string());
+        }
+
+        let line = trim_line_end(&buffer);
+        if line.trim_ascii().is_empty() {
+            continue;
+        }
+        let (size, entry_path) = parse_du_line(line).ok_or_else(|| {
+            format!("第 {} 行無法解析 (應為 \"SIZE<TAB>PATH\"): {}", line_number, String::from_utf8_lossy(line))
+        })?;
+        let entry_path = osname::from_bytes(entry_path);
+        let Ok(relative) = Path::new(&entry_path).strip_prefix(&root_path) else {
+            continue; // Outside the root du was given
+        };
+        let names: Vec<String> = relative
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(osname::encode(name).into_owned()),
+                _ => None,
+            })
+            .collect();
+
+        // Leave directories this entry isn't inside of (only happens with out-of-order input)
+        let common = open[1..].iter().zip(&names).take_while(|(dir, name)| &dir.name == *name).count();
+        while open.len() > common + 1 {
+            close_directory(sink, &mut open, None);
+        }
+
+        // The line for a directory comes after everything inside it
+        if common == names.len() {
+            close_directory(sink, &mut open, Some(size));
+            if open.is_empty() {
+                return Ok(root);
+            }
+            continue;
+        }
+
+        for name in &names[common..names.len() - 1] {
+            let parent = open.last().map_or(root, |dir| dir.id);
+            let id = sink.add_entry(parent, name, true, 0, EntryMeta::default())?;
+            open.push(OpenDirectory { name: name.clone(), id, children: 0 });
+        }
+
+        let parent = open.last_mut().ok_or("du 輸出順序錯誤")?;
+        let meta = EntryMeta { apparent_size: size, ..EntryMeta::default() };
+        sink.add_entry(parent.id, &names[names.len() - 1], false, size, meta)?;
+        parent.children += size;
+    }
+
+    // Input cut short: finish whatever is still open
+    while !open.is_empty() {
+        close_directory(sink, &mut open, None);
+    }
+    Ok(root)
+}
+
+fn close_directory(sink: &mut impl ImportSink, open: &mut Vec<OpenDirectory>, listed_size: Option<u64>) {
+    let Some(dir) = open.pop() else {
+        return;
+    };
+    let total = listed_size.unwrap_or(dir.children).max(dir.children);
+    let own = total - dir.children;
+    sink.finish_directory(dir.id, open.len(), own, own);
+    if let Some(parent) = open.last_mut() {
+        parent.children += total;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Listing {
+        entries: Vec<(NodeId, String, bool, u64)>, // Parent, name, directory, size
+        own_sizes: Vec<(NodeId, u64)>,
+    }
+
+    impl ImportSink for Listing {
+        fn add_entry(&mut self, parent: NodeId, name: &str, is_directory: bool, size: u64, _meta: EntryMeta) -> Result<NodeId, String> {
+            self.entries.push((parent, name.to_string(), is_directory, size));
+            Ok(self.entries.len() as NodeId - 1)
+        }
+
+        fn finish_directory(&mut self, id: NodeId, _depth: usize, own_size: u64, _own_apparent_size: u64) {
+            self.own_sizes.push((id, own_size));
+        }
+
+        fn is_cancelled(&self) -> bool {
+            false
+        }
+    }
+
+    fn import_bytes(name: &str, contents: &[u8]) -> Listing {
+        let path = std::env::temp_dir().join(format!("storviz-test-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        let mut listing = Listing::default();
+        let result = import(&path, ImportFormat::Auto, &mut listing);
+        let _ = std::fs::remove_file(&path);
+        result.unwrap();
+        listing
+    }
+
+    #[test]
+    fn ncdu_directories_and_hard_links() {
+        let listing = import_bytes("links.ncdu", br#"[1,2,{"progname":"ncdu"},
+            [{"name":"/data","dsize":4096,"dev":1},
+             {"name":"a.txt","asize":10,"dsize":4096,"ino":7,"hlnkc":true},
+             [{"name":"sub","dsize":4096},{"name":"b.txt","asize":10,"dsize":4096,"ino":7,"hlnkc":true}],
+             {"name":"c.bin","asize":100,"dsize":8192}]]"#);
+
+        let names: Vec<(NodeId, &str, bool, u64)> = listing.entries.iter().map(|(p, n, d, s)| (*p, n.as_str(), *d, *s)).collect();
+        assert_eq!(names, vec![
+            (NO_NODE, "/data", true, 0),
+            (0, "a.txt", false, 4096),
+            (0, "sub", true, 0),
+            (2, "b.txt", false, 0), // Second path of the same inode
+            (0, "c.bin", false, 8192),
+        ]);
+        assert_eq!(listing.own_sizes, vec![(2, 4096), (0, 4096)]);
+    }
+
+    #[test]
+    fn du_listing_builds_directories_from_paths() {
+        let listing = import_bytes("tree.du", b"3\t/data/a/f.txt\n4099\t/data/a\n10\t/data/g\n4200\t/data\n");
+
+        let names: Vec<(NodeId, &str, bool, u64)> = listing.entries.iter().map(|(p, n, d, s)| (*p, n.as_str(), *d, *s)).collect();
+        assert_eq!(names, vec![
+            (NO_NODE, "/data", true, 0),
+            (0, "a", true, 0),
+            (1, "f.txt", false, 3),
+            (0, "g", false, 10),
+        ]);
+        assert_eq!(listing.own_sizes, vec![(1, 4096), (0, 91)]);
+    }
+
+    #[test]
+    fn du_reports_the_bad_line() {
+        let path = std::env::temp_dir().join(format!("storviz-test-{}-bad.du", std::process::id()));
+        std::fs::write(&path, b"3\t/data/a\nnot a line\n4\t/data\n").unwrap();
+        let result = import(&path, ImportFormat::Du, &mut Listing::default());
+        let _ = std::fs::remove_file(&path);
+        assert!(result.unwrap_err().starts_with("第 2 行"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn names_that_are_not_utf8_keep_their_bytes() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let ncdu = import_bytes("bytes.ncdu", b"[1,2,{},[{\"name\":\"/data\"},{\"name\":\"caf\xe9.txt\",\"dsize\":1}]]");
+        let du = import_bytes("bytes.du", b"1\t/data/caf\xe9.txt\n1\t/data\n");
+
+        for listing in [ncdu, du] {
+            let name = &listing.entries[1].1;
+            assert_eq!(osname::decode(name).as_bytes(), b"caf\xe9.txt");
+        }
+    }
+}
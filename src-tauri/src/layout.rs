@@ -0,0 +1,373 @@
+// Treemap and sunburst geometry for one node of the scan tree, computed in the backend so the
+// frontend only draws what is visible. Entries below the minimum pixel area are folded into
+// one "smaller items" shape per directory, so the cost follows what fits on screen.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::classify::Category;
+use crate::query::{self, SortKey};
+use crate::spill;
+use crate::tree::{NodeId, ScanTree};
+
+pub const PADDING: f64 = 2.0; // Gap between a directory's edge and its children
+pub const HEADER: f64 = 16.0; // Room for a directory's label above its children
+const CHAR_WIDTH: f64 = 7.0; // Rough width of one label character at 11px
+const LABEL_MIN_WIDTH: f64 = 40.0;
+const LABEL_MIN_HEIGHT: f64 = 14.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LayoutKind {
+    #[default]
+    Treemap,
+    Sunburst,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct LayoutRequest {
+    #[serde(default)]
+    pub kind: LayoutKind,
+    pub width: f64,
+    pub height: f64,
+    #[serde(default = "default_min_area")]
+    pub min_area: f64, // In square pixels
+    #[serde(default = "default_max_depth")]
+    pub max_depth: usize, // Levels below the requested node
+}
+
+fn default_min_area() -> f64 {
+    16.0
+}
+
+fn default_max_depth() -> usize {
+    4
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "shape", rename_all = "snake_case")]
+pub enum Shape {
+    Rect { x: f64, y: f64, width: f64, height: f64 },
+    // Degrees clockwise from 12 o'clock, radii from the center of the viewport
+    Arc { start_angle: f64, end_angle: f64, inner_radius: f64, outer_radius: f64 },
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LayoutItem {
+    pub id: Option<NodeId>, // None for a "smaller items" bucket
+    pub name: String,
+    pub size: u64,
+    pub depth: usize, // 0 is the requested node
+    pub is_directory: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<Category>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub folded: Option<u64>, // Entries merged into a "smaller items" bucket
+    #[serde(flatten)]
+    pub shape: Shape,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>, // Name shortened to fit, when there is room for it
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Layout {
+    pub kind: LayoutKind,
+    pub width: f64,
+    pub height: f64,
+    pub items: Vec<LayoutItem>, // Parents before their children, in drawing order
+    pub hidden_count: u64, // Entries too small to draw even as part of a bucket
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+// A child to place: a real node or the bucket of everything too small
+struct Slot {
+    id: Option<NodeId>,
+    size: u64,
+    folded: u64,
+}
+
+struct LayoutBuilder<'a> {
+    tree: &'a mut ScanTree,
+    index: Option<&'a Path>,
+    request: &'a LayoutRequest,
+    items: Vec<LayoutItem>,
+    hidden_count: u64,
+}
+
+impl LayoutBuilder<'_> {
+    // Children of a directory by size, with everything below `min_size` folded into one slot
+    fn slots(&mut self, id: NodeId, min_size: f64) -> Vec<Slot> {
+        if let Err(e) = spill::ensure_loaded(self.tree, self.index, id) {
+            eprintln!("❌ Failed to load spilled children: {}", e);
+        }
+
+        let mut slots = Vec::new();
+        let mut bucket = Slot { id: None, size: 0, folded: 0 };
+        for child in query::sorted_children(self.tree, id, SortKey::Size) {
+            let size = self.tree.node(child).size;
+            if size == 0 {
+                continue;
+            }
+            if size as f64 >= min_size {
+                slots.push(Slot { id: Some(child), size, folded: 1 });
+            } else {
+                bucket.size += size;
+                bucket.folded += 1;
+            }
+        }
+
+        if bucket.folded > 0 {
+            if bucket.size as f64 >= min_size {
+                slots.push(bucket);
+            } else {
+                self.hidden_count += bucket.folded;
+            }
+        }
+        slots
+    }
+
+    fn item(&self, slot: &Slot, depth: usize, shape: Shape, room: (f64, f64)) -> LayoutItem {
+        let (name, is_directory, category) = match slot.id {
+            Some(id) => {
+                let node = self.tree.node(id);
+                let category = (!node.is_directory && node.meta.category != Category::Other).then_some(node.meta.category);
+                (self.tree.name(id).to_string(), node.is_directory, category)
+            }
+            None => (format!("{} 個較小項目", slot.folded), false, None),
+        };
+        let label = fit_label(&name, room.0, room.1);
+        LayoutItem {
+            id: slot.id,
+            name,
+            size: slot.size,
+            depth,
+            is_directory,
+            category,
+            folded: slot.id.is_none().then_some(slot.folded),
+            shape,
+            label,
+        }
+    }
+
+    fn treemap(&mut self, id: NodeId, depth: usize, x: f64, y: f64, width: f64, height: f64) {
+        let size = self.tree.node(id).size;
+        if size == 0 || width <= 0.0 || height <= 0.0 || depth >= self.request.max_depth {
+            return;
+        }
+        let bytes_per_pixel = size as f64 / (width * height);
+        let slots = self.slots(id, self.request.min_area * bytes_per_pixel);
+
+        for (slot, rect) in squarify(&slots, |slot| slot.size, Rect { x, y, width, height }) {
+            let Rect { x: rx, y: ry, width: rw, height: rh } = rect;
+            let shape = Shape::Rect { x: rx, y: ry, width: rw, height: rh };
+            let item = self.item(slot, depth + 1, shape, (rw - 2.0 * PADDING, rh));
+            let labeled = item.label.is_some();
+            let nested = item.is_directory;
+            self.items.push(item);
+
+            // Directories with room to spare show their own children inside
+            if let (true, Some(child)) = (nested, slot.id) {
+                let top = if labeled { HEADER } else { PADDING };
+                if rw > 2.0 * PADDING && rh > top + PADDING {
+                    self.treemap(child, depth + 1, rx + PADDING, ry + top, rw - 2.0 * PADDING, rh - top - PADDING);
+                }
+            }
+        }
+    }
+
+    fn sunburst(&mut self, id: NodeId, depth: usize, start: f64, end: f64, ring: f64) {
+        let size = self.tree.node(id).size;
+        if size == 0 || depth >= self.request.max_depth {
+            return;
+        }
+        let inner = ring * (depth + 1) as f64;
+        let outer = inner + ring;
+
+        // Area of a slot is its share of this ring segment
+        let segment_area = (end - start).to_radians() / 2.0 * (outer * outer - inner * inner);
+        let slots = self.slots(id, self.request.min_area * size as f64 / segment_area);
+
+        let mut angle = start;
+        for slot in slots {
+            let sweep = (end - start) * slot.size as f64 / size as f64;
+            let shape = Shape::Arc { start_angle: angle, end_angle: angle + sweep, inner_radius: inner, outer_radius: outer };
+            let arc_length = sweep.to_radians() * (inner + outer) / 2.0;
+            let item = self.item(&slot, depth + 1, shape, (arc_length, ring));
+            let nested = item.is_directory;
+            self.items.push(item);
+
+            if let (true, Some(child)) = (nested, slot.id) {
+                self.sunburst(child, depth + 1, angle, angle + sweep, ring);
+            }
+            angle += sweep;
+        }
+    }
+}
+
+// Squarified treemap (Bruls, Huizing & van Wijk): fill rows along the shorter side,
+// adding rectangles to a row for as long as that improves its worst aspect ratio.
+// Items are expected largest first.
+pub fn squarify<T>(slots: &[T], size: impl Fn(&T) -> u64, area: Rect) -> Vec<(&T, Rect)> {
+    let total: u64 = slots.iter().map(&size).sum();
+    let mut placed = Vec::with_capacity(slots.len());
+    if total == 0 {
+        return placed;
+    }
+    let scale = area.width * area.height / total as f64;
+    let Rect { mut x, mut y, mut width, mut height } = area;
+
+    let worst = |row: &[&T], side: f64| {
+        let areas = row.iter().map(|slot| size(slot) as f64 * scale);
+        let sum: f64 = areas.clone().sum();
+        let max = areas.clone().fold(0.0, f64::max);
+        let min = areas.fold(f64::INFINITY, f64::min);
+        (side * side * max / (sum * sum)).max(sum * sum / (side * side * min))
+    };
+
+    let mut row: Vec<&T> = Vec::new();
+    let mut remaining = slots.iter().peekable();
+    loop {
+        let side = width.min(height);
+        let grow = match remaining.peek() {
+            Some(&next) => {
+                let mut candidate = row.clone();
+                candidate.push(next);
+                row.is_empty() || worst(&candidate, side) <= worst(&row, side)
+            }
+            None => false,
+        };
+        if grow {
+            row.extend(remaining.next());
+            continue;
+        }
+        if row.is_empty() {
+            break;
+        }
+
+        // Lay the row out along the shorter side and shrink the free space
+        let area: f64 = row.iter().map(|slot| size(slot) as f64 * scale).sum();
+        if width >= height {
+            let column = area / height;
+            let mut cy = y;
+            for slot in row.drain(..) {
+                let h = size(slot) as f64 * scale / column;
+                placed.push((slot, Rect { x, y: cy, width: column, height: h }));
+                cy += h;
+            }
+            x += column;
+            width -= column;
+        } else {
+            let line = area / width;
+            let mut cx = x;
+            for slot in row.drain(..) {
+                let w = size(slot) as f64 * scale / line;
+                placed.push((slot, Rect { x: cx, y, width: w, height: line }));
+                cx += w;
+            }
+            y += line;
+            height -= line;
+        }
+    }
+    placed
+}
+
+// The name cut down to the room available, or None if not even a few characters fit
+pub fn fit_label(name: &str, width: f64, height: f64) -> Option<String> {
+    if width < LABEL_MIN_WIDTH || height < LABEL_MIN_HEIGHT {
+        return None;
+    }
+    let fits = (width / CHAR_WIDTH) as usize;
+    let length = name.chars().count();
+    if length <= fits {
+        return Some(name.to_string());
+    }
+    if fits < 4 {
+        return None;
+    }
+    let mut label: String = name.chars().take(fits - 1).collect();
+    label.push('…');
+    Some(label)
+}
+
+pub fn compute(tree: &mut ScanTree, index: Option<&Path>, id: NodeId, request: &LayoutRequest) -> Result<Layout, String> {
+    if !(request.width > 0.0 && request.height > 0.0) {
+        return Err("Viewport must have a positive size".to_string());
+    }
+
+    let mut builder = LayoutBuilder {
+        tree,
+        index,
+        request,
+        items: Vec::new(),
+        hidden_count: 0,
+    };
+
+    match request.kind {
+        LayoutKind::Treemap => builder.treemap(id, 0, 0.0, 0.0, request.width, request.height),
+        LayoutKind::Sunburst => {
+            // The node itself is the center disk; each level below gets an equal ring
+            let radius = request.width.min(request.height) / 2.0;
+            let ring = radius / (request.max_depth + 1) as f64;
+            let center = Slot { id: Some(id), size: builder.tree.node(id).size, folded: 1 };
+            let shape = Shape::Arc { start_angle: 0.0, end_angle: 360.0, inner_radius: 0.0, outer_radius: ring };
+            let item = builder.item(&center, 0, shape, (ring * 2.0, ring));
+            builder.items.push(item);
+            builder.sunburst(id, 0, 0.0, 360.0, ring);
+        }
+    }
+
+    Ok(Layout {
+        kind: request.kind,
+        width: request.width,
+        height: request.height,
+        items: builder.items,
+        hidden_count: builder.hidden_count,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area(rect: &Rect) -> f64 {
+        rect.width * rect.height
+    }
+
+    #[test]
+    fn squarify_areas_follow_sizes_and_fill_the_box() {
+        let sizes = [600u64, 300, 60, 30, 10];
+        let placed = squarify(&sizes, |&size| size, Rect { x: 10.0, y: 20.0, width: 100.0, height: 50.0 });
+
+        assert_eq!(placed.len(), sizes.len());
+        let total: f64 = placed.iter().map(|(_, rect)| area(rect)).sum();
+        assert!((total - 5000.0).abs() < 1e-6);
+        for &(&size, rect) in &placed {
+            assert!((area(&rect) - size as f64 * 5.0).abs() < 1e-6);
+            assert!(rect.x >= 10.0 - 1e-9 && rect.x + rect.width <= 110.0 + 1e-9);
+            assert!(rect.y >= 20.0 - 1e-9 && rect.y + rect.height <= 70.0 + 1e-9);
+        }
+    }
+
+    #[test]
+    fn squarify_keeps_aspect_ratios_reasonable() {
+        let sizes = [1u64; 16];
+        let placed = squarify(&sizes, |&size| size, Rect { x: 0.0, y: 0.0, width: 400.0, height: 400.0 });
+        for (_, rect) in placed {
+            let ratio = rect.width.max(rect.height) / rect.width.min(rect.height);
+            assert!(ratio < 2.0, "{:?}", rect);
+        }
+    }
+
+    #[test]
+    fn squarify_of_nothing_places_nothing() {
+        let sizes: [u64; 2] = [0, 0];
+        assert!(squarify(&sizes, |&size| size, Rect { x: 0.0, y: 0.0, width: 10.0, height: 10.0 }).is_empty());
+    }
+}
@@ -0,0 +1,161 @@
+// Resource limits for scans on machines that must not be slowed down: a wall-clock budget, an
+// entry cap, a cap on filesystem operations (stat / readdir) per second, and a lowered CPU and
+// I/O priority for the scan threads. A scan that hits a limit stops walking and completes with
+// what it found so far, marked with the reason.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ScanLimits {
+    #[serde(default)]
+    pub max_duration_ms: Option<u64>,
+    #[serde(default)]
+    pub max_entries: Option<u64>,
+    #[serde(default)]
+    pub max_ops_per_second: Option<u64>,
+    #[serde(default)]
+    pub low_priority: bool, // nice / ionice idle on Linux, background QoS on macOS
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    TimeLimit,
+    EntryLimit,
+}
+
+impl StopReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StopReason::TimeLimit => "time_limit",
+            StopReason::EntryLimit => "entry_limit",
+        }
+    }
+}
+
+// Shared by every thread of one scan
+pub struct Limiter {
+    limits: ScanLimits,
+    deadline: Option<Instant>,
+    entries: AtomicU64,
+    next_slot: Mutex<Instant>, // Earliest time the next operation may start under the rate cap
+    stopped: Mutex<Option<StopReason>>,
+}
+
+impl Limiter {
+    pub fn new(limits: ScanLimits) -> Self {
+        let now = Instant::now();
+        Self {
+            deadline: limits.max_duration_ms.map(|ms| now + Duration::from_millis(ms)),
+            limits,
+            entries: AtomicU64::new(0),
+            next_slot: Mutex::new(now),
+            stopped: Mutex::new(None),
+        }
+    }
+
+    // One entry about to be stat'ed; false once a limit is reached
+    pub fn admit_entry(&self) -> bool {
+        if let Some(max) = self.limits.max_entries {
+            if self.entries.fetch_add(1, Ordering::Relaxed) >= max {
+                self.stop(StopReason::EntryLimit);
+                return false;
+            }
+        }
+        self.admit_operation()
+    }
+
+    // Entries taken from a checkpoint instead of the disk: they count towards the entry and time
+    // limits like scanned ones, but cost no I/O, so the rate cap doesn't apply
+    pub fn admit_recorded(&self, count: u64) -> bool {
+        if let Some(max) = self.limits.max_entries {
+            if self.entries.fetch_add(count, Ordering::Relaxed) + count > max {
+                self.stop(StopReason::EntryLimit);
+                return false;
+            }
+        }
+        if self.stopped().is_some() {
+            return false;
+        }
+        if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            self.stop(StopReason::TimeLimit);
+            return false;
+        }
+        true
+    }
+
+    // Any other filesystem operation (reading a directory); waits for its turn under the rate cap
+    pub fn admit_operation(&self) -> bool {
+        if self.stopped().is_some() {
+            return false;
+        }
+        if let Some(deadline) = self.deadline {
+            if Instant::now() >= deadline {
+                self.stop(StopReason::TimeLimit);
+                return false;
+            }
+        }
+
+        let Some(rate) = self.limits.max_ops_per_second.filter(|&rate| rate > 0) else {
+            return true;
+        };
+        let slot = {
+            let mut next_slot = self.next_slot.lock().unwrap();
+            let slot = (*next_slot).max(Instant::now());
+            *next_slot = slot + Duration::from_secs(1) / rate as u32;
+            slot
+        };
+        // Never sleep past the time budget
+        let wake = self.deadline.map_or(slot, |deadline| slot.min(deadline));
+        let now = Instant::now();
+        if wake > now {
+            std::thread::sleep(wake - now);
+        }
+        if self.deadline.is_some_and(|deadline| slot >= deadline) {
+            self.stop(StopReason::TimeLimit);
+            return false;
+        }
+        true
+    }
+
+    fn stop(&self, reason: StopReason) {
+        if let Ok(mut stopped) = self.stopped.lock() {
+            if stopped.is_none() {
+                println!("⏹️ Scan limit reached: {}", reason.as_str());
+                *stopped = Some(reason);
+            }
+        }
+    }
+
+    pub fn stopped(&self) -> Option<StopReason> {
+        self.stopped.lock().ok().and_then(|stopped| *stopped)
+    }
+}
+
+// Lower the CPU and I/O priority of the calling thread
+#[cfg(target_os = "linux")]
+pub fn lower_thread_priority() {
+    const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+    const IOPRIO_CLASS_IDLE: libc::c_long = 3;
+    const IOPRIO_CLASS_SHIFT: libc::c_long = 13;
+    // On Linux both calls apply to the calling thread only when given its thread id (or 0)
+    unsafe {
+        let tid = libc::syscall(libc::SYS_gettid) as libc::id_t;
+        libc::setpriority(libc::PRIO_PROCESS, tid, 10);
+        libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0 as libc::c_long, IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT);
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn lower_thread_priority() {
+    // Background band: low CPU priority and throttled disk I/O
+    unsafe {
+        libc::setpriority(libc::PRIO_DARWIN_THREAD, 0, libc::PRIO_DARWIN_BG);
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn lower_thread_priority() {}
@@ -0,0 +1,184 @@
+// Progressive refinement of the upper levels of a running scan: directories down to
+// `max_depth` report a provisional size (bytes found so far) while they are scanned, and
+// a snapshot of their largest children once they complete, so the UI can fill in as it goes.
+// Deeper levels aren't streamed: they arrive with their first-level directory's subtree once
+// that completes, so a deep tree stays coarse in the UI until then.
+
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use crate::classify::Category;
+use crate::tree::{NodeId, ScanTree};
+
+const MAX_LISTED_CHILDREN: usize = 64; // Largest children sent with a completed directory
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LiveChild {
+    pub id: NodeId,
+    pub name: String,
+    pub size: u64,
+    pub is_directory: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<Category>,
+}
+
+// A directory's size as the scan refines it; `children` is only set once it is complete
+#[derive(Clone, Debug, Serialize)]
+pub struct DirectoryUpdate {
+    pub id: NodeId,
+    pub parent: NodeId,
+    pub name: String,
+    pub depth: usize,
+    pub size: u64,
+    pub complete: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub children: Option<Vec<LiveChild>>, // Largest first; the rest is `size` minus their sum
+}
+
+struct OpenDirectory {
+    depth: usize,
+    size: u64,
+    changed: bool,
+    above: Option<NodeId>, // Closest open ancestor
+}
+
+pub struct LiveTracker {
+    max_depth: usize,
+    open: HashMap<NodeId, OpenDirectory>,
+    below: HashMap<NodeId, NodeId>, // Directories being scanned under the tracked levels -> closest open ancestor
+    completed: Vec<DirectoryUpdate>,
+}
+
+impl LiveTracker {
+    pub fn new(max_depth: usize) -> Self {
+        Self {
+            max_depth,
+            open: HashMap::new(),
+            below: HashMap::new(),
+            completed: Vec::new(),
+        }
+    }
+
+    pub fn tracks(&self, depth: usize) -> bool {
+        depth >= 1 && depth <= self.max_depth
+    }
+
+    // The closest open directory at or above `id`, if `id` is being scanned
+    fn innermost(&self, id: NodeId) -> Option<NodeId> {
+        if self.open.contains_key(&id) {
+            Some(id)
+        } else {
+            self.below.get(&id).copied()
+        }
+    }
+
+    // Every directory is registered as its scan starts (its parent is still being scanned), so
+    // files find their open ancestors without walking up the tree
+    pub fn open(&mut self, id: NodeId, parent: NodeId, depth: usize) {
+        let above = self.innermost(parent);
+        if self.tracks(depth) {
+            self.open.insert(id, OpenDirectory { depth, size: 0, changed: true, above });
+        } else if let Some(above) = above {
+            self.below.insert(id, above);
+        }
+    }
+
+    // Count a file towards every open directory above it
+    pub fn add_file(&mut self, parent: NodeId, size: u64) {
+        if size == 0 {
+            return;
+        }
+        let mut current = self.innermost(parent);
+        while let Some(id) = current {
+            let Some(directory) = self.open.get_mut(&id) else { break };
+            directory.size += size;
+            directory.changed = true;
+            current = directory.above;
+        }
+    }
+
+    // Snapshot a finished directory; must run before its children are spilled
+    pub fn close(&mut self, tree: &ScanTree, id: NodeId) {
+        self.below.remove(&id);
+        let Some(directory) = self.open.remove(&id) else {
+            return;
+        };
+
+        let mut children: Vec<NodeId> = tree.children(id).collect();
+        children.sort_by_key(|&child| Reverse(tree.node(child).size));
+        let children = children
+            .into_iter()
+            .take(MAX_LISTED_CHILDREN)
+            .map(|child| {
+                let node = tree.node(child);
+                LiveChild {
+                    id: child,
+                    name: tree.name(child).to_string(),
+                    size: node.size,
+                    is_directory: node.is_directory,
+                    category: (!node.is_directory && node.meta.category != Category::Other).then_some(node.meta.category),
+                }
+            })
+            .collect();
+
+        self.completed.push(DirectoryUpdate {
+            id,
+            parent: tree.parent(id).unwrap_or(id),
+            name: tree.name(id).to_string(),
+            depth: directory.depth,
+            size: tree.node(id).size,
+            complete: true,
+            children: Some(children),
+        });
+    }
+
+    // Directories completed since the last call, then open ones whose size moved
+    pub fn take_updates(&mut self, tree: &ScanTree) -> Vec<DirectoryUpdate> {
+        let mut updates = std::mem::take(&mut self.completed);
+        for (&id, directory) in self.open.iter_mut().filter(|(_, directory)| directory.changed) {
+            directory.changed = false;
+            updates.push(DirectoryUpdate {
+                id,
+                parent: tree.parent(id).unwrap_or(id),
+                name: tree.name(id).to_string(),
+                depth: directory.depth,
+                size: directory.size,
+                complete: false,
+                children: None,
+            });
+        }
+        updates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::{EntryMeta, NO_NODE};
+
+    #[test]
+    fn files_count_towards_open_ancestors_only() {
+        let mut tree = ScanTree::new("/scan");
+        let mut ids = vec![tree.add_node(NO_NODE, "scan", true, 0, EntryMeta::default()).unwrap()];
+        let mut live = LiveTracker::new(2);
+        for depth in 1..=4 {
+            let id = tree.add_node(ids[depth - 1], &format!("d{}", depth), true, 0, EntryMeta::default()).unwrap();
+            live.open(id, ids[depth - 1], depth);
+            ids.push(id);
+        }
+
+        live.add_file(ids[4], 10);
+        live.add_file(ids[1], 5);
+        live.add_file(ids[0], 7);
+        assert_eq!(live.open[&ids[1]].size, 15);
+        assert_eq!(live.open[&ids[2]].size, 10);
+        assert!(!live.open.contains_key(&ids[3]));
+
+        for &id in ids[1..].iter().rev() {
+            live.close(&tree, id);
+        }
+        assert!(live.open.is_empty() && live.below.is_empty());
+        assert_eq!(live.take_updates(&tree).len(), 2);
+    }
+}
@@ -1,63 +1,264 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod checkpoint;
+mod classify;
+mod cli;
+mod device;
+mod errors;
+mod estimate;
+mod export;
+mod filter;
+mod fold;
+mod import;
+mod layout;
+mod limits;
+mod live;
+mod osname;
+mod overview;
+mod owner;
+mod progress;
+mod query;
+mod render;
+mod report;
+mod spill;
+mod stats;
+mod topn;
+mod tree;
+mod wire;
+
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
-use std::collections::HashSet;
-use std::sync::atomic::{AtomicBool, Ordering};
-use tauri::ipc::Channel;
-use rayon::prelude::*;
+use std::sync::{Arc, Condvar, Mutex};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tauri::ipc::{Channel, InvokeResponseBody, IpcResponse};
 use sysinfo::Disks;
 #[cfg(unix)]
-use std::os::unix::fs::MetadataExt;
+use std::os::unix::fs::{DirEntryExt, MetadataExt};
 use filesize::PathExt;
 use tauri_plugin_updater::UpdaterExt;
+use checkpoint::{Checkpoint, DirectoryRecord, Resumed};
+use classify::{SniffMode, TypeBreakdown, TypeStats};
+use device::{DevicePool, DevicePools};
+use errors::ErrorLog;
+use estimate::{ChildStats, Estimate, EstimateOptions};
+use fold::FoldOptions;
+use limits::{Limiter, ScanLimits, StopReason};
+use live::{DirectoryUpdate, LiveTracker};
+use overview::{Overview, ScanStrategy};
+use progress::{ProgressMeter, ProgressTarget, TargetBasis, Throughput};
+use query::{ChildrenPage, ChildrenQuery, NodeInfo};
+use spill::{SpillIndex, SpillOptions};
+use topn::{SizedPath, TopN};
+use tree::{CompactSubtree, EntryMeta, NodeId, ScanTree, NO_NODE, ROOT_NODE};
+use wire::WireFormat;
 
 // Constants
 const BATCH_SIZE: usize = 10000;
-const MAX_DEPTH: usize = 100; // Increased depth limit
-const PATH_UPDATE_INTERVAL: usize = 10; // Update path display every N files
+const MAX_DEPTH: usize = 100; // Default levels below the root a scan walks; deeper directories are only measured
+const MAX_DEPTH_LIMIT: usize = 512; // Highest depth a scan may ask for; subtree serialization nests a few frames per level
+const ENTRIES_PER_JOB: usize = 64; // Entries of one directory scanned by a single job
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100); // At most 10 progress events a second
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1); // Progress is resent this often even when nothing moved
+const MAX_BATCHES_IN_FLIGHT: u64 = 4; // Unacknowledged subtree batches before new ones are held back and merged
+const DEFAULT_TOP_N: usize = 50; // Entries kept in the largest files / directories lists
+const DEFAULT_FILTER_LIMIT: usize = 1000; // Nodes filter_scan builds for its matches and tree (totals always cover all)
+const DEFAULT_STREAM_DEPTH: usize = 3; // Levels below the root refined live while scanning (deeper ones come with their first-level subtree)
 
 // Global scan state for cancellation
 use std::sync::OnceLock;
 static CURRENT_SCAN_STATE: OnceLock<Arc<Mutex<Option<ScanState>>>> = OnceLock::new();
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct FileNode {
-    name: String,
-    size: u64,
-    path: String,
-    children: Option<Vec<FileNode>>,
-    #[serde(rename = "isDirectory")]
-    is_directory: bool,
+// The last finished scan, kept in the backend so the UI can query it by node ID
+static LAST_SCAN: OnceLock<Mutex<Option<Arc<RetainedScan>>>> = OnceLock::new();
+
+struct RetainedScan {
+    tree: Arc<Mutex<ScanTree>>,
+    spill: Option<Arc<(SpillOptions, SpillIndex)>>, // The index file goes away with the last reference
+    type_breakdown: TypeBreakdown,
+    largest_files: Vec<SizedPath>,
+    largest_dirs: Vec<SizedPath>,
+    disk_info: Option<DiskInfo>,
+    errors: ErrorLog,
+    imported: bool, // Loaded from an ncdu / du listing; its paths may name unrelated local files
+}
+
+fn retain_scan(scan: RetainedScan) {
+    let last_scan = LAST_SCAN.get_or_init(|| Mutex::new(None));
+    if let Ok(mut last) = last_scan.lock() {
+        *last = Some(Arc::new(scan));
+    }
+}
+
+fn retained_scan() -> Result<Arc<RetainedScan>, String> {
+    LAST_SCAN
+        .get()
+        .and_then(|last_scan| last_scan.lock().ok()?.clone())
+        .ok_or_else(|| "No scan results available".to_string())
+}
+
+impl RetainedScan {
+    fn spill_index(&self) -> Option<&Path> {
+        self.spill.as_ref().map(|spill| spill.1.path())
+    }
 }
 
-// Compact version - remove redundant path info
-#[derive(Debug, Clone, Serialize)]
-struct CompactFileNode {
-    #[serde(rename = "n")]
+// Statics are never dropped, so on exit the spill index of the last scan (and of one still
+// running, whose threads may hold on to it) is removed here
+fn release_scans() {
+    if let Some(Ok(mut current)) = CURRENT_SCAN_STATE.get().map(|state| state.lock()) {
+        if let Some(spill) = current.take().and_then(|state| state.spill.clone()) {
+            spill.1.remove();
+        }
+    }
+    if let Some(Ok(mut last)) = LAST_SCAN.get().map(|last| last.lock()) {
+        if let Some(spill) = last.take().and_then(|scan| scan.spill.clone()) {
+            spill.1.remove();
+        }
+    }
+}
+
+// Version of the scan event protocol, sent with every event. Bump it when an existing event
+// changes shape; frontends skip event types they don't know, so new events don't need a bump.
+const SCAN_PROTOCOL_VERSION: u32 = 2;
+
+#[derive(Clone, Serialize)]
+struct ScanRoot {
+    id: NodeId,
     name: String,
-    #[serde(rename = "s")]
+    path: String,
     size: u64,
-    #[serde(rename = "c", skip_serializing_if = "Option::is_none")]
-    children: Option<Vec<CompactFileNode>>,
-    #[serde(rename = "d")]
-    is_directory: bool,
 }
 
+// Totals and rankings of a scan, sent once it completes (or what was gathered before a cancel)
 #[derive(Clone, Serialize)]
-struct PartialScanResult {
-    nodes: Vec<FileNode>,
-    compact_nodes: Vec<CompactFileNode>, // Batch of compact nodes
+struct ScanSummary {
+    root: ScanRoot,
     total_scanned: u64,
     total_size: u64,
-    is_complete: bool,
-    root_node: Option<FileNode>,
-    compact_root: Option<CompactFileNode>,
     disk_info: Option<DiskInfo>,
-    current_path: Option<String>,
+    spill_index: Option<String>, // Index file holding spilled subtrees
+    largest_files: Vec<SizedPath>,
+    largest_dirs: Vec<SizedPath>, // Largest leaf directories
+    type_breakdown: TypeBreakdown, // Bytes and counts per file category / extension
+    errors: ErrorLog, // Paths that couldn't be read
+    estimate: Option<Estimate>, // Set when the root size is extrapolated from samples
+    refining: bool, // Exact values follow in another subtree / completed round
+    stopped: Option<StopReason>, // Set when a scan limit cut the scan short; the totals are partial
+}
+
+// Everything a scan streams to the frontend, tagged by "event"
+#[derive(Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum ScanEvent {
+    Started {
+        root_path: String,
+        disk_info: Option<DiskInfo>, // Only for whole-disk scans
+        target: Option<ProgressTarget>, // Expected totals, if known before the scan
+        resumed: Option<Resumed>, // Set when an interrupted scan of this root is continued
+        options: ScanOptions,
+    },
+    // Estimated sizes of the top level from the quick overview pass, refined once per level walked
+    Overview {
+        overview: Overview,
+    },
+    Progress {
+        total_scanned: u64,
+        total_size: u64,
+        current_path: String,
+        largest_files: Option<Vec<SizedPath>>, // Only sent when the list changed
+        largest_dirs: Option<Vec<SizedPath>>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        directories: Vec<DirectoryUpdate>, // Directories completed or grown since the last progress event
+        #[serde(flatten)]
+        throughput: Throughput, // Rates, and the fraction done and ETA when there is a target
+    },
+    // Finished subtrees directly under the root, serialized straight from the tree
+    Subtree {
+        seq: u64, // Acknowledged with ack_scan_batch when the scan uses backpressure
+        nodes: Vec<CompactSubtree>,
+        total_scanned: u64,
+        total_size: u64,
+    },
+    // A path that couldn't be read; fatal errors end the scan
+    Error {
+        path: String,
+        message: String,
+        fatal: bool,
+    },
+    Completed {
+        summary: ScanSummary,
+    },
+    Cancelled {
+        partial: Option<ScanSummary>, // None if the scan root itself was never read
+    },
+}
+
+#[derive(Serialize)]
+struct VersionedEvent<'a> {
+    v: u32,
+    #[serde(flatten)]
+    event: &'a ScanEvent,
+}
+
+// An event on its way to the frontend; subtrees go out in the binary wire format if asked to
+struct ScanMessage {
+    event: ScanEvent,
+    format: WireFormat,
+}
+
+impl IpcResponse for ScanMessage {
+    fn body(mut self) -> tauri::Result<InvokeResponseBody> {
+        if let (WireFormat::Binary, ScanEvent::Subtree { nodes, .. }) = (self.format, &mut self.event) {
+            let nodes = std::mem::take(nodes);
+            let header = VersionedEvent { v: SCAN_PROTOCOL_VERSION, event: &self.event };
+            match wire::encode(&header, &nodes) {
+                Ok(bytes) => return Ok(InvokeResponseBody::Raw(bytes)),
+                Err(e) => {
+                    eprintln!("❌ Binary encoding failed, sending JSON instead: {}", e);
+                    if let ScanEvent::Subtree { nodes: slot, .. } = &mut self.event {
+                        *slot = nodes;
+                    }
+                }
+            }
+        }
+        VersionedEvent { v: SCAN_PROTOCOL_VERSION, event: &self.event }.body()
+    }
+}
+
+// Optional per-scan settings; omitted fields keep the default in-memory scan
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct ScanOptions {
+    #[serde(default)]
+    spill: Option<SpillOptions>,
+    #[serde(default)]
+    top_n: Option<usize>,
+    #[serde(default)]
+    sniff: SniffMode,
+    #[serde(default)]
+    wire_format: WireFormat, // Binary batches need the frontend decoder in src/lib/scanWire.ts
+    #[serde(default)]
+    stream_depth: Option<usize>, // Levels with live directory updates (0 turns them off)
+    #[serde(default)]
+    strategy: ScanStrategy,
+    #[serde(default)]
+    overview_budget_ms: Option<u64>, // Time the quick overview may take before the full scan starts
+    #[serde(default)]
+    estimate: Option<EstimateOptions>, // Sample very large directories instead of reading them fully
+    #[serde(default)]
+    backpressure: bool, // The frontend acknowledges subtree batches, so sending can wait for it
+    #[serde(default)]
+    fold: Option<FoldOptions>, // Send tiny children as one "N smaller items" node per directory
+    #[serde(default)]
+    max_depth: Option<usize>, // Levels below the root to walk (capped at MAX_DEPTH_LIMIT)
+    #[serde(default)]
+    limits: ScanLimits, // Time, entry and I/O rate budgets; hitting one ends the scan with a partial result
+    #[serde(default)]
+    checkpoint: bool, // Log completed directories so an interrupted scan of the same root can resume
 }
 
 #[derive(Clone, Serialize)]
@@ -72,27 +273,75 @@ struct DiskInfo {
 struct ScanState {
     counter: Arc<Mutex<u64>>,
     scanned_size: Arc<Mutex<u64>>,
-    compact_batch_buffer: Arc<Mutex<Vec<CompactFileNode>>>, // Buffer for compact nodes
+    compact_batch_buffer: Arc<Mutex<Vec<NodeId>>>, // Buffer for compact nodes (tree node IDs)
+    tree: Arc<Mutex<ScanTree>>,
     #[cfg(unix)]
-    visited_inodes: Arc<Mutex<HashSet<u64>>>,
+    visited_inodes: Arc<Mutex<HashSet<(u64, u64)>>>, // (device, inode): inode numbers repeat across filesystems
     recursion_stack: Arc<Mutex<HashSet<PathBuf>>>,
     cancelled: Arc<AtomicBool>,
     current_path: Arc<Mutex<String>>,
-    path_update_counter: Arc<Mutex<usize>>,
+    devices: Arc<DevicePools>,
+    spill: Option<Arc<(SpillOptions, SpillIndex)>>,
+    largest_files: Arc<Mutex<TopN>>,
+    largest_dirs: Arc<Mutex<TopN>>,
+    sniff: SniffMode,
+    type_stats: Arc<Mutex<TypeStats>>,
+    errors: Arc<Mutex<ErrorLog>>,
+    wire_format: WireFormat,
+    live: Arc<Mutex<LiveTracker>>,
+    estimate: Option<EstimateOptions>,
+    sampling: Arc<Mutex<HashMap<NodeId, (usize, usize)>>>, // Sampled directories still being scanned: (listed, scanned)
+    sampled_dirs: Arc<Mutex<Vec<(NodeId, PathBuf, usize)>>>, // Every sampled directory, for refinement
+    refining: Arc<AtomicBool>,
+    progress: Arc<Mutex<ProgressMeter>>,
+    backpressure: bool,
+    batches_sent: Arc<AtomicU64>,
+    batches_acknowledged: Arc<AtomicU64>,
+    fold: Option<FoldOptions>,
+    max_depth: usize,
+    limiter: Arc<Limiter>,
+    checkpoint: Option<Arc<Checkpoint>>,
 }
 
 impl ScanState {
-    fn new() -> Self {
+    fn new(root_path: &Path, options: &ScanOptions, spill: Option<(SpillOptions, SpillIndex)>) -> Self {
+        let top_n = options.top_n.unwrap_or(DEFAULT_TOP_N);
+        let max_depth = options.max_depth.unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH_LIMIT);
+        // Without a checkpoint the scan still runs, it just can't be resumed
+        let checkpoint = options.checkpoint.then(|| Checkpoint::open(root_path, max_depth)).and_then(|result| {
+            result.map_err(|e| eprintln!("❌ Failed to open checkpoint: {}", e)).ok()
+        });
         Self {
             counter: Arc::new(Mutex::new(0)),
             scanned_size: Arc::new(Mutex::new(0)),
             compact_batch_buffer: Arc::new(Mutex::new(Vec::new())),
+            tree: Arc::new(Mutex::new(ScanTree::new(root_path))),
             #[cfg(unix)]
             visited_inodes: Arc::new(Mutex::new(HashSet::new())),
             recursion_stack: Arc::new(Mutex::new(HashSet::new())),
             cancelled: Arc::new(AtomicBool::new(false)),
             current_path: Arc::new(Mutex::new(String::new())),
-            path_update_counter: Arc::new(Mutex::new(0)),
+            devices: Arc::new(DevicePools::with_low_priority(options.limits.low_priority)),
+            spill: spill.map(Arc::new),
+            largest_files: Arc::new(Mutex::new(TopN::new(top_n))),
+            largest_dirs: Arc::new(Mutex::new(TopN::new(top_n))),
+            sniff: options.sniff,
+            type_stats: Arc::new(Mutex::new(TypeStats::default())),
+            errors: Arc::new(Mutex::new(ErrorLog::default())),
+            wire_format: options.wire_format,
+            live: Arc::new(Mutex::new(LiveTracker::new(options.stream_depth.unwrap_or(DEFAULT_STREAM_DEPTH)))),
+            estimate: options.estimate.clone(),
+            sampling: Arc::new(Mutex::new(HashMap::new())),
+            sampled_dirs: Arc::new(Mutex::new(Vec::new())),
+            refining: Arc::new(AtomicBool::new(false)),
+            progress: Arc::new(Mutex::new(ProgressMeter::new(None))),
+            backpressure: options.backpressure,
+            batches_sent: Arc::new(AtomicU64::new(0)),
+            batches_acknowledged: Arc::new(AtomicU64::new(0)),
+            fold: options.fold,
+            max_depth,
+            limiter: Arc::new(Limiter::new(options.limits.clone())),
+            checkpoint: checkpoint.map(Arc::new),
         }
     }
 
@@ -100,13 +349,21 @@ impl ScanState {
         self.cancelled.load(Ordering::Relaxed)
     }
 
+    fn message(&self, event: ScanEvent) -> ScanMessage {
+        ScanMessage { event, format: self.wire_format }
+    }
+
     fn cancel(&self) {
         self.cancelled.store(true, Ordering::Relaxed)
     }
 
     fn increment_counter(&self) {
+        self.add_to_counter(1);
+    }
+
+    fn add_to_counter(&self, entries: u64) {
         if let Ok(mut count) = self.counter.lock() {
-            *count += 1;
+            *count += entries;
         }
     }
 
@@ -122,7 +379,7 @@ impl ScanState {
         (*count, *size)
     }
 
-    fn add_compact_to_buffer(&self, node: CompactFileNode) -> bool {
+    fn add_compact_to_buffer(&self, node: NodeId) -> bool {
         if let Ok(mut buffer) = self.compact_batch_buffer.lock() {
             buffer.push(node);
             buffer.len() >= BATCH_SIZE
@@ -131,27 +388,328 @@ impl ScanState {
         }
     }
 
-    fn clear_compact_buffer(&self) -> Vec<CompactFileNode> {
+    // A full batch is waiting, e.g. because it was held back while the frontend caught up
+    fn batch_ready(&self) -> bool {
+        self.compact_batch_buffer.lock().map(|buffer| buffer.len() >= BATCH_SIZE).unwrap_or(false)
+    }
+
+    // Subtree batches the frontend hasn't finished processing
+    fn batches_in_flight(&self) -> u64 {
+        if !self.backpressure {
+            return 0;
+        }
+        self.batches_sent.load(Ordering::Relaxed).saturating_sub(self.batches_acknowledged.load(Ordering::Relaxed))
+    }
+
+    fn acknowledge_batch(&self, seq: u64) {
+        self.batches_acknowledged.fetch_max(seq, Ordering::Relaxed);
+    }
+
+    fn clear_compact_buffer(&self) -> Vec<CompactSubtree> {
         if let Ok(mut buffer) = self.compact_batch_buffer.lock() {
-            buffer.drain(..).collect()
+            buffer.drain(..).map(|id| self.compact_subtree(id)).collect()
         } else {
             Vec::new()
         }
     }
 
+    fn compact_subtree(&self, id: NodeId) -> CompactSubtree {
+        CompactSubtree::new(self.tree.clone(), id, self.max_depth, self.fold)
+    }
+
+    // A full arena fails the entry and flags its parent as partial
+    fn add_node(&self, parent: NodeId, name: &str, is_directory: bool, size: u64, meta: EntryMeta) -> Result<NodeId, String> {
+        let id = {
+            let mut tree = self.tree.lock().unwrap();
+            tree.add_node(parent, name, is_directory, size, meta).inspect_err(|_| {
+                if parent != NO_NODE {
+                    tree.mark_partial(parent);
+                }
+            })?
+        };
+        if !is_directory && parent != NO_NODE {
+            if let Ok(mut live) = self.live.lock() {
+                live.add_file(parent, size);
+            }
+        }
+        Ok(id)
+    }
+
+    // Link a batch of files under one parent, locking the tree once for all of them. If the
+    // arena fills up, the files that didn't fit fail and the parent is flagged as partial
+    fn add_files(&self, parent: NodeId, files: Vec<NewFile>) -> Result<(), String> {
+        if files.is_empty() {
+            return Ok(());
+        }
+        let mut total = 0;
+        let result = {
+            let mut tree = self.tree.lock().unwrap();
+            let result = files.into_iter().try_for_each(|file| {
+                tree.add_node(parent, &file.name, false, file.size, file.meta)?;
+                total += file.size;
+                Ok(())
+            });
+            if result.is_err() {
+                tree.mark_partial(parent);
+            }
+            result
+        };
+        if let Ok(mut live) = self.live.lock() {
+            live.add_file(parent, total);
+        }
+        result
+    }
+
+    // Start reporting a directory's provisional size if it is shallow enough, or count its files
+    // towards the closest one that is
+    fn watch_directory(&self, id: NodeId, parent: NodeId, depth: usize) {
+        if let Ok(mut live) = self.live.lock() {
+            live.open(id, parent, depth);
+        }
+    }
+
+    fn open_directory(&self, id: NodeId, parent: NodeId, depth: usize) {
+        self.watch_directory(id, parent, depth);
+        if let Some(checkpoint) = &self.checkpoint {
+            checkpoint.open_directory(id);
+        }
+    }
+
+    // Log a directory as complete, unless a cancel or a limit may have cut part of it off
+    fn checkpoint_directory(&self, id: NodeId, hidden_entries: Option<u64>) {
+        let Some(checkpoint) = &self.checkpoint else { return };
+        if self.is_cancelled() || self.limiter.stopped().is_some() {
+            return;
+        }
+        let record = checkpoint::record(&self.tree.lock().unwrap(), id, hidden_entries);
+        if let Err(e) = checkpoint.complete_directory(id, record, &self.tree) {
+            eprintln!("❌ Failed to write checkpoint: {}", e);
+        }
+    }
+
+    // A directory the interrupted scan already completed, if the checkpoint has it unchanged
+    fn resume_record(&self, id: NodeId) -> Option<DirectoryRecord> {
+        let checkpoint = self.checkpoint.as_ref()?;
+        let tree = self.tree.lock().unwrap();
+        checkpoint.take(&checkpoint::key(&tree, id), tree.node(id).meta.mtime)
+    }
+
+    // Drop the checkpoint of a completed scan, or make an interrupted one durable
+    fn close_checkpoint(&self, completed: bool) {
+        let Some(checkpoint) = &self.checkpoint else { return };
+        if completed && !self.is_cancelled() && self.limiter.stopped().is_none() {
+            checkpoint.remove();
+        } else if let Err(e) = checkpoint.sync(&self.tree) {
+            eprintln!("❌ Failed to write checkpoint: {}", e);
+        }
+    }
+
+    fn take_directory_updates(&self) -> Vec<DirectoryUpdate> {
+        let tree = self.tree.lock().unwrap();
+        self.live.lock().map(|mut live| live.take_updates(&tree)).unwrap_or_default()
+    }
+
+    // In estimate mode, scan only a sample of a directory with very many entries
+    fn sample_entries(&self, id: NodeId, path: &Path, depth: usize, entries: Vec<fs::DirEntry>) -> Vec<fs::DirEntry> {
+        let Some(options) = &self.estimate else {
+            return entries;
+        };
+        if entries.len() <= options.threshold || options.sample_size == 0 || self.refining.load(Ordering::Relaxed) {
+            return entries;
+        }
+
+        let listed = entries.len();
+        let seed = {
+            use std::hash::{Hash, Hasher};
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            path.hash(&mut hasher);
+            hasher.finish()
+        };
+        let mut entries: Vec<Option<fs::DirEntry>> = entries.into_iter().map(Some).collect();
+        let sample: Vec<fs::DirEntry> = estimate::sample_indices(listed, options.sample_size, seed)
+            .into_iter()
+            .filter_map(|index| entries[index].take())
+            .collect();
+
+        if let Ok(mut sampling) = self.sampling.lock() {
+            sampling.insert(id, (listed, sample.len()));
+        }
+        if let Ok(mut sampled) = self.sampled_dirs.lock() {
+            sampled.push((id, path.to_path_buf(), depth));
+        }
+        sample
+    }
+
+    fn set_progress_target(&self, target: ProgressTarget) {
+        if let Ok(mut progress) = self.progress.lock() {
+            progress.offer_target(target);
+        }
+    }
+
+    fn throughput(&self, total_scanned: u64, total_size: u64) -> Throughput {
+        self.progress.lock().map(|mut progress| progress.sample(total_scanned, total_size)).unwrap_or_default()
+    }
+
+    // True when a completed estimate scan is going to be refined to exact values
+    fn will_refine(&self) -> bool {
+        self.estimate.as_ref().is_some_and(|options| options.refine)
+            && !self.refining.load(Ordering::Relaxed)
+            && !self.is_cancelled()
+            && self.limiter.stopped().is_none()
+            && self.sampled_dirs.lock().map(|sampled| !sampled.is_empty()).unwrap_or(false)
+    }
+
+    // Add the sizes of a directory's children to the directory node (which starts out with
+    // its own allocation: zero for live scans, whatever an import listed), then move its children to the spill index if this subtree doesn't need to stay in memory
+    fn finish_directory(&self, id: NodeId, depth: usize) {
+        let mut tree = self.tree.lock().unwrap();
+        let own = tree.node(id);
+        let (own_size, own_apparent_size) = (own.size, own.meta.apparent_size);
+        let (total_size, apparent_size) = if self.estimate.is_some() {
+            // Estimate mode extrapolates sampled directories and tracks how uncertain every size is
+            let children: Vec<ChildStats> = tree
+                .children(id)
+                .map(|child| {
+                    let node = tree.node(child);
+                    ChildStats { size: node.size, apparent_size: node.meta.apparent_size, spread: tree.spread(child).unwrap_or_default() }
+                })
+                .collect();
+            let sampling = self.sampling.lock().ok().and_then(|mut sampling| sampling.remove(&id));
+            let (size, apparent_size, spread) = estimate::combine(&children, sampling);
+            tree.set_spread(id, spread);
+            (own_size + size, own_apparent_size + apparent_size)
+        } else {
+            tree.children(id)
+                .map(|child| tree.node(child))
+                .fold((own_size, own_apparent_size), |(size, apparent), node| (size + node.size, apparent + node.meta.apparent_size))
+        };
+        tree.set_size(id, total_size, apparent_size);
+
+        // Leaf directories (no subdirectories) compete for the largest directories list
+        if !tree.children(id).any(|child| tree.node(child).is_directory) {
+            if let Ok(mut largest) = self.largest_dirs.lock() {
+                largest.offer(total_size, || osname::encode_path(&tree.path(id)));
+            }
+        }
+
+        // Per-type bytes of the files directly inside, for the top directories of each type
+        if let Ok(mut stats) = self.type_stats.lock() {
+            let files = tree.children(id).filter(|&child| !tree.node(child).is_directory).map(|child| {
+                let node = tree.node(child);
+                (node.meta.category, classify::extension_of(tree.name(child)), node.size)
+            });
+            stats.add_directory(files, || osname::encode_path(&tree.path(id)));
+        }
+
+        // Snapshot for the live view while the children are still in memory
+        if let Ok(mut live) = self.live.lock() {
+            live.close(&tree, id);
+        }
+
+        // Children are collected here but written after the tree is unlocked, so other scan
+        // threads don't wait on the disk; nothing else touches a finished directory's children
+        let spilled = self
+            .spill
+            .as_ref()
+            .filter(|spill| spill.0.should_spill(depth, total_size))
+            .map(|spill| (spill.clone(), spill::spill_entries(&tree, id)));
+        drop(tree);
+        if let Some((spill, entries)) = spilled {
+            match spill.1.write_record(&entries) {
+                Ok(offset) => spill::release_spilled(&mut self.tree.lock().unwrap(), id, offset),
+                Err(e) => eprintln!("❌ Failed to spill {}: {}", self.tree.lock().unwrap().path(id).display(), e),
+            }
+        }
+    }
+
+    // Spill mode drops single-link files from inode tracking to keep memory bounded;
+    // directories and hard-linked files are still tracked for loop and duplicate detection
     #[cfg(unix)]
-    fn is_visited_inode(&self, inode: u64) -> bool {
+    fn tracks_inode(&self, metadata: &fs::Metadata) -> bool {
+        self.spill.is_none() || metadata.is_dir() || metadata.nlink() > 1
+    }
+
+    // Classify a file and count it towards the per-category / per-extension totals
+    fn classify_file(&self, path: &Path, name: &str, size: u64, display_path: &str) -> classify::Category {
+        let category = classify::classify(path, name, self.sniff);
+        self.count_file_type(category, name, size, display_path);
+        category
+    }
+
+    fn count_file_type(&self, category: classify::Category, name: &str, size: u64, display_path: &str) {
+        if let Ok(mut stats) = self.type_stats.lock() {
+            stats.add(category, classify::extension_of(name), size, display_path);
+        }
+    }
+
+    fn type_breakdown(&self) -> TypeBreakdown {
+        self.type_stats.lock().map(|stats| stats.breakdown()).unwrap_or_else(|_| TypeStats::default().breakdown())
+    }
+
+    fn offer_largest_file(&self, size: u64, path: &str) {
+        if let Ok(mut largest) = self.largest_files.lock() {
+            largest.offer(size, || path.to_string());
+        }
+    }
+
+    // Top-N lists that changed since they were last sent
+    fn take_largest_updates(&self) -> (Option<Vec<SizedPath>>, Option<Vec<SizedPath>>) {
+        let files = self.largest_files.lock().ok().and_then(|mut largest| largest.take_update());
+        let dirs = self.largest_dirs.lock().ok().and_then(|mut largest| largest.take_update());
+        (files, dirs)
+    }
+
+    fn largest_lists(&self) -> (Vec<SizedPath>, Vec<SizedPath>) {
+        let files = self.largest_files.lock().map(|largest| largest.sorted()).unwrap_or_default();
+        let dirs = self.largest_dirs.lock().map(|largest| largest.sorted()).unwrap_or_default();
+        (files, dirs)
+    }
+
+    // True while the error log still lists individual entries
+    fn record_error(&self, path: &str, error: impl ToString) -> bool {
+        self.errors.lock().map(|mut errors| errors.record(path, error)).unwrap_or(false)
+    }
+
+    fn error_log(&self) -> ErrorLog {
+        self.errors.lock().map(|errors| errors.clone()).unwrap_or_default()
+    }
+
+    // Everything the query, export and report commands need once the scan is done
+    fn retained(&self, disk_info: Option<DiskInfo>) -> RetainedScan {
+        let (largest_files, largest_dirs) = self.largest_lists();
+        RetainedScan {
+            tree: self.tree.clone(),
+            spill: self.spill.clone().inspect(|spill| {
+                if let Err(e) = spill.1.flush() {
+                    eprintln!("❌ Failed to flush spill index: {}", e);
+                }
+            }),
+            type_breakdown: self.type_breakdown(),
+            largest_files,
+            largest_dirs,
+            disk_info,
+            errors: self.error_log(),
+            imported: false,
+        }
+    }
+
+    fn spill_index_path(&self) -> Option<String> {
+        self.spill.as_ref().map(|spill| spill.1.path().to_string_lossy().to_string())
+    }
+
+    #[cfg(unix)]
+    fn is_visited_inode(&self, metadata: &fs::Metadata) -> bool {
         if let Ok(visited) = self.visited_inodes.lock() {
-            visited.contains(&inode)
+            visited.contains(&(metadata.dev(), metadata.ino()))
         } else {
             false
         }
     }
 
     #[cfg(unix)]
-    fn mark_visited_inode(&self, inode: u64) -> bool {
+    fn mark_visited_inode(&self, metadata: &fs::Metadata) -> bool {
         if let Ok(mut visited) = self.visited_inodes.lock() {
-            visited.insert(inode)
+            visited.insert((metadata.dev(), metadata.ino()))
         } else {
             false
         }
@@ -193,17 +751,6 @@ impl ScanState {
         }
     }
 
-    fn should_send_path_update(&self) -> bool {
-        if let Ok(mut counter) = self.path_update_counter.lock() {
-            *counter += 1;
-            if *counter >= PATH_UPDATE_INTERVAL {
-                *counter = 0;
-                return true;
-            }
-        }
-        false
-    }
-
 }
 
 // Get disk space information using sysinfo
@@ -352,7 +899,7 @@ fn is_root_directory(path: &str) -> bool {
         if path.starts_with("/Volumes/") {
             let parts: Vec<&str> = path.split('/').collect();
             // Should be exactly ["", "Volumes", "VolumeName"]
-            if parts.len() == 3 && parts[0] == "" && parts[1] == "Volumes" && !parts[2].is_empty() {
+            if parts.len() == 3 && parts[0].is_empty() && parts[1] == "Volumes" && !parts[2].is_empty() {
                 return true;
             }
         }
@@ -361,8 +908,8 @@ fn is_root_directory(path: &str) -> bool {
         if path.starts_with("/mnt/") || path.starts_with("/media/") {
             let parts: Vec<&str> = path.split('/').collect();
             // Should be exactly ["", "mnt", "diskname"] or ["", "media", "user", "diskname"]
-            if (parts.len() == 3 && parts[0] == "" && parts[1] == "mnt" && !parts[2].is_empty()) ||
-               (parts.len() == 4 && parts[0] == "" && parts[1] == "media" && !parts[2].is_empty() && !parts[3].is_empty()) {
+            if (parts.len() == 3 && parts[0].is_empty() && parts[1] == "mnt" && !parts[2].is_empty()) ||
+               (parts.len() == 4 && parts[0].is_empty() && parts[1] == "media" && !parts[2].is_empty() && !parts[3].is_empty()) {
                 return true;
             }
         }
@@ -381,16 +928,50 @@ fn is_root_directory(path: &str) -> bool {
     }
 }
 
+// Open the spill index up front so a bad location fails the command instead of the scan
+fn open_spill_index(options: &mut ScanOptions) -> Result<Option<(SpillOptions, SpillIndex)>, String> {
+    let Some(spill_options) = options.spill.take() else {
+        return Ok(None);
+    };
+    let index_path = spill_options
+        .index_path
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(spill::default_index_path);
+    let index = SpillIndex::create(&index_path)
+        .map_err(|e| format!("無法建立索引檔 {}: {}", index_path.display(), e))?;
+    Ok(Some((spill_options, index)))
+}
+
 #[tauri::command]
-async fn scan_directory_streaming(path: String, on_batch: Channel<PartialScanResult>) -> Result<(), String> {
-    let root_path = Path::new(&path);
+async fn scan_directory_streaming(
+    path: String,
+    options: Option<ScanOptions>,
+    on_batch: Channel<ScanMessage>,
+) -> Result<(), String> {
+    // Paths built from scanned names may carry escaped bytes
+    let path_buf = osname::decode_path(&path);
+    let root_path = path_buf.as_path();
     if !root_path.exists() {
         return Err("路徑不存在".to_string());
     }
 
+    let mut options = options.unwrap_or_default();
+    if options.estimate.is_some() && options.spill.is_some() {
+        return Err("估計模式無法與索引檔模式同時使用".to_string());
+    }
+    if options.checkpoint && (options.estimate.is_some() || options.spill.is_some()) {
+        return Err("檢查點無法與估計模式或索引檔模式同時使用".to_string());
+    }
+    let announced = options.clone();
+    let spill = open_spill_index(&mut options)?;
+
     // Spawn background scanning task
     std::thread::spawn(move || {
-        let state = ScanState::new();
+        if options.limits.low_priority {
+            limits::lower_thread_priority();
+        }
+        let state = ScanState::new(&path_buf, &options, spill);
 
         // Register the current scan state for cancellation
         let global_state = CURRENT_SCAN_STATE.get_or_init(|| Arc::new(Mutex::new(None)));
@@ -398,7 +979,7 @@ async fn scan_directory_streaming(path: String, on_batch: Channel<PartialScanRes
             *current = Some(state.clone());
         }
 
-        let root_path = Path::new(&path);
+        let root_path = path_buf.as_path();
 
         // Get disk info for root directory scans
         let is_root = is_root_directory(&path);
@@ -409,29 +990,61 @@ async fn scan_directory_streaming(path: String, on_batch: Channel<PartialScanRes
             None
         };
 
-        // Send initial message with disk_info for progress calculation
-        if disk_info.is_some() {
-            let initial_payload = PartialScanResult {
-                nodes: Vec::new(),
-                compact_nodes: Vec::new(),
-                total_scanned: 0,
-                total_size: 0,
-                is_complete: false,
-                root_node: None,
-                compact_root: None,
-                disk_info: disk_info.clone(),
-                current_path: Some(path.clone()),
-            };
-            let _ = on_batch.send(initial_payload);
-        }
-
-        match scan_directory_recursive(root_path, &on_batch, &state, root_path) {
-            Ok(root_node) => {
-                let limited_root = build_limited_depth_node(&root_node, MAX_DEPTH);
-                send_final_batch(&on_batch, &state, limited_root, disk_info);
+        // Announce the scan with what it's expected to find, for progress and ETA
+        let target = progress::target_for(root_path, disk_info.as_ref().map(|info| info.used_space));
+        if let Some(target) = target {
+            state.set_progress_target(target);
+        }
+        let started = ScanEvent::Started {
+            root_path: path.clone(),
+            disk_info: disk_info.clone(),
+            target,
+            resumed: state.checkpoint.as_ref().and_then(|checkpoint| checkpoint.resumed()),
+            options: announced,
+        };
+        let _ = on_batch.send(state.message(started));
+
+        if options.strategy == ScanStrategy::QuickOverview {
+            let budget = Duration::from_millis(options.overview_budget_ms.unwrap_or(overview::DEFAULT_BUDGET_MS));
+            let overview = overview::run(root_path, budget, &state.cancelled, |overview| {
+                let _ = on_batch.send(state.message(ScanEvent::Overview { overview: overview.clone() }));
+            });
+            if let Some(overview) = overview.filter(|overview| overview.total_size > 0) {
+                state.set_progress_target(ProgressTarget { entries: None, bytes: Some(overview.total_size), basis: TargetBasis::Overview });
+            }
+        }
+
+        let root_name = root_path.file_name().map(osname::encode).unwrap_or_default();
+        let ticker = ProgressTicker::start(&on_batch, &state);
+        let result = scan_tree(root_path, &root_name, NO_NODE, 0, &on_batch, &state, root_path);
+        ticker.stop();
+        state.close_checkpoint(result.is_ok());
+        match result {
+            Ok(root_id) => {
+                let refine = state.will_refine();
+                send_completion(&on_batch, &state, root_id, disk_info.clone());
+                retain_scan(state.retained(disk_info.clone()));
+
+                // The estimate is usable right away; exact values replace it once refined
+                if refine {
+                    let ticker = ProgressTicker::start(&on_batch, &state);
+                    let refined = refine_estimates(&on_batch, &state, root_path, root_id);
+                    ticker.stop();
+                    if refined {
+                        send_completion(&on_batch, &state, root_id, disk_info.clone());
+                        retain_scan(state.retained(disk_info));
+                    }
+                }
+
+                // Exact totals become the progress target of the next scan of this path
+                if !state.is_cancelled() && state.limiter.stopped().is_none() && state.tree.lock().unwrap().estimate(root_id).is_none() {
+                    let (total_scanned, total_size) = state.get_stats();
+                    progress::remember(root_path, total_scanned, total_size);
+                }
             }
             Err(e) => {
                 eprintln!("Scan failed: {}", e);
+                send_failure(&on_batch, &state, &path, e);
             }
         }
 
@@ -446,6 +1059,118 @@ async fn scan_directory_streaming(path: String, on_batch: Channel<PartialScanRes
     Ok(())
 }
 
+// Feeds imported entries into a scan state, streaming depth-1 subtrees like a live scan
+struct ImportTarget<'a> {
+    state: &'a ScanState,
+    channel: &'a Channel<ScanMessage>,
+}
+
+impl import::ImportSink for ImportTarget<'_> {
+    fn add_entry(&mut self, parent: NodeId, name: &str, is_directory: bool, size: u64, meta: EntryMeta) -> Result<NodeId, String> {
+        let state = self.state;
+        state.increment_counter();
+
+        if parent == NO_NODE {
+            let root_path = osname::decode_path(name);
+            let root_name = root_path.file_name().map(osname::encode).unwrap_or(Cow::Borrowed(name));
+            let mut tree = state.tree.lock().unwrap();
+            tree.set_root_path(&root_path);
+            return tree.add_node(NO_NODE, &root_name, true, size, meta);
+        }
+        if is_directory {
+            return state.add_node(parent, name, true, size, meta);
+        }
+
+        let path_str = osname::encode_path(&state.tree.lock().unwrap().path(parent).join(osname::decode(name)));
+        state.add_size(size);
+        state.offer_largest_file(size, &path_str);
+        let category = state.classify_file(Path::new(&path_str), name, size, &path_str);
+        let id = state.add_node(parent, name, false, size, meta.with_category(category))?;
+
+        state.set_current_path(&path_str);
+        Ok(id)
+    }
+
+    fn finish_directory(&mut self, id: NodeId, depth: usize, own_size: u64, own_apparent_size: u64) {
+        let state = self.state;
+        state.tree.lock().unwrap().set_size(id, own_size, own_apparent_size);
+        state.add_size(own_size);
+        state.finish_directory(id, depth);
+
+        if depth == 1 && state.add_compact_to_buffer(id) {
+            flush_subtrees(self.channel, state);
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.state.is_cancelled()
+    }
+}
+
+// Load an `ncdu -o` export or `du -ab` output and stream it to the frontend like a scan
+#[tauri::command]
+async fn import_scan(
+    path: String,
+    format: Option<import::ImportFormat>,
+    options: Option<ScanOptions>,
+    on_batch: Channel<ScanMessage>,
+) -> Result<(), String> {
+    let mut options = options.unwrap_or_default();
+    // Imported files don't exist locally, so there's nothing to sniff
+    options.sniff = SniffMode::Off;
+    let announced = options.clone();
+    let spill = open_spill_index(&mut options)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = ScanState::new(Path::new(&path), &options, spill);
+        let global_state = CURRENT_SCAN_STATE.get_or_init(|| Arc::new(Mutex::new(None)));
+        if let Ok(mut current) = global_state.lock() {
+            *current = Some(state.clone());
+        }
+
+        let started = ScanEvent::Started {
+            root_path: path.clone(),
+            disk_info: None,
+            target: None,
+            resumed: None,
+            options: announced,
+        };
+        let _ = on_batch.send(state.message(started));
+
+        let mut target = ImportTarget { state: &state, channel: &on_batch };
+        let ticker = ProgressTicker::start(&on_batch, &state);
+        let result = import::import(Path::new(&path), format.unwrap_or_default(), &mut target);
+        ticker.stop();
+        match &result {
+            Ok(root_id) => {
+                println!("📥 Imported {} entries from {}", state.get_stats().0, path);
+                send_completion(&on_batch, &state, *root_id, None);
+                retain_scan(RetainedScan { imported: true, ..state.retained(None) });
+            }
+            Err(e) => send_failure(&on_batch, &state, &path, e.clone()),
+        }
+
+        if let Ok(mut current) = global_state.lock() {
+            *current = None;
+        }
+        result.map(|_| ())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// The frontend has processed every subtree batch up to `seq`
+#[tauri::command]
+fn ack_scan_batch(seq: u64) {
+    if let Some(global_state) = CURRENT_SCAN_STATE.get() {
+        if let Ok(current) = global_state.lock() {
+            if let Some(state) = &*current {
+                state.acknowledge_batch(seq);
+            }
+        }
+    }
+}
+
 #[tauri::command]
 fn cancel_scan() -> Result<(), String> {
     if let Some(global_state) = CURRENT_SCAN_STATE.get() {
@@ -471,317 +1196,549 @@ struct DeletionProgress {
     deleted_count: Option<usize>,
 }
 
+// An imported listing describes another machine or an older state of this one
+const IMPORTED_DELETE_ERROR: &str = "匯入的清單不代表本機檔案，無法刪除";
+
 #[tauri::command]
 async fn delete_files_batch(paths: Vec<String>, on_progress: Channel<DeletionProgress>) -> Result<(), String> {
-    let total = paths.len();
+    if let Ok(scan) = retained_scan() {
+        if scan.imported {
+            let root = scan.tree.lock().map_err(|e| e.to_string())?.path(ROOT_NODE);
+            if paths.iter().any(|path| osname::decode_path(path).starts_with(&root)) {
+                return Err(IMPORTED_DELETE_ERROR.to_string());
+            }
+        }
+    }
 
     // Spawn background deletion task
-    std::thread::spawn(move || {
-        let mut deleted_count = 0usize;
-        let mut deleted_size = 0u64;
-        let mut failed_paths = Vec::new();
-
-        for (index, path) in paths.iter().enumerate() {
-            // Normalize path separators (replace backslash with forward slash)
-            let normalized_path = path.replace("\\", "/");
-            let path_obj = Path::new(&normalized_path);
-            let current_path = normalized_path.clone();
-
-            // Calculate size before deletion
-            let size_before = if path_obj.exists() {
-                if path_obj.is_file() {
-                    path_obj.size_on_disk().unwrap_or(0)
-                } else if path_obj.is_dir() {
-                    calculate_dir_size(path_obj)
+    std::thread::spawn(move || delete_paths(paths, on_progress));
+
+    Ok(())
+}
+
+// Delete nodes of the last scan by ID; paths are rebuilt from the retained tree
+#[tauri::command]
+async fn delete_nodes_batch(node_ids: Vec<NodeId>, on_progress: Channel<DeletionProgress>) -> Result<(), String> {
+    let scan = retained_scan()?;
+    if scan.imported {
+        return Err(IMPORTED_DELETE_ERROR.to_string());
+    }
+    let paths = {
+        let tree = scan.tree.lock().map_err(|e| e.to_string())?;
+        node_ids
+            .iter()
+            .map(|&id| {
+                if tree.contains(id) {
+                    Ok(osname::encode_path(&tree.path(id)))
                 } else {
-                    0
+                    Err(format!("Unknown node: {}", id))
                 }
-            } else {
-                0
-            };
-
-            // Send progress update
-            let progress = DeletionProgress {
-                current: index + 1,
-                total,
-                current_path: current_path.clone(),
-                success: false,
-                completed: false,
-                deleted_size: None,
-                deleted_count: None,
-            };
-            let _ = on_progress.send(progress);
-
-            // Attempt deletion
-            let deletion_result = if path_obj.is_file() {
-                fs::remove_file(path_obj)
-            } else if path_obj.is_dir() {
-                fs::remove_dir_all(path_obj)
-            } else {
-                Err(std::io::Error::new(std::io::ErrorKind::Other, "Not a file or directory"))
-            };
-
-            match deletion_result {
-                Ok(_) => {
-                    deleted_count += 1;
-                    deleted_size += size_before;
-                    println!("✅ Deleted: {} (size: {} bytes)", current_path, size_before);
-                }
-                Err(e) => {
-                    eprintln!("❌ Failed to delete {}: {}", current_path, e);
-                    failed_paths.push(current_path);
-                }
-            }
-        }
+            })
+            .collect::<Result<Vec<_>, String>>()?
+    };
 
-        // Send completion message
-        let all_success = failed_paths.is_empty();
-        let completion = DeletionProgress {
-            current: total,
-            total,
-            current_path: if all_success {
-                String::from("完成")
-            } else {
-                format!("完成 ({} 個失敗)", failed_paths.len())
-            },
-            success: all_success,
-            completed: true,
-            deleted_size: Some(deleted_size),
-            deleted_count: Some(deleted_count),
-        };
-        let _ = on_progress.send(completion);
-    });
+    std::thread::spawn(move || delete_paths(paths, on_progress));
 
     Ok(())
 }
 
-// Helper function to calculate directory size recursively
-fn calculate_dir_size(path: &Path) -> u64 {
-    let mut total_size = 0u64;
-
-    if let Ok(entries) = fs::read_dir(path) {
-        for entry in entries.flatten() {
-            let entry_path = entry.path();
-            if entry_path.is_file() {
-                total_size += entry_path.size_on_disk().unwrap_or(0);
-            } else if entry_path.is_dir() {
-                total_size += calculate_dir_size(&entry_path);
+fn delete_paths(paths: Vec<String>, on_progress: Channel<DeletionProgress>) {
+    let total = paths.len();
+    let mut deleted_count = 0usize;
+    let mut deleted_size = 0u64;
+    let mut failed_paths = Vec::new();
+
+    for (index, path) in paths.iter().enumerate() {
+        let path_buf = deletion_target(path);
+        let path_obj = path_buf.as_path();
+        let current_path = osname::encode_path(path_obj);
+
+        // Send progress update
+        let progress = DeletionProgress {
+            current: index + 1,
+            total,
+            current_path: current_path.clone(),
+            success: false,
+            completed: false,
+            deleted_size: None,
+            deleted_count: None,
+        };
+        let _ = on_progress.send(progress);
+
+        match delete_path(path_obj) {
+            Ok(size_before) => {
+                deleted_count += 1;
+                deleted_size += size_before;
+                forget_deleted_path(path_obj);
+                println!("✅ Deleted: {} (size: {} bytes)", current_path, size_before);
+            }
+            Err(e) => {
+                eprintln!("❌ Failed to delete {}: {}", current_path, e);
+                failed_paths.push(current_path);
             }
         }
     }
 
-    total_size
+    // Send completion message
+    let all_success = failed_paths.is_empty();
+    let completion = DeletionProgress {
+        current: total,
+        total,
+        current_path: if all_success {
+            String::from("完成")
+        } else {
+            format!("完成 ({} 個失敗)", failed_paths.len())
+        },
+        success: all_success,
+        completed: true,
+        deleted_size: Some(deleted_size),
+        deleted_count: Some(deleted_count),
+    };
+    let _ = on_progress.send(completion);
 }
 
-// Convert FileNode to compact format (removes path, uses short keys)
-fn to_compact_node(node: &FileNode) -> CompactFileNode {
-    CompactFileNode {
-        name: node.name.clone(),
-        size: node.size,
-        children: node.children.as_ref().map(|children| {
-            children.iter().map(|child| to_compact_node(child)).collect()
-        }),
-        is_directory: node.is_directory,
+// The path to delete as the UI sent it. Windows paths may use either separator; elsewhere a
+// backslash is part of the name. Names that aren't valid Unicode arrive escaped
+fn deletion_target(path: &str) -> PathBuf {
+    if cfg!(windows) {
+        osname::decode_path(&path.replace('\\', "/"))
+    } else {
+        osname::decode_path(path)
     }
 }
 
-fn send_final_batch(channel: &Channel<PartialScanResult>, state: &ScanState, root_node: FileNode, disk_info: Option<DiskInfo>) {
-    let (total_items, total_size) = state.get_stats();
-    let mut remaining_compact_nodes = state.clear_compact_buffer();
-
-    // Add root-level files as compact nodes (directories were already added during scan)
-    if let Some(ref children) = root_node.children {
-        let root_files: Vec<CompactFileNode> = children
-            .iter()
-            .filter(|child| !child.is_directory)
-            .map(|child| to_compact_node(child))
-            .collect();
+// Delete a file or a whole directory, returning the size it took up
+fn delete_path(path: &Path) -> std::io::Result<u64> {
+    if path.is_file() {
+        let size = path.size_on_disk().unwrap_or(0);
+        fs::remove_file(path)?;
+        Ok(size)
+    } else if path.is_dir() {
+        let size = calculate_dir_size(path);
+        fs::remove_dir_all(path)?;
+        Ok(size)
+    } else {
+        Err(std::io::Error::other("Not a file or directory"))
+    }
+}
 
-        if !root_files.is_empty() {
-            remaining_compact_nodes.extend(root_files);
-        }
+// Keep the retained tree in sync with the disk after a successful deletion
+fn forget_deleted_path(path: &Path) {
+    let Ok(scan) = retained_scan() else {
+        return;
+    };
+    let Ok(mut tree) = scan.tree.lock() else {
+        return;
+    };
+    if let Some(id) = query::resolve_path(&mut tree, scan.spill_index(), path) {
+        tree.remove_node(id);
     }
+}
 
-    // Send remaining compact nodes if any
-    if !remaining_compact_nodes.is_empty() {
-        let batch_payload = PartialScanResult {
-            nodes: Vec::new(),
-            compact_nodes: remaining_compact_nodes,
-            total_scanned: total_items,
-            total_size,
-            is_complete: false,
-            root_node: None,
-            compact_root: None,
-            disk_info: None,
-            current_path: None,
-        };
-        let _ = channel.send(batch_payload);
+// Helper function to calculate directory size
+fn calculate_dir_size(path: &Path) -> u64 {
+    measure_directory(path, || true).size
+}
+
+// Everything below a directory, without adding it to any tree
+#[derive(Default)]
+struct Measured {
+    size: u64,
+    apparent_size: u64,
+    entries: u64,
+    cut_short: bool, // `admit` ended the walk; the totals cover only what was read
+}
+
+// Walks with an explicit stack, so no nesting depth can overflow it; symlinks are counted but
+// not followed, so the walk can't loop. `admit` is asked before every entry and can end the walk.
+fn measure_directory(path: &Path, admit: impl Fn() -> bool) -> Measured {
+    let mut measured = Measured::default();
+    let mut pending = vec![path.to_path_buf()];
+    'walk: while let Some(directory) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&directory) else { continue };
+        for entry in entries.flatten() {
+            if !admit() {
+                measured.cut_short = true;
+                break 'walk;
+            }
+            let Ok(metadata) = entry.metadata() else { continue };
+            measured.entries += 1;
+            if metadata.is_dir() {
+                pending.push(entry.path());
+            } else if metadata.is_file() {
+                measured.size += entry.path().size_on_disk_fast(&metadata).unwrap_or(0);
+                measured.apparent_size += metadata.len();
+            }
+        }
     }
+    measured
+}
 
-    // Send final completion message with root metadata only
-    let root_metadata = FileNode {
-        name: root_node.name.clone(),
-        size: root_node.size,
-        path: root_node.path.clone(),
-        children: Some(Vec::new()),
-        is_directory: true,
+fn scan_summary(state: &ScanState, root_id: NodeId, disk_info: Option<DiskInfo>) -> ScanSummary {
+    let (total_scanned, total_size) = state.get_stats();
+    let (largest_files, largest_dirs) = state.largest_lists();
+    let root = {
+        let tree = state.tree.lock().unwrap();
+        ScanRoot {
+            id: root_id,
+            name: tree.name(root_id).to_string(),
+            path: osname::encode_path(&tree.path(root_id)),
+            size: tree.node(root_id).size,
+        }
     };
 
-    let payload = PartialScanResult {
-        nodes: Vec::new(),
-        compact_nodes: Vec::new(),
-        total_scanned: total_items,
+    ScanSummary {
+        root,
+        total_scanned,
         total_size,
-        is_complete: true,
-        root_node: Some(root_metadata),
-        compact_root: None,
         disk_info,
-        current_path: None,
+        spill_index: state.spill_index_path(),
+        largest_files,
+        largest_dirs,
+        type_breakdown: state.type_breakdown(),
+        errors: state.error_log(),
+        estimate: state.tree.lock().unwrap().estimate(root_id),
+        refining: state.will_refine(),
+        stopped: state.limiter.stopped(),
+    }
+}
+
+// Flush the last subtrees, then report the scan as completed (or cancelled part way)
+fn send_completion(channel: &Channel<ScanMessage>, state: &ScanState, root_id: NodeId, disk_info: Option<DiskInfo>) {
+    // Root-level files go out as subtrees of their own (directories were sent during the scan)
+    {
+        let tree = state.tree.lock().unwrap();
+        for child in tree.children(root_id).filter(|&child| !tree.node(child).is_directory) {
+            state.add_compact_to_buffer(child);
+        }
+    }
+    send_subtrees(channel, state);
+
+    let summary = scan_summary(state, root_id, disk_info);
+    let event = if state.is_cancelled() {
+        ScanEvent::Cancelled { partial: Some(summary) }
+    } else {
+        ScanEvent::Completed { summary }
     };
+    let _ = channel.send(state.message(event));
+}
 
-    let _ = channel.send(payload);
+// The scan root couldn't be read at all
+fn send_failure(channel: &Channel<ScanMessage>, state: &ScanState, path: &str, message: String) {
+    let event = if state.is_cancelled() {
+        ScanEvent::Cancelled { partial: None }
+    } else {
+        ScanEvent::Error {
+            path: path.to_string(),
+            message,
+            fatal: true,
+        }
+    };
+    let _ = channel.send(state.message(event));
 }
 
-fn send_compact_batch(channel: &Channel<PartialScanResult>, state: &ScanState) {
-    let (total_items, total_size) = state.get_stats();
-    let compact_nodes = state.clear_compact_buffer();
-    let current_path = state.get_current_path();
+fn send_subtrees(channel: &Channel<ScanMessage>, state: &ScanState) {
+    let nodes = state.clear_compact_buffer();
+    if nodes.is_empty() {
+        return;
+    }
+    let seq = state.batches_sent.fetch_add(1, Ordering::Relaxed) + 1;
+    let (total_scanned, total_size) = state.get_stats();
+    let _ = channel.send(state.message(ScanEvent::Subtree { seq, nodes, total_scanned, total_size }));
+}
 
-    let payload = PartialScanResult {
-        nodes: Vec::new(),
-        compact_nodes,
-        total_scanned: total_items,
-        total_size,
-        is_complete: false,
-        root_node: None,
-        compact_root: None,
-        disk_info: None,
-        current_path: Some(current_path),
-    };
+// Send a full batch during the scan, unless the frontend is behind: then the buffer keeps
+// growing and goes out as one merged batch once it catches up (or when the scan completes)
+fn flush_subtrees(channel: &Channel<ScanMessage>, state: &ScanState) {
+    if state.batches_in_flight() < MAX_BATCHES_IN_FLIGHT {
+        send_subtrees(channel, state);
+    }
+}
 
-    let _ = channel.send(payload);
+// Sends progress on a timer while a scan runs, so fast disks don't flood IPC and slow ones
+// don't go quiet; it also releases subtree batches that were held back for backpressure
+struct ProgressTicker {
+    stop: Arc<AtomicBool>,
+    handle: Option<std::thread::JoinHandle<()>>,
 }
 
-fn send_path_update(channel: &Channel<PartialScanResult>, state: &ScanState) {
-    let (total_items, total_size) = state.get_stats();
-    let current_path = state.get_current_path();
+impl ProgressTicker {
+    fn start(channel: &Channel<ScanMessage>, state: &ScanState) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (channel, state, stopped) = (channel.clone(), state.clone(), stop.clone());
+        let handle = std::thread::spawn(move || {
+            let mut last_sent = (Instant::now(), None);
+            loop {
+                std::thread::sleep(PROGRESS_INTERVAL);
+                if stopped.load(Ordering::Relaxed) {
+                    break;
+                }
+
+                let stats = Some(state.get_stats());
+                if stats != last_sent.1 || last_sent.0.elapsed() >= HEARTBEAT_INTERVAL {
+                    send_progress(&channel, &state);
+                    last_sent = (Instant::now(), stats);
+                }
+                if state.batch_ready() {
+                    flush_subtrees(&channel, &state);
+                }
+            }
+        });
+        Self { stop, handle: Some(handle) }
+    }
+
+    // Stop before the final events, so no progress arrives after them
+    fn stop(mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
 
-    let payload = PartialScanResult {
-        nodes: Vec::new(),
-        compact_nodes: Vec::new(),
-        total_scanned: total_items,
+fn send_progress(channel: &Channel<ScanMessage>, state: &ScanState) {
+    let (total_scanned, total_size) = state.get_stats();
+    let (largest_files, largest_dirs) = state.take_largest_updates();
+    let event = ScanEvent::Progress {
+        total_scanned,
         total_size,
-        is_complete: false,
-        root_node: None,
-        compact_root: None,
-        disk_info: None,
-        current_path: Some(current_path),
+        current_path: state.get_current_path(),
+        largest_files,
+        largest_dirs,
+        directories: state.take_directory_updates(),
+        throughput: state.throughput(total_scanned, total_size),
     };
+    let _ = channel.send(state.message(event));
+}
 
-    let _ = channel.send(payload);
+// Record an unreadable path and tell the frontend, while the error log still lists entries
+fn report_error(channel: &Channel<ScanMessage>, state: &ScanState, path: &str, error: impl ToString) {
+    let message = error.to_string();
+    if state.record_error(path, &message) {
+        let event = ScanEvent::Error {
+            path: path.to_string(),
+            message,
+            fatal: false,
+        };
+        let _ = channel.send(state.message(event));
+    }
 }
 
-fn build_limited_depth_node(node: &FileNode, max_depth: usize) -> FileNode {
-    build_limited_depth_node_recursive(node, 0, max_depth)
+// A file scanned but not linked into the tree yet
+struct NewFile {
+    name: String,
+    size: u64,
+    meta: EntryMeta,
 }
 
-fn build_limited_depth_node_recursive(node: &FileNode, current_depth: usize, max_depth: usize) -> FileNode {
-    if current_depth >= max_depth {
-        return FileNode {
-            name: node.name.clone(),
-            size: node.size,
-            path: node.path.clone(),
-            children: if node.is_directory { Some(Vec::new()) } else { None },
-            is_directory: node.is_directory,
-        };
+// A directory whose entries are still being scanned. Every job over a batch of its entries and
+// every subdirectory still open holds one count; whoever drops the last one completes it, then
+// releases its own count on the directory above.
+struct PendingDirectory {
+    id: NodeId,
+    parent: NodeId,
+    depth: usize,
+    canonical: Option<PathBuf>, // Held in the recursion stack until the directory completes
+    scanned: bool, // Read from disk, so it goes into the checkpoint; grafted ones already are
+    pending: AtomicUsize,
+    above: Option<Arc<PendingDirectory>>,
+}
+
+// One walk over the disk. Directories don't recurse: their entries are handed out in jobs on
+// the pool of their device, so no thread's stack grows with the depth of the tree, and the
+// caller waits for the last job instead of joining every level.
+#[derive(Clone)]
+struct Walk {
+    channel: Channel<ScanMessage>,
+    state: ScanState,
+    root_path: Arc<PathBuf>,
+    jobs: Arc<(Mutex<usize>, Condvar)>,
+}
+
+impl Walk {
+    fn new(channel: &Channel<ScanMessage>, state: &ScanState, root_path: &Path) -> Self {
+        Self {
+            channel: channel.clone(),
+            state: state.clone(),
+            root_path: Arc::new(root_path.to_path_buf()),
+            jobs: Arc::new((Mutex::new(0), Condvar::new())),
+        }
     }
 
-    let limited_children = node.children.as_ref().map(|children| {
-        children
-            .iter()
-            .map(|child| build_limited_depth_node_recursive(child, current_depth + 1, max_depth))
-            .collect()
-    });
+    fn spawn(&self, device: &DevicePool, job: impl FnOnce(&Walk) + Send + 'static) {
+        *self.jobs.0.lock().unwrap() += 1;
+        let walk = self.clone();
+        device.spawn(move || {
+            job(&walk);
+            let (jobs, done) = &*walk.jobs;
+            let mut jobs = jobs.lock().unwrap();
+            *jobs -= 1;
+            if *jobs == 0 {
+                done.notify_all();
+            }
+        });
+    }
 
-    FileNode {
-        name: node.name.clone(),
-        size: node.size,
-        path: node.path.clone(),
-        children: limited_children,
-        is_directory: node.is_directory,
+    // Until every job spawned so far (and every job they spawned) has run
+    fn wait(&self) {
+        let (jobs, done) = &*self.jobs;
+        let mut jobs = jobs.lock().unwrap();
+        while *jobs > 0 {
+            jobs = done.wait(jobs).unwrap();
+        }
     }
+
+    // Scan `entries` into `parent` a batch per job. Jobs run newest first, so the batches are
+    // queued back to front to keep their order (inode order on spinning disks).
+    fn spawn_entries(&self, device: &DevicePool, entries: Vec<fs::DirEntry>, parent: NodeId, depth: usize, directory: Option<&Arc<PendingDirectory>>) {
+        let mut batches: Vec<Vec<fs::DirEntry>> = Vec::new();
+        let mut entries = entries.into_iter().peekable();
+        while entries.peek().is_some() {
+            batches.push(entries.by_ref().take(ENTRIES_PER_JOB).collect());
+        }
+        for batch in batches.into_iter().rev() {
+            let directory = directory.cloned();
+            if let Some(directory) = &directory {
+                directory.pending.fetch_add(1, Ordering::AcqRel);
+            }
+            self.spawn(device, move |walk| {
+                let mut files = Vec::new();
+                for entry in batch {
+                    let entry_name = entry.file_name();
+                    let _ = scan_entry(walk, &entry.path(), &osname::encode(&entry_name), parent, depth, directory.as_ref(), Some(&mut files));
+                }
+                walk.link_files(parent, files);
+                if let Some(directory) = directory {
+                    walk.release(directory);
+                }
+            });
+        }
+    }
+
+    // Link a job's files into the tree, reporting their directory if they didn't all fit
+    fn link_files(&self, parent: NodeId, files: Vec<NewFile>) {
+        if let Err(e) = self.state.add_files(parent, files) {
+            let path = osname::encode_path(&self.state.tree.lock().unwrap().path(parent));
+            report_error(&self.channel, &self.state, &path, e);
+        }
+    }
+
+    // Start tracking a directory whose entries are about to be handed out; the caller holds
+    // the first count and releases it once everything is queued
+    fn open(&self, id: NodeId, parent: NodeId, depth: usize, canonical: Option<PathBuf>, scanned: bool, above: Option<&Arc<PendingDirectory>>) -> Arc<PendingDirectory> {
+        if let Some(above) = above {
+            above.pending.fetch_add(1, Ordering::AcqRel);
+        }
+        Arc::new(PendingDirectory { id, parent, depth, canonical, scanned, pending: AtomicUsize::new(1), above: above.cloned() })
+    }
+
+    fn release(&self, mut directory: Arc<PendingDirectory>) {
+        while directory.pending.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.complete(&directory);
+            match directory.above.clone() {
+                Some(above) => directory = above,
+                None => return,
+            }
+        }
+    }
+
+    fn complete(&self, directory: &PendingDirectory) {
+        let state = &self.state;
+        if let Some(canonical) = &directory.canonical {
+            state.pop_from_recursion_stack(canonical);
+        }
+        state.finish_directory(directory.id, directory.depth);
+        if directory.scanned {
+            state.checkpoint_directory(directory.id, None);
+        }
+
+        // Each first-level directory is sent once, with its whole subtree, when it completes;
+        // below that the UI only sees the live directory updates, down to the stream depth
+        if directory.parent == ROOT_NODE && state.add_compact_to_buffer(directory.id) {
+            flush_subtrees(&self.channel, state);
+        }
+    }
+}
+
+// Scan `path` and everything below it into the shared tree under `parent`; returns once the
+// whole subtree is in, with its node ID
+fn scan_tree(path: &Path, name: &str, parent: NodeId, depth: usize, channel: &Channel<ScanMessage>, state: &ScanState, root_path: &Path) -> Result<NodeId, String> {
+    let walk = Walk::new(channel, state, root_path);
+    let result = scan_entry(&walk, path, name, parent, depth, None, None);
+    walk.wait();
+    result
 }
 
-fn scan_directory_recursive(
+// Scan one entry into the shared tree under `parent` and return its node ID. A directory's
+// entries are queued as jobs and it completes once they (and its subdirectories) are done.
+// Given `files`, a regular file is left there for the caller to link with the rest of its
+// batch, and NO_NODE is returned for it.
+fn scan_entry(
+    walk: &Walk,
     path: &Path,
-    channel: &Channel<PartialScanResult>,
-    state: &ScanState,
-    root_path: &Path,
-) -> Result<FileNode, String> {
+    name: &str,
+    parent: NodeId,
+    depth: usize,
+    above: Option<&Arc<PendingDirectory>>,
+    files: Option<&mut Vec<NewFile>>,
+) -> Result<NodeId, String> {
+    let (channel, state, root_path) = (&walk.channel, &walk.state, walk.root_path.as_path());
     // Check if scan has been cancelled
     if state.is_cancelled() {
         return Err("Scan cancelled".to_string());
     }
+    // Past a scan limit nothing more is read; what was found so far stays as the result, and
+    // the directory that lost this entry is flagged as partial
+    if !state.limiter.admit_entry() {
+        // At the scan root that leaves an empty result, partial like any other
+        if parent == NO_NODE {
+            let id = state.add_node(NO_NODE, name, path.is_dir(), 0, EntryMeta::default())?;
+            state.tree.lock().unwrap().mark_partial(id);
+            return Ok(id);
+        }
+        state.tree.lock().unwrap().mark_partial(parent);
+        return Err("Scan limit reached".to_string());
+    }
 
-    let path_str = path.to_string_lossy().to_string();
+    let path_str = osname::encode_path(path);
 
-    // Update current scanning path
+    // Update current scanning path (sent by the progress ticker)
     state.set_current_path(&path_str);
 
-    // Send path update if interval reached
-    if state.should_send_path_update() {
-        send_path_update(channel, state);
-    }
-
     // Prevent scanning above the root path to avoid duplicate counting
     // Use canonicalized paths for accurate comparison
     if let Ok(canonical_root) = fs::canonicalize(root_path) {
         if let Ok(canonical_path) = fs::canonicalize(path) {
             if !canonical_path.starts_with(&canonical_root) {
-                return Ok(FileNode {
-                    name: path.file_name().and_then(|n| n.to_str()).unwrap_or("").to_string(),
-                    size: 0,
-                    path: path_str,
-                    children: None,
-                    is_directory: false,
-                });
+                return state.add_node(parent, name, false, 0, EntryMeta::default());
             }
         }
     }
-    
+
     // Check for circular path using canonicalized path
     if let Ok(canonical_path) = fs::canonicalize(path) {
         if state.is_in_recursion_stack(&canonical_path) {
-            return Ok(FileNode {
-                name: path.file_name().and_then(|n| n.to_str()).unwrap_or("").to_string(),
-                size: 0,
-                path: path_str,
-                children: None,
-                is_directory: false,
-            });
+            return state.add_node(parent, name, false, 0, EntryMeta::default());
         }
     }
-    
-    let metadata = fs::metadata(path).map_err(|e| e.to_string())?;
-    
+
+    let metadata = fs::metadata(path).map_err(|e| {
+        report_error(channel, state, &path_str, &e);
+        e.to_string()
+    })?;
+
     // Check if we've already visited this inode (prevents symlink loops and hard link duplicates)
     // Only use inode tracking on Unix systems
     #[cfg(unix)]
     {
-        let inode = metadata.ino();
-        if state.is_visited_inode(inode) {
-            return Ok(FileNode {
-                name: path.file_name().and_then(|n| n.to_str()).unwrap_or("").to_string(),
-                size: 0,
-                path: path_str,
-                children: None,
-                is_directory: false,
-            });
+        if state.tracks_inode(&metadata) {
+            if state.is_visited_inode(&metadata) {
+                return state.add_node(parent, name, false, 0, EntryMeta::default());
+            }
+            state.mark_visited_inode(&metadata);
         }
-        state.mark_visited_inode(inode);
     }
-    
-    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("").to_string();
+
     state.increment_counter();
 
     // Handle symlinks by following them
@@ -793,56 +1750,35 @@ fn scan_directory_recursive(
                     // Use filesize to get actual disk usage for symlinked files
                     let file_size = target_path.size_on_disk().unwrap_or(0);
                     state.add_size(file_size);
-                    
-                    return Ok(FileNode {
-                        name,
-                        size: file_size,
-                        path: path_str,
-                        children: None,
-                        is_directory: false,
-                    });
+                    state.offer_largest_file(file_size, &path_str);
+                    let category = state.classify_file(&target_path, name, file_size, &path_str);
+
+                    let meta = EntryMeta::from_metadata(&target_metadata).with_category(category);
+                    return state.add_node(parent, name, false, file_size, meta);
                 } else if target_metadata.is_dir() {
                     // For directory symlinks, check if target is above root path or in recursion stack
                     if let Ok(canonical_root) = fs::canonicalize(root_path) {
                         if let Ok(canonical_target) = fs::canonicalize(&target_path) {
                             if !canonical_target.starts_with(&canonical_root) {
-                                return Ok(FileNode {
-                                    name,
-                                    size: 0,
-                                    path: path_str,
-                                    children: None,
-                                    is_directory: false,
-                                });
+                                return state.add_node(parent, name, false, 0, EntryMeta::default());
                             }
                         }
                     }
-                    
+
                     if let Ok(canonical_target) = fs::canonicalize(&target_path) {
                         if state.is_in_recursion_stack(&canonical_target) {
-                            return Ok(FileNode {
-                                name,
-                                size: 0,
-                                path: path_str,
-                                children: None,
-                                is_directory: false,
-                            });
+                            return state.add_node(parent, name, false, 0, EntryMeta::default());
                         }
                     }
-                    
-                    // Safe to scan the target directory
-                    return scan_directory_recursive(&target_path, channel, state, root_path);
+
+                    // Safe to scan the target directory (keeping the link's own name in the tree)
+                    return scan_entry(walk, &target_path, name, parent, depth, above, files);
                 }
             }
         }
-        
+
         // If we can't follow the symlink, return size 0
-        return Ok(FileNode {
-            name,
-            size: 0,
-            path: path_str,
-            children: None,
-            is_directory: false,
-        });
+        return state.add_node(parent, name, false, 0, EntryMeta::default());
     }
 
     if metadata.is_file() {
@@ -850,83 +1786,417 @@ fn scan_directory_recursive(
         let file_size = path.size_on_disk().unwrap_or(0);
 
         state.add_size(file_size);
+        state.offer_largest_file(file_size, &path_str);
+        let category = state.classify_file(path, name, file_size, &path_str);
 
         // Don't add files to batch buffer - only send directories to reduce IPC load
-        let node = FileNode {
-            name,
-            size: file_size,
-            path: path_str,
-            children: None,
-            is_directory: false,
+        let meta = EntryMeta::from_metadata(&metadata).with_category(category);
+        let Some(files) = files else {
+            return state.add_node(parent, name, false, file_size, meta);
         };
+        files.push(NewFile { name: name.to_string(), size: file_size, meta });
+        return Ok(NO_NODE);
+    }
+
+    // Register the directory first so its children can link to it
+    let dir_id = state.add_node(parent, name, true, 0, EntryMeta::from_metadata(&metadata))?;
+
+    // Past the depth limit the contents are only measured, and the directory flagged as truncated
+    if depth >= state.max_depth {
+        let measured = measure_directory(path, || !state.is_cancelled() && state.limiter.admit_entry());
+        state.add_to_counter(measured.entries);
+        state.add_size(measured.size);
+        {
+            let mut tree = state.tree.lock().unwrap();
+            tree.set_size(dir_id, measured.size, measured.apparent_size);
+            tree.mark_truncated(dir_id, measured.entries);
+            if measured.cut_short {
+                tree.mark_partial(dir_id);
+            }
+        }
+        state.finish_directory(dir_id, depth);
+        state.checkpoint_directory(dir_id, Some(measured.entries));
+        if parent == ROOT_NODE && state.add_compact_to_buffer(dir_id) {
+            flush_subtrees(channel, state);
+        }
+        return Ok(dir_id);
+    }
 
-        return Ok(node);
+    // Completed by an interrupted earlier scan: rebuilt from its checkpoint without touching the disk
+    if let Some(record) = state.resume_record(dir_id) {
+        state.watch_directory(dir_id, parent, depth);
+        let directory = walk.open(dir_id, parent, depth, None, false, above);
+        graft_directory(walk, record, &directory, path);
+        walk.release(directory);
+        return Ok(dir_id);
     }
 
-    // Scan directory with parallel processing
-    if let Ok(entries) = fs::read_dir(path) {
-        // Add current directory to recursion stack
-        if let Ok(canonical_path) = fs::canonicalize(path) {
-            state.push_to_recursion_stack(&canonical_path);
+    if !state.limiter.admit_operation() {
+        state.tree.lock().unwrap().mark_partial(dir_id);
+        state.finish_directory(dir_id, depth);
+        return Ok(dir_id);
+    }
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            report_error(channel, state, &path_str, &e);
+            return Ok(dir_id);
         }
+    };
+    state.open_directory(dir_id, parent, depth);
 
-        let entries_vec: Vec<_> = entries.flatten().collect();
+    // In the recursion stack until the directory completes, so links back into it are caught
+    let canonical = fs::canonicalize(path).ok();
+    if let Some(canonical) = &canonical {
+        state.push_to_recursion_stack(canonical);
+    }
+    let directory = walk.open(dir_id, parent, depth, canonical, true, above);
 
-        // No filtering - scan everything
-        let filtered_entries = entries_vec;
+    let entries_vec: Vec<_> = entries
+        .filter_map(|entry| entry.map_err(|e| report_error(channel, state, &path_str, e)).ok())
+        .collect();
 
-        // For root directory's direct children, send immediate progress updates
-        let is_root_level = path == root_path;
+    // Pick the I/O pool of the device backing this directory
+    let device = state.devices.for_metadata(&metadata);
 
-        let children: Vec<FileNode> = filtered_entries
-            .par_iter()
-            .filter_map(|entry| {
-                // Send progress update before scanning each root-level directory
-                if is_root_level {
-                    send_path_update(channel, state);
-                }
-                scan_directory_recursive(&entry.path(), channel, state, root_path).ok()
-            })
-            .collect();
-        
-        // Remove current directory from recursion stack
-        if let Ok(canonical_path) = fs::canonicalize(path) {
-            state.pop_from_recursion_stack(&canonical_path);
+    // On spinning disks, visit entries in inode order to keep head movement short
+    #[cfg(unix)]
+    let entries_vec = {
+        let mut entries_vec = entries_vec;
+        if device.profile.rotational {
+            entries_vec.sort_unstable_by_key(|entry| entry.ino());
         }
+        entries_vec
+    };
+
+    // Everything, unless estimate mode samples this directory
+    let filtered_entries = state.sample_entries(dir_id, path, depth, entries_vec);
+
+    // Subtrees on other devices queue their entries on their own pools, so disks are scanned in
+    // parallel; children link themselves under dir_id and failed entries are simply left out
+    walk.spawn_entries(&device, filtered_entries, dir_id, depth + 1, Some(&directory));
+    walk.release(directory);
+
+    Ok(dir_id)
+}
 
-        let dir_total_size: u64 = children.iter().map(|c| c.size).sum();
+// Add the children of a checkpointed directory to it, counting every entry as the walk would
+// have; the directory completes once its subdirectories do. Those come from their own records,
+// grafted by jobs of their own; one that is missing or has changed is scanned instead.
+fn graft_directory(walk: &Walk, record: DirectoryRecord, directory: &Arc<PendingDirectory>, path: &Path) {
+    let state = &walk.state;
+    let (id, depth) = (directory.id, directory.depth);
+    let mut files = Vec::new();
+    let mut cut_short = false;
+    for entry in record.entries {
+        // A resumed scan is held to its limits too; only the directories cost a stat
+        let admitted = if entry.is_directory { state.limiter.admit_entry() } else { state.limiter.admit_recorded(1) };
+        if !admitted {
+            cut_short = true;
+            break;
+        }
 
-        // Create directory node for return (with full children tree)
-        let dir_node_with_children = FileNode {
-            name: name.clone(),
-            size: dir_total_size,
-            path: path_str.clone(),
-            children: Some(children),
-            is_directory: true,
+        let entry_path = path.join(osname::decode(&entry.name));
+        if !entry.is_directory {
+            let path_str = osname::encode_path(&entry_path);
+            // Placeholders for loops and already seen inodes carry no metadata and weren't counted
+            if entry.size > 0 || entry.mtime != 0 {
+                state.increment_counter();
+            }
+            state.add_size(entry.size);
+            state.offer_largest_file(entry.size, &path_str);
+            state.count_file_type(entry.category, &entry.name, entry.size, &path_str);
+            let meta = entry.meta();
+            files.push(NewFile { name: entry.name, size: entry.size, meta });
+            continue;
+        }
+
+        // One stat per directory: it must still be there, unchanged, and not reached through another link
+        let Ok(metadata) = fs::metadata(&entry_path) else { continue };
+        #[cfg(unix)]
+        if state.tracks_inode(&metadata) && state.is_visited_inode(&metadata) {
+            files.push(NewFile { name: entry.name, size: 0, meta: EntryMeta::default() });
+            continue;
+        }
+
+        let key = checkpoint::child_key(&record.path, &entry.name);
+        let mtime = EntryMeta::from_metadata(&metadata).mtime;
+        let child_record = state.checkpoint.as_ref().and_then(|checkpoint| checkpoint.take(&key, mtime));
+        let Some(child_record) = child_record else {
+            let _ = scan_entry(walk, &entry_path, &entry.name, id, depth + 1, Some(directory), None);
+            continue;
         };
+        if child_record.hidden_entries.is_some_and(|hidden_entries| !state.limiter.admit_recorded(hidden_entries)) {
+            cut_short = true;
+            break;
+        }
+        #[cfg(unix)]
+        if state.tracks_inode(&metadata) {
+            state.mark_visited_inode(&metadata);
+        }
 
-        // Only send compact nodes for direct children of root (depth 1)
-        // This prevents sending duplicate nested directories
-        if let Some(parent) = path.parent() {
-            if parent == root_path {
-                // This is a direct child of root - send it with full subtree
-                let compact_dir = to_compact_node(&dir_node_with_children);
-                if state.add_compact_to_buffer(compact_dir) {
-                    send_compact_batch(channel, state);
+        state.increment_counter();
+        let Ok(child) = state.add_node(id, &entry.name, true, 0, entry.meta()) else { break };
+        match child_record.hidden_entries {
+            Some(hidden_entries) => {
+                state.add_to_counter(hidden_entries);
+                state.add_size(entry.size);
+                {
+                    let mut tree = state.tree.lock().unwrap();
+                    tree.set_size(child, entry.size, entry.apparent_size);
+                    tree.mark_truncated(child, hidden_entries);
                 }
+                state.finish_directory(child, depth + 1);
+            }
+            None => {
+                state.watch_directory(child, id, depth + 1);
+                let pending = walk.open(child, id, depth + 1, None, false, Some(directory));
+                walk.spawn(&state.devices.for_metadata(&metadata), move |walk| {
+                    graft_directory(walk, child_record, &pending, &entry_path);
+                    walk.release(pending);
+                });
             }
         }
+    }
+    walk.link_files(id, files);
+    if cut_short {
+        state.tree.lock().unwrap().mark_partial(id);
+    }
+}
 
-        Ok(dir_node_with_children)
-    } else {
-        Ok(FileNode {
-            name,
-            size: 0,
-            path: path_str,
-            children: Some(Vec::new()),
-            is_directory: true,
-        })
+// Scan the entries estimate mode skipped, then recompute every size exactly.
+// Returns false if the scan was cancelled part way, leaving the estimate in place.
+fn refine_estimates(channel: &Channel<ScanMessage>, state: &ScanState, root_path: &Path, root_id: NodeId) -> bool {
+    state.refining.store(true, Ordering::Relaxed);
+    let sampled = state.sampled_dirs.lock().map(|mut sampled| std::mem::take(&mut *sampled)).unwrap_or_default();
+    println!("🔬 Refining {} sampled directories", sampled.len());
+
+    for (id, path, depth) in sampled {
+        if state.is_cancelled() {
+            return false;
+        }
+        let scanned: HashSet<String> = {
+            let tree = state.tree.lock().unwrap();
+            tree.children(id).map(|child| tree.name(child).to_string()).collect()
+        };
+        let (Ok(entries), Ok(metadata)) = (fs::read_dir(&path), fs::metadata(&path)) else {
+            continue;
+        };
+        let remaining: Vec<_> = entries
+            .flatten()
+            .filter(|entry| !scanned.contains(osname::encode(&entry.file_name()).as_ref()))
+            .collect();
+
+        let walk = Walk::new(channel, state, root_path);
+        walk.spawn_entries(&state.devices.for_metadata(&metadata), remaining, id, depth + 1, None);
+        walk.wait();
+    }
+    if state.is_cancelled() {
+        return false;
+    }
+
+    // Refined entries were added below extrapolated directories, so rebuild every total bottom-up.
+    // Directories that weren't walked (past the depth limit, or refused by a scan limit) have no
+    // children to add up and keep the size measured for them
+    {
+        let mut tree = state.tree.lock().unwrap();
+        let mut order = vec![root_id];
+        let mut next = 0;
+        while next < order.len() {
+            let id = order[next];
+            next += 1;
+            order.extend(tree.children(id));
+        }
+        order.retain(|&id| tree.node(id).is_directory);
+        for &id in order.iter().rev() {
+            if tree.truncation(id).is_some() || tree.children(id).next().is_none() {
+                continue;
+            }
+            let (size, apparent_size) = tree
+                .children(id)
+                .map(|child| tree.node(child))
+                .fold((0, 0), |(size, apparent), node| (size + node.size, apparent + node.meta.apparent_size));
+            tree.set_size(id, size, apparent_size);
+        }
+        tree.clear_spreads();
+    }
+
+    // Resend the whole first level, replacing what the frontend built from the estimate
+    state.clear_compact_buffer();
+    let tree = state.tree.lock().unwrap();
+    for child in tree.children(root_id).filter(|&child| tree.node(child).is_directory) {
+        state.add_compact_to_buffer(child);
+    }
+    true
+}
+
+// Children of a node in the last scan (sorted, paginated, optionally nested)
+#[tauri::command]
+fn get_node_children(node_id: NodeId, query: Option<ChildrenQuery>) -> Result<ChildrenPage, String> {
+    let scan = retained_scan()?;
+    let mut tree = scan.tree.lock().map_err(|e| e.to_string())?;
+    if !tree.contains(node_id) {
+        return Err(format!("Unknown node: {}", node_id));
+    }
+    query::children_page(&mut tree, scan.spill_index(), node_id, &query.unwrap_or_default())
+}
+
+#[tauri::command]
+fn resolve_node_path(path: String) -> Result<Option<NodeInfo>, String> {
+    let scan = retained_scan()?;
+    let mut tree = scan.tree.lock().map_err(|e| e.to_string())?;
+    let id = query::resolve_path(&mut tree, scan.spill_index(), &osname::decode_path(&path));
+    Ok(id.map(|id| query::node_info(&tree, id)))
+}
+
+// Ancestors from the scan root down to the node's parent, for breadcrumbs
+#[tauri::command]
+fn get_node_ancestors(node_id: NodeId) -> Result<Vec<NodeInfo>, String> {
+    let scan = retained_scan()?;
+    let tree = scan.tree.lock().map_err(|e| e.to_string())?;
+    if !tree.contains(node_id) {
+        return Err(format!("Unknown node: {}", node_id));
+    }
+    Ok(query::ancestors(&tree, node_id))
+}
+
+// Treemap rectangles or sunburst arcs for a node of the last scan (the root by default)
+#[tauri::command]
+fn get_layout(node_id: Option<NodeId>, request: layout::LayoutRequest) -> Result<layout::Layout, String> {
+    let scan = retained_scan()?;
+    let mut tree = scan.tree.lock().map_err(|e| e.to_string())?;
+    let id = node_id.unwrap_or(ROOT_NODE);
+    if !tree.contains(id) {
+        return Err(format!("Unknown node: {}", id));
     }
+    layout::compute(&mut tree, scan.spill_index(), id, &request)
+}
+
+// Bytes and file counts per category and per extension for the last scan
+#[tauri::command]
+fn get_type_breakdown() -> Result<TypeBreakdown, String> {
+    Ok(retained_scan()?.type_breakdown.clone())
+}
+
+// Evaluate a filter expression (e.g. "size>1G ext:mkv,mp4") over the last scan
+#[tauri::command]
+fn filter_scan(expression: String, limit: Option<usize>) -> Result<filter::FilterResult, String> {
+    let filter = filter::Filter::parse(&expression)?;
+    let scan = retained_scan()?;
+    let tree = scan.tree.lock().map_err(|e| e.to_string())?;
+    filter::run(&tree, scan.spill_index(), &filter, limit.unwrap_or(DEFAULT_FILTER_LIMIT))
+}
+
+// Write the last scan (or one subtree of it) to a JSON, CSV or ncdu export file
+#[tauri::command]
+async fn export_scan(path: String, options: Option<export::ExportOptions>) -> Result<export::ExportSummary, String> {
+    let scan = retained_scan()?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let pending = {
+            let tree = scan.tree.lock().map_err(|e| e.to_string())?;
+            export::export(&tree, scan.spill_index(), Path::new(&path), &options.unwrap_or_default())?
+        };
+        // Flushing and renaming don't need the tree, so queries can go on meanwhile
+        pending.finish()
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// Write a self-contained HTML report of the last scan
+#[tauri::command]
+async fn generate_report(path: String, options: Option<report::ReportOptions>) -> Result<(), String> {
+    let scan = retained_scan()?;
+    tauri::async_runtime::spawn_blocking(move || write_report(&scan, Path::new(&path), &options.unwrap_or_default()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// Gather the report under the tree lock, then write the file without it
+fn write_report(scan: &RetainedScan, destination: &Path, options: &report::ReportOptions) -> Result<(), String> {
+    let pending = {
+        let tree = scan.tree.lock().map_err(|e| e.to_string())?;
+        let input = report::ReportInput {
+            tree: &tree,
+            spill_index: scan.spill_index(),
+            largest_files: &scan.largest_files,
+            largest_dirs: &scan.largest_dirs,
+            type_breakdown: &scan.type_breakdown,
+            errors: &scan.errors,
+            disk_info: scan.disk_info.as_ref().map(|disk| report::DiskSummary {
+                total_space: disk.total_space,
+                available_space: disk.available_space,
+                used_space: disk.used_space,
+            }),
+        };
+        report::prepare_report(destination, &input, options)?
+    };
+    pending.finish()
+}
+
+// Draw a treemap or sunburst of a node of the last scan to an SVG or PNG file
+#[tauri::command]
+async fn render_image(path: String, node_id: Option<NodeId>, options: Option<render::RenderOptions>) -> Result<(), String> {
+    let scan = retained_scan()?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let pending = {
+            let mut tree = scan.tree.lock().map_err(|e| e.to_string())?;
+            let id = node_id.unwrap_or(ROOT_NODE);
+            if !tree.contains(id) {
+                return Err(format!("Unknown node: {}", id));
+            }
+            render::render(&mut tree, scan.spill_index(), id, Path::new(&path), &options.unwrap_or_default())?
+        };
+        // Drawing and encoding don't need the tree, so queries can go on meanwhile
+        pending.finish()
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// Scan a directory (or load an ncdu / du file) to completion without a window, for the CLI
+fn run_headless(path: &str, import_format: Option<import::ImportFormat>, limits: ScanLimits, checkpoint: bool) -> Result<RetainedScan, String> {
+    let channel: Channel<ScanMessage> = Channel::new(|_| Ok(()));
+    let root_path = Path::new(path);
+    if !root_path.exists() {
+        return Err("路徑不存在".to_string());
+    }
+
+    let options = ScanOptions {
+        limits,
+        checkpoint: checkpoint && import_format.is_none(),
+        ..ScanOptions::default()
+    };
+    if options.limits.low_priority {
+        limits::lower_thread_priority();
+    }
+    let state = ScanState::new(root_path, &options, None);
+
+    match import_format {
+        Some(format) => {
+            import::import(root_path, format, &mut ImportTarget { state: &state, channel: &channel })?;
+            Ok(RetainedScan { imported: true, ..state.retained(None) })
+        }
+        None => {
+            let root_name = root_path.file_name().map(osname::encode).unwrap_or_default();
+            let result = scan_tree(root_path, &root_name, NO_NODE, 0, &channel, &state, root_path);
+            state.close_checkpoint(result.is_ok());
+            result?;
+            if let Some(reason) = state.limiter.stopped() {
+                let (total_scanned, total_size) = state.get_stats();
+                println!("⚠️ Scan stopped early ({}): partial result of {} entries, {} bytes", reason.as_str(), total_scanned, total_size);
+            }
+            Ok(state.retained(get_disk_info(root_path)))
+        }
+    }
+}
+
+// Drill into a spilled directory: read its children back from the last scan's spill index
+#[tauri::command]
+fn read_spilled_children(offset: u64) -> Result<Vec<spill::SpillEntry>, String> {
+    let scan = retained_scan()?;
+    let index_path = scan.spill_index().ok_or("此掃描沒有索引檔")?;
+    spill::read_record(index_path, offset).map_err(|e| e.to_string())
 }
 
 async fn update(app: tauri::AppHandle) -> tauri_plugin_updater::Result<()> {
@@ -954,6 +2224,12 @@ async fn update(app: tauri::AppHandle) -> tauri_plugin_updater::Result<()> {
 }
 
 fn main() {
+    // Subcommands like `storviz report <path>` run without opening a window
+    if let Some(code) = cli::run() {
+        release_scans();
+        std::process::exit(code);
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_opener::init())
@@ -966,7 +2242,131 @@ fn main() {
             });
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![scan_directory_streaming, cancel_scan, delete_files_batch])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .invoke_handler(tauri::generate_handler![
+            scan_directory_streaming,
+            cancel_scan,
+            ack_scan_batch,
+            delete_files_batch,
+            delete_nodes_batch,
+            read_spilled_children,
+            get_node_children,
+            resolve_node_path,
+            get_node_ancestors,
+            get_layout,
+            filter_scan,
+            export_scan,
+            import_scan,
+            generate_report,
+            render_image,
+            get_type_breakdown
+        ])
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|_app, event| {
+            if let tauri::RunEvent::Exit = event {
+                release_scans();
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deep_trees_scan_without_deep_stacks() {
+        let root = std::env::temp_dir().join(format!("storviz-deep-{}", std::process::id()));
+        let levels = MAX_DEPTH_LIMIT - 1;
+        let deepest = (0..levels).fold(root.clone(), |path, _| path.join("d"));
+        fs::create_dir_all(&deepest).unwrap();
+        fs::write(deepest.join("f"), [0u8; 100]).unwrap();
+
+        let options = ScanOptions { max_depth: Some(MAX_DEPTH_LIMIT), ..ScanOptions::default() };
+        let state = ScanState::new(&root, &options, None);
+        let channel: Channel<ScanMessage> = Channel::new(|_| Ok(()));
+        // The caller only waits, so a small stack is enough however deep the tree goes
+        let scanned = std::thread::Builder::new()
+            .stack_size(256 * 1024)
+            .spawn({
+                let (root, state) = (root.clone(), state.clone());
+                move || scan_tree(&root, "root", NO_NODE, 0, &channel, &state, &root)
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        let _ = fs::remove_dir_all(&root);
+
+        let id = scanned.unwrap();
+        let tree = state.tree.lock().unwrap();
+        assert_eq!(state.get_stats().0, levels as u64 + 2); // The root, every level and the file
+        assert!(tree.node(id).size > 0);
+        assert_eq!(tree.node(id).size, state.get_stats().1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn nested_files_delete_through_the_paths_the_ui_builds() {
+        let root = std::env::temp_dir().join(format!("storviz-delete-{}", std::process::id()));
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("sub").join("a\\b.txt"), b"gone").unwrap();
+        fs::write(root.join("sub").join("b.txt"), b"kept").unwrap();
+
+        // As joinNodePath in src/lib/nodePath.ts builds it below a Unix root
+        let path = format!("{}/sub/a\\b.txt", root.display());
+        delete_paths(vec![path], Channel::new(|_| Ok(())));
+        let deleted = !root.join("sub").join("a\\b.txt").exists();
+        let kept = root.join("sub").join("b.txt").exists();
+        let _ = fs::remove_dir_all(&root);
+        assert!(deleted && kept);
+    }
+
+    #[test]
+    fn refined_estimates_keep_directories_past_the_depth_limit() {
+        let root = std::env::temp_dir().join(format!("storviz-refine-{}", std::process::id()));
+        fs::create_dir_all(root.join("wide")).unwrap();
+        fs::create_dir_all(root.join("deep").join("a").join("b")).unwrap();
+        for i in 0..40 {
+            fs::write(root.join("wide").join(format!("f{}", i)), vec![0u8; 4096 * (i % 7 + 1)]).unwrap();
+        }
+        fs::write(root.join("deep").join("a").join("b").join("f"), vec![0u8; 65536]).unwrap();
+        let on_disk: u64 = walkdir::WalkDir::new(&root)
+            .into_iter()
+            .flatten()
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| entry.path().size_on_disk().unwrap())
+            .sum();
+
+        let options = ScanOptions {
+            max_depth: Some(2),
+            estimate: Some(EstimateOptions { threshold: 10, sample_size: 5, refine: true }),
+            ..ScanOptions::default()
+        };
+        let state = ScanState::new(&root, &options, None);
+        let channel: Channel<ScanMessage> = Channel::new(|_| Ok(()));
+        let id = scan_tree(&root, "root", NO_NODE, 0, &channel, &state, &root).unwrap();
+        assert!(state.will_refine());
+        assert!(refine_estimates(&channel, &state, &root, id));
+        let _ = fs::remove_dir_all(&root);
+
+        let tree = state.tree.lock().unwrap();
+        let deep = tree.find_child(id, "deep").unwrap();
+        let truncated = tree.find_child(deep, "a").unwrap();
+        assert!(tree.truncation(truncated).is_some());
+        assert!(tree.node(truncated).size >= 65536);
+        assert_eq!(tree.node(id).size, on_disk);
+    }
+
+    #[test]
+    fn limits_hit_at_the_root_leave_a_partial_result() {
+        let root = std::env::temp_dir();
+        let options = ScanOptions { limits: ScanLimits { max_entries: Some(0), ..ScanLimits::default() }, ..ScanOptions::default() };
+        let state = ScanState::new(&root, &options, None);
+        let channel: Channel<ScanMessage> = Channel::new(|_| Ok(()));
+        let id = scan_tree(&root, "root", NO_NODE, 0, &channel, &state, &root).unwrap();
+
+        let tree = state.tree.lock().unwrap();
+        assert!(tree.node(id).is_directory && tree.is_partial(id));
+        assert_eq!(tree.node(id).size, 0);
+        assert_eq!(state.limiter.stopped(), Some(StopReason::EntryLimit));
+    }
 }
@@ -0,0 +1,215 @@
+// Lossless text form of OS file names. Names that are valid Unicode pass through unchanged; the
+// bytes of a name that aren't (legacy-encoded names on Unix, unpaired surrogates on Windows) are
+// written as ESCAPE followed by their hex value, and a literal ESCAPE is doubled. The tree, the
+// IPC messages and exports carry this form, and decode() turns it back into the exact OS name so
+// such files can still be opened and deleted.
+
+use std::borrow::Cow;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+pub const ESCAPE: char = '\u{241B}'; // ␛
+
+pub fn encode(name: &OsStr) -> Cow<'_, str> {
+    match name.to_str() {
+        Some(text) if !text.contains(ESCAPE) => Cow::Borrowed(text),
+        _ => Cow::Owned(encode_raw(name)),
+    }
+}
+
+pub fn decode(text: &str) -> Cow<'_, OsStr> {
+    if text.contains(ESCAPE) {
+        Cow::Owned(decode_raw(text))
+    } else {
+        Cow::Borrowed(OsStr::new(text))
+    }
+}
+
+// A whole path in the same form, separators kept as they are
+pub fn encode_path(path: &Path) -> String {
+    encode(path.as_os_str()).into_owned()
+}
+
+pub fn decode_path(text: &str) -> PathBuf {
+    PathBuf::from(decode(text).into_owned())
+}
+
+// A name or path as raw bytes, as listed in ncdu and du output. Only Unix names can hold
+// arbitrary bytes; elsewhere the listing is taken to be UTF-8.
+#[cfg(unix)]
+pub fn from_bytes(bytes: &[u8]) -> Cow<'_, OsStr> {
+    use std::os::unix::ffi::OsStrExt;
+
+    Cow::Borrowed(OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(unix))]
+pub fn from_bytes(bytes: &[u8]) -> Cow<'_, OsStr> {
+    match String::from_utf8_lossy(bytes) {
+        Cow::Borrowed(text) => Cow::Borrowed(OsStr::new(text)),
+        Cow::Owned(text) => Cow::Owned(OsString::from(text)),
+    }
+}
+
+// The other way round, for writing such listings
+#[cfg(unix)]
+pub fn to_bytes(text: &str) -> Cow<'_, [u8]> {
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+
+    match decode(text) {
+        Cow::Borrowed(name) => Cow::Borrowed(name.as_bytes()),
+        Cow::Owned(name) => Cow::Owned(name.into_vec()),
+    }
+}
+
+#[cfg(not(unix))]
+pub fn to_bytes(text: &str) -> Cow<'_, [u8]> {
+    match decode(text) {
+        Cow::Borrowed(name) => Cow::Borrowed(name.to_str().unwrap_or_default().as_bytes()),
+        Cow::Owned(name) => Cow::Owned(name.to_string_lossy().into_owned().into_bytes()),
+    }
+}
+
+fn push_text(out: &mut String, text: &str) {
+    for c in text.chars() {
+        out.push(c);
+        if c == ESCAPE {
+            out.push(ESCAPE);
+        }
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<u16> {
+    if hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        u16::from_str_radix(hex, 16).ok()
+    } else {
+        None
+    }
+}
+
+// ␛HH per byte that isn't part of valid UTF-8
+#[cfg(unix)]
+fn encode_raw(name: &OsStr) -> String {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut out = String::new();
+    for chunk in name.as_bytes().utf8_chunks() {
+        push_text(&mut out, chunk.valid());
+        for byte in chunk.invalid() {
+            out.push(ESCAPE);
+            out.push_str(&format!("{:02X}", byte));
+        }
+    }
+    out
+}
+
+#[cfg(unix)]
+fn decode_raw(text: &str) -> OsString {
+    use std::os::unix::ffi::OsStringExt;
+
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(position) = rest.find(ESCAPE) {
+        bytes.extend_from_slice(&rest.as_bytes()[..position]);
+        let after = &rest[position + ESCAPE.len_utf8()..];
+        if let Some(doubled) = after.strip_prefix(ESCAPE) {
+            bytes.extend_from_slice(ESCAPE.to_string().as_bytes());
+            rest = doubled;
+        } else if let Some(byte) = after.get(..2).and_then(parse_hex).map(|value| value as u8) {
+            bytes.push(byte);
+            rest = &after[2..];
+        } else {
+            // Not an escape we wrote; keep it as typed
+            bytes.extend_from_slice(ESCAPE.to_string().as_bytes());
+            rest = after;
+        }
+    }
+    bytes.extend_from_slice(rest.as_bytes());
+    OsString::from_vec(bytes)
+}
+
+// ␛uHHHH per unpaired surrogate
+#[cfg(windows)]
+fn encode_raw(name: &OsStr) -> String {
+    use std::os::windows::ffi::OsStrExt;
+
+    let mut out = String::new();
+    for unit in char::decode_utf16(name.encode_wide()) {
+        match unit {
+            Ok(c) => push_text(&mut out, c.encode_utf8(&mut [0; 4])),
+            Err(e) => out.push_str(&format!("{}u{:04X}", ESCAPE, e.unpaired_surrogate())),
+        }
+    }
+    out
+}
+
+#[cfg(windows)]
+fn decode_raw(text: &str) -> OsString {
+    use std::os::windows::ffi::OsStringExt;
+
+    let mut wide = Vec::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(position) = rest.find(ESCAPE) {
+        wide.extend(rest[..position].encode_utf16());
+        let after = &rest[position + ESCAPE.len_utf8()..];
+        if let Some(doubled) = after.strip_prefix(ESCAPE) {
+            wide.push(ESCAPE as u16);
+            rest = doubled;
+        } else if let Some(unit) = after
+            .strip_prefix('u')
+            .and_then(|hex| hex.get(..4))
+            .and_then(parse_hex)
+        {
+            wide.push(unit);
+            rest = &after[5..];
+        } else {
+            wide.push(ESCAPE as u16);
+            rest = after;
+        }
+    }
+    wide.extend(rest.encode_utf16());
+    OsString::from_wide(&wide)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn encode_raw(name: &OsStr) -> String {
+    let mut out = String::new();
+    push_text(&mut out, &name.to_string_lossy());
+    out
+}
+
+#[cfg(not(any(unix, windows)))]
+fn decode_raw(text: &str) -> OsString {
+    OsString::from(text.replace(&format!("{}{}", ESCAPE, ESCAPE), &ESCAPE.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(name: &OsStr) {
+        let text = encode(name);
+        assert_eq!(decode(&text), name, "{:?} went out as {:?}", name, text);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn arbitrary_bytes_round_trip() {
+        use std::os::unix::ffi::OsStrExt;
+
+        for byte in 0..=255u8 {
+            round_trip(OsStr::from_bytes(&[b'a', byte, b'z']));
+        }
+        round_trip(OsStr::from_bytes(b"caf\xe9 \xff\xfe.txt"));
+        assert_eq!(encode(OsStr::from_bytes(b"\xe9")), "\u{241B}E9");
+    }
+
+    #[test]
+    fn literal_escapes_round_trip() {
+        for name in ["\u{241B}", "a\u{241B}b", "\u{241B}\u{241B}", "\u{241B}E9", "x\u{241B}41y", "\u{241B}u00E9", "end\u{241B}"] {
+            round_trip(OsStr::new(name));
+        }
+        assert_eq!(encode(OsStr::new("\u{241B}E9")), "\u{241B}\u{241B}E9");
+        assert_eq!(encode(OsStr::new("plain")), "plain");
+    }
+}
@@ -0,0 +1,237 @@
+// Quick overview: a breadth-first pass over the whole tree with a time budget, run before the
+// full scan so the first picture of a large volume arrives in seconds. Every top-level entry
+// gets a size: exact if its subtree was walked completely, the statvfs total if it is a mount of
+// its own, otherwise the bytes found so far extrapolated over the directories still unvisited.
+
+use filesize::PathExt;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::device::{self, device_id};
+use crate::osname;
+
+pub const DEFAULT_BUDGET_MS: u64 = 2000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanStrategy {
+    #[default]
+    DepthFirst,
+    QuickOverview, // Breadth-first estimate of the top levels, then the full scan
+}
+
+// How an overview size was obtained
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Basis {
+    Exact,
+    Volume, // statvfs of a mount point
+    Sampled,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct OverviewEntry {
+    pub name: String,
+    pub size: u64,
+    pub is_directory: bool,
+    pub basis: Basis,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Overview {
+    pub total_size: u64,
+    pub total_basis: Basis, // Volume when the root is a mount point, else the weakest entry basis
+    pub entries: Vec<OverviewEntry>, // Largest first
+    pub depth: usize, // Levels walked so far
+    pub elapsed_ms: u64,
+}
+
+// Per top-level directory: what the breadth-first walk has seen of it so far
+struct Sample {
+    known: u64,
+    visited: u64,
+    pending: Vec<PathBuf>,
+}
+
+impl Sample {
+    fn estimate(&self) -> u64 {
+        if self.pending.is_empty() || self.visited == 0 {
+            return self.known;
+        }
+        // Assume unvisited directories hold about as much as the visited ones did. That counts
+        // only their own files, not the subtrees below them, so deep trees come out low until
+        // the walk reaches their bottom; on a mount root the volume total scales them back up
+        self.known + self.known / self.visited * self.pending.len() as u64
+    }
+}
+
+struct Entry {
+    name: String,
+    is_directory: bool,
+    size: u64,
+    basis: Basis,
+    sample: Option<Sample>,
+}
+
+// Bytes of the files directly in a directory, and its subdirectories to visit next
+type Level = (u64, Vec<PathBuf>);
+
+// Symlinks aren't followed, so the overview can't loop. Filesystems mounted below the top level
+// aren't walked either: a network share or a pseudo filesystem would eat the budget or swamp
+// the sample of the directory it sits in
+fn read_level(path: &Path, root_device: u64) -> Level {
+    let Ok(entries) = fs::read_dir(path) else {
+        return (0, Vec::new());
+    };
+    let mut bytes = 0;
+    let mut directories = Vec::new();
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else { continue };
+        if file_type.is_dir() {
+            if entry.metadata().is_ok_and(|metadata| device_id(&metadata) == root_device) {
+                directories.push(entry.path());
+            }
+        } else if file_type.is_file() {
+            bytes += entry.path().size_on_disk().unwrap_or(0);
+        }
+    }
+    (bytes, directories)
+}
+
+// Walk `root` level by level until `budget` runs out, calling `on_level` after every level
+pub fn run(root: &Path, budget: Duration, cancelled: &AtomicBool, mut on_level: impl FnMut(&Overview)) -> Option<Overview> {
+    let started = Instant::now();
+    let deadline = started + budget;
+    let root_device = fs::metadata(root).ok().map(|metadata| device_id(&metadata))?;
+    let volume = device::is_mount_point(root).then(|| device::volume_usage(root)).flatten();
+
+    let mut entries: Vec<Entry> = fs::read_dir(root)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let name = osname::encode(&entry.file_name()).into_owned();
+            let metadata = entry.metadata().ok()?;
+            let path = entry.path();
+
+            if !metadata.is_dir() {
+                let size = if metadata.is_file() { path.size_on_disk().unwrap_or(0) } else { 0 };
+                return Some(Entry { name, is_directory: false, size, basis: Basis::Exact, sample: None });
+            }
+
+            // A separate filesystem mounted here: statvfs already knows its usage
+            if device_id(&metadata) != root_device {
+                if let Some(usage) = device::volume_usage(&path) {
+                    return Some(Entry { name, is_directory: true, size: usage.used_bytes, basis: Basis::Volume, sample: None });
+                }
+            }
+
+            let sample = Sample { known: 0, visited: 0, pending: vec![path] };
+            Some(Entry { name, is_directory: true, size: 0, basis: Basis::Sampled, sample: Some(sample) })
+        })
+        .collect();
+
+    let mut depth = 0;
+    let mut overview = summarize(&mut entries, volume.map(|usage| usage.used_bytes), depth, started);
+    on_level(&overview);
+
+    while Instant::now() < deadline && !cancelled.load(Ordering::Relaxed) {
+        // One level across every top-level directory, so they all refine at the same pace
+        let frontier: Vec<(usize, PathBuf)> = entries
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, entry)| entry.sample.as_mut().map(|sample| (index, sample)))
+            .flat_map(|(index, sample)| std::mem::take(&mut sample.pending).into_iter().map(move |path| (index, path)))
+            .collect();
+        if frontier.is_empty() {
+            break;
+        }
+
+        // Directories past the deadline are left pending and only count towards the estimate
+        let results: Vec<(usize, PathBuf, Option<Level>)> = frontier
+            .into_par_iter()
+            .map(|(index, path)| {
+                let level = (Instant::now() < deadline && !cancelled.load(Ordering::Relaxed)).then(|| read_level(&path, root_device));
+                (index, path, level)
+            })
+            .collect();
+
+        for (index, path, level) in results {
+            let Some(sample) = entries[index].sample.as_mut() else { continue };
+            match level {
+                Some((bytes, directories)) => {
+                    sample.known += bytes;
+                    sample.visited += 1;
+                    sample.pending.extend(directories);
+                }
+                None => sample.pending.push(path),
+            }
+        }
+
+        depth += 1;
+        overview = summarize(&mut entries, volume.map(|usage| usage.used_bytes), depth, started);
+        on_level(&overview);
+    }
+
+    println!("🔭 Quick overview: {} levels in {} ms, ~{} bytes", overview.depth, overview.elapsed_ms, overview.total_size);
+    Some(overview)
+}
+
+fn summarize(entries: &mut [Entry], volume_used: Option<u64>, depth: usize, started: Instant) -> Overview {
+    for entry in entries.iter_mut() {
+        if let Some(sample) = &entry.sample {
+            entry.size = sample.estimate();
+            entry.basis = if sample.pending.is_empty() { Basis::Exact } else { Basis::Sampled };
+        }
+    }
+
+    let mut sizes: Vec<u64> = entries.iter().map(|entry| entry.size).collect();
+    let sampled: u64 = entries.iter().filter(|entry| entry.basis == Basis::Sampled).map(|entry| entry.size).sum();
+
+    // On a mount root the volume total is known, so the sampled entries share what's unaccounted for
+    if let Some(used) = volume_used {
+        let fixed: u64 = entries.iter().filter(|entry| entry.basis != Basis::Sampled).map(|entry| entry.size).sum();
+        let remaining = used.saturating_sub(fixed);
+        if sampled > 0 && remaining > sampled {
+            let scale = remaining as f64 / sampled as f64;
+            for (size, entry) in sizes.iter_mut().zip(entries.iter()) {
+                if entry.basis == Basis::Sampled {
+                    *size = (*size as f64 * scale) as u64;
+                }
+            }
+        }
+    }
+
+    let mut listed: Vec<OverviewEntry> = entries
+        .iter()
+        .zip(sizes)
+        .map(|(entry, size)| OverviewEntry { name: entry.name.clone(), size, is_directory: entry.is_directory, basis: entry.basis })
+        .collect();
+    listed.sort_by_key(|entry| Reverse(entry.size));
+
+    let (total_size, total_basis) = match volume_used {
+        Some(used) => (used, Basis::Volume),
+        None => {
+            let basis = if listed.iter().any(|entry| entry.basis == Basis::Sampled) {
+                Basis::Sampled
+            } else if listed.iter().any(|entry| entry.basis == Basis::Volume) {
+                Basis::Volume
+            } else {
+                Basis::Exact
+            };
+            (listed.iter().map(|entry| entry.size).sum(), basis)
+        }
+    };
+
+    Overview {
+        total_size,
+        total_basis,
+        entries: listed,
+        depth,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    }
+}
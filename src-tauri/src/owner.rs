@@ -0,0 +1,52 @@
+// Resolve a user name (or a numeric ID) to a uid
+#[cfg(unix)]
+pub fn uid_for_name(name: &str) -> Option<u32> {
+    if let Ok(uid) = name.parse::<u32>() {
+        return Some(uid);
+    }
+
+    let c_name = std::ffi::CString::new(name).ok()?;
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buffer = vec![0 as libc::c_char; 4096];
+    let mut result: *mut libc::passwd = std::ptr::null_mut();
+
+    // SAFETY: every pointer refers to a live local buffer of the advertised size
+    let status = unsafe {
+        libc::getpwnam_r(c_name.as_ptr(), &mut passwd, buffer.as_mut_ptr(), buffer.len(), &mut result)
+    };
+
+    if status == 0 && !result.is_null() {
+        Some(passwd.pw_uid)
+    } else {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+pub fn uid_for_name(name: &str) -> Option<u32> {
+    name.parse::<u32>().ok()
+}
+
+// Resolve a uid to a user name, falling back to the numeric ID
+#[cfg(unix)]
+pub fn name_for_uid(uid: u32) -> String {
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buffer = vec![0 as libc::c_char; 4096];
+    let mut result: *mut libc::passwd = std::ptr::null_mut();
+
+    // SAFETY: every pointer refers to a live local buffer of the advertised size
+    let status = unsafe { libc::getpwuid_r(uid, &mut passwd, buffer.as_mut_ptr(), buffer.len(), &mut result) };
+
+    if status == 0 && !result.is_null() {
+        // SAFETY: on success pw_name points to a NUL-terminated string inside `buffer`
+        let name = unsafe { std::ffi::CStr::from_ptr(passwd.pw_name) };
+        name.to_string_lossy().into_owned()
+    } else {
+        uid.to_string()
+    }
+}
+
+#[cfg(not(unix))]
+pub fn name_for_uid(uid: u32) -> String {
+    uid.to_string()
+}
@@ -0,0 +1,205 @@
+// Progress, throughput and ETA for any scan. The expected totals come from statvfs when the
+// scan root is a mount point, else from the last completed scan of the same path (remembered in
+// a small history file), else from the quick overview estimate if one ran.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::device;
+use crate::osname;
+
+const HISTORY_FILE: &str = "storviz-scan-history.json";
+const HISTORY_LIMIT: usize = 200; // Paths remembered; the least recently scanned are dropped
+const RATE_SMOOTHING: f64 = 0.3; // Weight of the newest sample in the throughput average
+const MAX_FRACTION: f64 = 0.99; // Never claim completion before the scan actually ends
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetBasis {
+    Volume,
+    Snapshot,
+    Overview,
+}
+
+// What a scan is expected to find in total
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct ProgressTarget {
+    pub entries: Option<u64>,
+    pub bytes: Option<u64>,
+    pub basis: TargetBasis,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct Throughput {
+    pub files_per_second: f64,
+    pub bytes_per_second: f64,
+    pub target_basis: Option<TargetBasis>, // None when nothing knows the totals: only the rates mean anything
+    pub fraction: Option<f64>, // 0..1 of the target, if there is one
+    pub eta_seconds: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+struct Snapshot {
+    entries: u64,
+    bytes: u64,
+    scanned_at: u64, // Seconds since the Unix epoch
+}
+
+// The totals a scan of `root` should reach, if anything knows them before the scan does. A
+// directory that is neither a mount point nor scanned before has none, until an overview runs.
+pub fn target_for(root: &Path, volume_bytes: Option<u64>) -> Option<ProgressTarget> {
+    if device::is_mount_point(root) {
+        if let Some(usage) = device::volume_usage(root) {
+            return Some(ProgressTarget {
+                entries: (usage.used_inodes > 0).then_some(usage.used_inodes), // Some filesystems don't count inodes
+                bytes: Some(volume_bytes.unwrap_or(usage.used_bytes)),
+                basis: TargetBasis::Volume,
+            });
+        }
+    }
+
+    let snapshot = load_history().remove(&history_key(root))?;
+    Some(ProgressTarget {
+        entries: Some(snapshot.entries),
+        bytes: Some(snapshot.bytes),
+        basis: TargetBasis::Snapshot,
+    })
+}
+
+// Remember the totals of a completed scan as the next scan's target
+pub fn remember(root: &Path, entries: u64, bytes: u64) {
+    let mut history = load_history();
+    let scanned_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    history.insert(history_key(root), Snapshot { entries, bytes, scanned_at });
+
+    if history.len() > HISTORY_LIMIT {
+        let mut by_age: Vec<(String, u64)> = history.iter().map(|(path, snapshot)| (path.clone(), snapshot.scanned_at)).collect();
+        by_age.sort_by_key(|(_, scanned_at)| *scanned_at);
+        for (path, _) in by_age.into_iter().take(history.len() - HISTORY_LIMIT) {
+            history.remove(&path);
+        }
+    }
+
+    // Written aside and renamed over the old file, so a crash never leaves half a history
+    let path = history_path();
+    let partial = path.with_file_name(format!("{}.{}.partial", HISTORY_FILE, std::process::id()));
+    let result = serde_json::to_vec(&history)
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| fs::write(&partial, bytes).map_err(|e| e.to_string()))
+        .and_then(|_| fs::rename(&partial, &path).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        let _ = fs::remove_file(&partial);
+        eprintln!("❌ Failed to save scan history: {}", e);
+    }
+}
+
+fn history_key(root: &Path) -> String {
+    osname::encode_path(&fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf()))
+}
+
+fn load_history() -> HashMap<String, Snapshot> {
+    fs::read(history_path())
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+fn history_path() -> PathBuf {
+    cache_dir().join(HISTORY_FILE)
+}
+
+// The per-user cache directory of the platform, or the temp directory if there is none
+pub fn cache_dir() -> PathBuf {
+    let cache = if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Caches"))
+    } else if cfg!(windows) {
+        std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else {
+        std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+    };
+    cache.filter(|dir| dir.is_dir()).unwrap_or_else(std::env::temp_dir)
+}
+
+// Turns the running totals into rates and a completion estimate
+pub struct ProgressMeter {
+    started: Instant,
+    target: Option<ProgressTarget>,
+    last: Option<(Instant, u64, u64)>,
+    rates: (f64, f64),
+}
+
+impl ProgressMeter {
+    pub fn new(target: Option<ProgressTarget>) -> Self {
+        Self { started: Instant::now(), target, last: None, rates: (0.0, 0.0) }
+    }
+
+    // Only fills in a missing target: statvfs and snapshots beat an overview estimate
+    pub fn offer_target(&mut self, target: ProgressTarget) {
+        if self.target.is_none() {
+            self.target = Some(target);
+        }
+    }
+
+    pub fn sample(&mut self, entries: u64, bytes: u64) -> Throughput {
+        let now = Instant::now();
+        let (since, last_entries, last_bytes) = self.last.unwrap_or((self.started, 0, 0));
+        let seconds = now.duration_since(since).as_secs_f64();
+        if seconds > 0.05 {
+            let files_rate = entries.saturating_sub(last_entries) as f64 / seconds;
+            let bytes_rate = bytes.saturating_sub(last_bytes) as f64 / seconds;
+            self.rates = if self.last.is_none() {
+                (files_rate, bytes_rate)
+            } else {
+                (
+                    self.rates.0 + RATE_SMOOTHING * (files_rate - self.rates.0),
+                    self.rates.1 + RATE_SMOOTHING * (bytes_rate - self.rates.1),
+                )
+            };
+            self.last = Some((now, entries, bytes));
+        }
+
+        // Entry counts track scan time more closely than bytes (one huge file is one stat)
+        let fraction = self.target.and_then(|target| {
+            let by_entries = target.entries.filter(|&total| total > 0 && entries <= total).map(|total| entries as f64 / total as f64);
+            let by_bytes = target.bytes.filter(|&total| total > 0 && bytes <= total).map(|total| bytes as f64 / total as f64);
+            by_entries.or(by_bytes).map(|fraction| fraction.min(MAX_FRACTION))
+        });
+
+        let elapsed = now.duration_since(self.started).as_secs_f64();
+        let eta_seconds = fraction
+            .filter(|&fraction| fraction > 0.0)
+            .map(|fraction| (elapsed * (1.0 - fraction) / fraction).round() as u64);
+
+        Throughput {
+            files_per_second: self.rates.0,
+            bytes_per_second: self.rates.1,
+            target_basis: self.target.map(|target| target.basis),
+            fraction,
+            eta_seconds,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn without_a_target_only_rates_are_reported() {
+        let mut meter = ProgressMeter::new(None);
+        let throughput = meter.sample(100, 1000);
+        assert_eq!(throughput.target_basis, None);
+        assert_eq!(throughput.fraction, None);
+        assert_eq!(throughput.eta_seconds, None);
+
+        meter.offer_target(ProgressTarget { entries: None, bytes: Some(4000), basis: TargetBasis::Overview });
+        let throughput = meter.sample(100, 1000);
+        assert_eq!(throughput.target_basis, Some(TargetBasis::Overview));
+        assert_eq!(throughput.fraction, Some(0.25));
+    }
+}
@@ -0,0 +1,211 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::path::Path;
+
+use crate::classify::Category;
+use crate::estimate::Estimate;
+use crate::fold::{Folded, FoldOptions};
+use crate::osname;
+use crate::spill;
+use crate::tree::{NodeId, ScanTree, Truncated, ROOT_NODE};
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    #[default]
+    Size,
+    Name,
+}
+
+// Paging and nesting for a children listing; `depth` 1 returns only direct children
+#[derive(Clone, Debug, Deserialize)]
+pub struct ChildrenQuery {
+    #[serde(default)]
+    pub sort: SortKey,
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default = "default_depth")]
+    pub depth: usize,
+    #[serde(default)]
+    pub fold: Option<FoldOptions>, // Tiny children become one "N smaller items" entry at the end
+    #[serde(default)]
+    pub bucket: bool, // List what a bucket folded: all children by size from `offset`, `fold` only below
+}
+
+fn default_depth() -> usize {
+    1
+}
+
+impl Default for ChildrenQuery {
+    fn default() -> Self {
+        Self {
+            sort: SortKey::default(),
+            offset: 0,
+            limit: None,
+            depth: default_depth(),
+            fold: None,
+            bucket: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NodeInfo {
+    pub id: NodeId,
+    pub name: String,
+    pub size: u64,
+    pub is_directory: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<Category>, // Same as the scan batches' "t"; left out for folders and Other
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<Estimate>, // Only for sizes extrapolated by an estimate-mode scan
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub folded: Option<Folded>, // Set on the synthetic entry; its id is the parent directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub truncated: Option<Truncated>, // Directories past the scan's depth limit, measured but not walked
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub partial: bool, // A scan limit cut the directory short; its size is a lower bound
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub children: Option<Vec<NodeInfo>>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ChildrenPage {
+    pub total: usize,
+    pub children: Vec<NodeInfo>,
+}
+
+pub fn node_info(tree: &ScanTree, id: NodeId) -> NodeInfo {
+    let node = tree.node(id);
+    NodeInfo {
+        id,
+        name: tree.name(id).to_string(),
+        size: node.size,
+        is_directory: node.is_directory,
+        category: (!node.is_directory && node.meta.category != Category::Other).then_some(node.meta.category),
+        estimate: tree.estimate(id),
+        folded: None,
+        truncated: tree.truncation(id),
+        partial: tree.is_partial(id),
+        children: None,
+    }
+}
+
+fn folded_info(folded: Folded) -> NodeInfo {
+    NodeInfo {
+        id: folded.parent,
+        name: folded.label(),
+        size: folded.size,
+        is_directory: false,
+        category: None,
+        estimate: None,
+        folded: Some(folded),
+        truncated: None,
+        partial: false,
+        children: None,
+    }
+}
+
+pub fn sorted_children(tree: &ScanTree, id: NodeId, sort: SortKey) -> Vec<NodeId> {
+    let mut children: Vec<NodeId> = tree.children(id).collect();
+    match sort {
+        SortKey::Size => children.sort_by_key(|&child| Reverse(tree.node(child).size)),
+        SortKey::Name => children.sort_by(|&a, &b| tree.name(a).cmp(tree.name(b))),
+    }
+    children
+}
+
+// One page of a node's children; nested levels use the same sort and limit but start at 0
+pub fn children_page(tree: &mut ScanTree, index: Option<&Path>, id: NodeId, query: &ChildrenQuery) -> Result<ChildrenPage, String> {
+    spill::ensure_loaded(tree, index, id).map_err(|e| e.to_string())?;
+
+    // With folding, the listing is the kept children followed by the bucket as one more entry
+    let (children, folded) = match &query.fold {
+        // The bucket's offset counts from the largest child, so it pages the full size order
+        _ if query.bucket => (sorted_children(tree, id, SortKey::Size), None),
+        Some(fold) => {
+            let (mut kept, folded) = fold.split(tree, id);
+            if matches!(query.sort, SortKey::Name) {
+                kept.sort_by(|&a, &b| tree.name(a).cmp(tree.name(b)));
+            }
+            (kept, folded)
+        }
+        None => (sorted_children(tree, id, query.sort), None),
+    };
+    let total = children.len() + folded.is_some() as usize;
+    let limit = query.limit.unwrap_or(usize::MAX);
+    let bucket = folded.filter(|_| query.offset.saturating_add(limit) >= total && query.offset < total);
+
+    let mut page = Vec::new();
+    for child in children.into_iter().skip(query.offset).take(limit) {
+        let mut info = node_info(tree, child);
+        if query.depth > 1 && info.is_directory {
+            let nested = ChildrenQuery {
+                offset: 0,
+                depth: query.depth - 1,
+                bucket: false,
+                ..query.clone()
+            };
+            info.children = Some(children_page(tree, index, child, &nested)?.children);
+        }
+        page.push(info);
+    }
+    page.extend(bucket.map(folded_info));
+
+    Ok(ChildrenPage { total, children: page })
+}
+
+// Walk from the scan root down to `path`, loading spilled directories on the way
+pub fn resolve_path(tree: &mut ScanTree, index: Option<&Path>, path: &Path) -> Option<NodeId> {
+    let relative = path.strip_prefix(tree.root_path()).ok()?;
+
+    let mut current = ROOT_NODE;
+    for component in relative.components() {
+        let name = osname::encode(component.as_os_str());
+        spill::ensure_loaded(tree, index, current).ok()?;
+        current = tree.find_child(current, &name)?;
+    }
+    Some(current)
+}
+
+// Ancestors of a node ordered from the scan root down to its parent
+pub fn ancestors(tree: &ScanTree, id: NodeId) -> Vec<NodeInfo> {
+    let mut chain = Vec::new();
+    let mut current = tree.parent(id);
+    while let Some(ancestor) = current {
+        chain.push(node_info(tree, ancestor));
+        current = tree.parent(ancestor);
+    }
+    chain.reverse();
+    chain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::{EntryMeta, NO_NODE};
+
+    #[test]
+    fn buckets_page_back_their_folded_children() {
+        let mut tree = ScanTree::new("/scan");
+        let root = tree.add_node(NO_NODE, "scan", true, 0, EntryMeta::default()).unwrap();
+        for (name, size) in [("big", 1000), ("a", 10), ("b", 20), ("c", 30)] {
+            tree.add_node(root, name, false, size, EntryMeta::default()).unwrap();
+        }
+        tree.set_size(root, 1060, 1060);
+        let fold = FoldOptions { min_size: 100, min_fraction: 0.0, min_items: 2 };
+
+        let query = ChildrenQuery { fold: Some(fold), ..ChildrenQuery::default() };
+        let page = children_page(&mut tree, None, root, &query).unwrap();
+        let folded = page.children.last().and_then(|child| child.folded).unwrap();
+        assert_eq!((page.total, folded.count, folded.offset), (2, 3, 1));
+
+        let query = ChildrenQuery { offset: folded.offset, limit: Some(folded.count as usize), fold: Some(fold), bucket: true, ..ChildrenQuery::default() };
+        let page = children_page(&mut tree, None, root, &query).unwrap();
+        let names: Vec<&str> = page.children.iter().map(|child| child.name.as_str()).collect();
+        assert_eq!(names, ["c", "b", "a"]);
+        assert!(page.children.iter().all(|child| child.folded.is_none()));
+    }
+}
@@ -0,0 +1,459 @@
+// Static treemap / sunburst images (SVG or PNG) of one node, drawn entirely in the backend
+// from the same geometry as get_layout, so the CLI can produce them without a window.
+
+use serde::Deserialize;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::classify::Category;
+use crate::layout::{self, LayoutItem, LayoutKind, LayoutRequest, Shape};
+use crate::tree::{NodeId, ScanTree};
+
+const MAX_DIMENSION: u32 = 16384;
+const MAX_PNG_PIXELS: u64 = 40_000_000; // About 120 MB of RGB canvas
+const BACKGROUND: [u8; 3] = [0x0f, 0x17, 0x2a];
+const TEXT: [u8; 3] = [0xff, 0xff, 0xff];
+const BUCKET: [u8; 3] = [0x47, 0x55, 0x69];
+// Directories get lighter the deeper they are
+const DIRECTORY_SHADES: [[u8; 3]; 4] = [[0x1e, 0x29, 0x3b], [0x27, 0x34, 0x49], [0x33, 0x41, 0x55], [0x3f, 0x4c, 0x61]];
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageFormat {
+    Svg,
+    Png,
+}
+
+impl ImageFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "svg" => Some(Self::Svg),
+            "png" => Some(Self::Png),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct RenderOptions {
+    pub kind: LayoutKind,
+    pub format: Option<ImageFormat>, // Taken from the file extension when missing
+    pub width: u32,
+    pub height: u32,
+    pub max_depth: usize,
+    pub labels: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            kind: LayoutKind::Treemap,
+            format: None,
+            width: 1200,
+            height: 800,
+            max_depth: 3,
+            labels: true,
+        }
+    }
+}
+
+pub fn category_color(category: Category) -> [u8; 3] {
+    // Same palette as the HTML report
+    match category {
+        Category::Video => [0xe1, 0x1d, 0x48],
+        Category::Image => [0xf5, 0x9e, 0x0b],
+        Category::Audio => [0xa8, 0x55, 0xf7],
+        Category::Archive => [0x84, 0xcc, 0x16],
+        Category::DiskImage => [0x14, 0xb8, 0xa6],
+        Category::VmImage => [0x0e, 0xa5, 0xe9],
+        Category::Package => [0x63, 0x66, 0xf1],
+        Category::Database => [0xf9, 0x73, 0x16],
+        Category::Code => [0x22, 0xc5, 0x5e],
+        Category::Document => [0xea, 0xb3, 0x08],
+        Category::Executable => [0xef, 0x44, 0x44],
+        Category::Font => [0xec, 0x48, 0x99],
+        Category::Log | Category::Other => [0x64, 0x74, 0x8b],
+    }
+}
+
+fn item_color(item: &LayoutItem) -> [u8; 3] {
+    if item.id.is_none() {
+        BUCKET
+    } else if item.is_directory {
+        DIRECTORY_SHADES[item.depth.min(DIRECTORY_SHADES.len() - 1)]
+    } else {
+        category_color(item.category.unwrap_or_default())
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+// Point on a circle, with angles in degrees clockwise from 12 o'clock
+fn polar(cx: f64, cy: f64, radius: f64, degrees: f64) -> (f64, f64) {
+    let angle = degrees.to_radians();
+    (cx + radius * angle.sin(), cy - radius * angle.cos())
+}
+
+// Where a label goes: the top-left corner of a rectangle, the middle of an arc
+fn label_anchor(shape: &Shape, cx: f64, cy: f64) -> (f64, f64, bool) {
+    match *shape {
+        Shape::Rect { x, y, .. } => (x + 4.0, y + 12.0, false),
+        Shape::Arc { start_angle, end_angle, inner_radius, outer_radius } => {
+            if end_angle - start_angle >= 360.0 - 1e-6 && inner_radius == 0.0 {
+                return (cx, cy + 4.0, true);
+            }
+            let (x, y) = polar(cx, cy, (inner_radius + outer_radius) / 2.0, (start_angle + end_angle) / 2.0);
+            (x, y + 4.0, true)
+        }
+    }
+}
+
+fn svg_escape(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn arc_path(cx: f64, cy: f64, start: f64, end: f64, inner: f64, outer: f64) -> String {
+    let mut path = String::new();
+    if end - start >= 360.0 - 1e-6 {
+        // A full ring can't be one arc command; draw two circles and let evenodd cut the hole
+        let _ = write!(path, "M{:.2},{:.2}A{:.2},{:.2} 0 1 1 {:.2},{:.2}A{:.2},{:.2} 0 1 1 {:.2},{:.2}Z", cx, cy - outer, outer, outer, cx, cy + outer, outer, outer, cx, cy - outer);
+        if inner > 0.0 {
+            let _ = write!(path, "M{:.2},{:.2}A{:.2},{:.2} 0 1 0 {:.2},{:.2}A{:.2},{:.2} 0 1 0 {:.2},{:.2}Z", cx, cy - inner, inner, inner, cx, cy + inner, inner, inner, cx, cy - inner);
+        }
+        return path;
+    }
+
+    let large = if end - start > 180.0 { 1 } else { 0 };
+    let (x0, y0) = polar(cx, cy, outer, start);
+    let (x1, y1) = polar(cx, cy, outer, end);
+    let _ = write!(path, "M{:.2},{:.2}A{:.2},{:.2} 0 {} 1 {:.2},{:.2}", x0, y0, outer, outer, large, x1, y1);
+    if inner > 0.0 {
+        let (x2, y2) = polar(cx, cy, inner, end);
+        let (x3, y3) = polar(cx, cy, inner, start);
+        let _ = write!(path, "L{:.2},{:.2}A{:.2},{:.2} 0 {} 0 {:.2},{:.2}Z", x2, y2, inner, inner, large, x3, y3);
+    } else {
+        let _ = write!(path, "L{:.2},{:.2}Z", cx, cy);
+    }
+    path
+}
+
+fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+pub fn render_svg(layout: &layout::Layout, labels: bool) -> String {
+    let (cx, cy) = (layout.width / 2.0, layout.height / 2.0);
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="-apple-system, 'Segoe UI', 'Noto Sans TC', sans-serif" font-size="11">"#,
+        w = layout.width,
+        h = layout.height
+    );
+    let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="{}"/>"#, hex(BACKGROUND));
+
+    for item in &layout.items {
+        let fill = hex(item_color(item));
+        let tooltip = format!("<title>{} ({})</title>", svg_escape(&item.name), format_size(item.size));
+        match item.shape {
+            Shape::Rect { x, y, width, height } => {
+                let _ = writeln!(
+                    svg,
+                    r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="{}" stroke="{}" stroke-width="1">{}</rect>"#,
+                    x, y, width, height, fill, hex(BACKGROUND), tooltip
+                );
+            }
+            Shape::Arc { start_angle, end_angle, inner_radius, outer_radius } => {
+                let path = arc_path(cx, cy, start_angle, end_angle, inner_radius, outer_radius);
+                let _ = writeln!(svg, r#"<path d="{}" fill="{}" fill-rule="evenodd" stroke="{}" stroke-width="1">{}</path>"#, path, fill, hex(BACKGROUND), tooltip);
+            }
+        }
+    }
+
+    if labels {
+        for item in &layout.items {
+            let Some(label) = &item.label else { continue };
+            let (x, y, centered) = label_anchor(&item.shape, cx, cy);
+            let anchor = if centered { r#" text-anchor="middle""# } else { "" };
+            let _ = writeln!(svg, r#"<text x="{:.2}" y="{:.2}" fill="{}"{}>{}</text>"#, x, y, hex(TEXT), anchor, svg_escape(label));
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// RGB canvas for the PNG output; shapes are filled without anti-aliasing
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize) -> Self {
+        let mut pixels = Vec::with_capacity(width * height * 3);
+        for _ in 0..width * height {
+            pixels.extend_from_slice(&BACKGROUND);
+        }
+        Self { width, height, pixels }
+    }
+
+    fn set(&mut self, x: i64, y: i64, color: [u8; 3]) {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return;
+        }
+        let offset = (y as usize * self.width + x as usize) * 3;
+        self.pixels[offset..offset + 3].copy_from_slice(&color);
+    }
+
+    // Filled rectangle with a one pixel border in the background color
+    fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, color: [u8; 3]) {
+        let (x0, y0) = (x.round() as i64, y.round() as i64);
+        let (x1, y1) = ((x + width).round() as i64, (y + height).round() as i64);
+        for py in y0..y1 {
+            for px in x0..x1 {
+                let edge = px == x0 || py == y0 || px == x1 - 1 || py == y1 - 1;
+                self.set(px, py, if edge { BACKGROUND } else { color });
+            }
+        }
+    }
+
+    fn text(&mut self, x: f64, y: f64, text: &str, centered: bool) {
+        let advance = (GLYPH_WIDTH + 1) as i64;
+        let length = text.chars().count() as i64;
+        let mut left = x.round() as i64 - if centered { length * advance / 2 } else { 0 };
+        let top = y.round() as i64 - GLYPH_HEIGHT as i64;
+        for c in text.chars() {
+            let Some(glyph) = glyph(c) else { continue };
+            for (column, bits) in glyph.iter().enumerate() {
+                for row in 0..GLYPH_HEIGHT {
+                    if bits & (1 << row) != 0 {
+                        self.set(left + column as i64, top + row as i64, TEXT);
+                    }
+                }
+            }
+            left += advance;
+        }
+    }
+}
+
+struct RingArc {
+    start: f64,
+    end: f64,
+    inner: f64,
+    outer: f64,
+    color: [u8; 3],
+}
+
+// Every pixel of the sunburst is looked up by ring, then by angle within the ring
+fn fill_sunburst(canvas: &mut Canvas, items: &[LayoutItem]) {
+    let mut rings: Vec<Vec<RingArc>> = Vec::new();
+    for item in items {
+        if let Shape::Arc { start_angle, end_angle, inner_radius, outer_radius } = item.shape {
+            if rings.len() <= item.depth {
+                rings.resize_with(item.depth + 1, Vec::new);
+            }
+            rings[item.depth].push(RingArc { start: start_angle, end: end_angle, inner: inner_radius, outer: outer_radius, color: item_color(item) });
+        }
+    }
+
+    let (cx, cy) = (canvas.width as f64 / 2.0, canvas.height as f64 / 2.0);
+    for py in 0..canvas.height {
+        for px in 0..canvas.width {
+            let (dx, dy) = (px as f64 + 0.5 - cx, py as f64 + 0.5 - cy);
+            let radius = (dx * dx + dy * dy).sqrt();
+            let angle = dx.atan2(-dy).to_degrees().rem_euclid(360.0);
+
+            let ring = rings.iter().find(|arcs| arcs.first().is_some_and(|arc| radius >= arc.inner && radius < arc.outer));
+            let Some(arcs) = ring else { continue };
+            // Arcs of one ring are laid out in angular order
+            let index = arcs.partition_point(|arc| arc.start <= angle);
+            let Some(arc) = index.checked_sub(1).map(|i| &arcs[i]) else { continue };
+            if angle >= arc.end {
+                continue;
+            }
+
+            // Leave a one pixel gap along the inner edge and the leading side of each arc
+            let along = (angle - arc.start).to_radians() * radius;
+            let gap = (arc.inner > 0.0 && radius - arc.inner < 1.0) || (arc.end - arc.start < 360.0 && along < 1.0);
+            if !gap {
+                canvas.set(px as i64, py as i64, arc.color);
+            }
+        }
+    }
+}
+
+// The bitmap font only has ASCII, so other names are left unlabelled rather than drawn as
+// boxes; "smaller items" buckets are marked with their count instead of the translated text
+fn png_label(item: &LayoutItem) -> Option<String> {
+    let label = item.label.as_ref()?;
+    match item.folded {
+        Some(count) if item.id.is_none() => Some(format!("+{}", count)),
+        _ if label.chars().all(|c| glyph(c).is_some()) => Some(label.clone()),
+        _ => None,
+    }
+}
+
+pub fn render_png(layout: &layout::Layout, labels: bool) -> Result<Vec<u8>, String> {
+    let (width, height) = (layout.width as usize, layout.height as usize);
+    let mut canvas = Canvas::new(width, height);
+    match layout.kind {
+        LayoutKind::Treemap => {
+            for item in &layout.items {
+                if let Shape::Rect { x, y, width, height } = item.shape {
+                    canvas.rect(x, y, width, height, item_color(item));
+                }
+            }
+        }
+        LayoutKind::Sunburst => fill_sunburst(&mut canvas, &layout.items),
+    }
+
+    if labels {
+        let (cx, cy) = (layout.width / 2.0, layout.height / 2.0);
+        for item in &layout.items {
+            let Some(label) = png_label(item) else { continue };
+            let (x, y, centered) = label_anchor(&item.shape, cx, cy);
+            canvas.text(x, y, &label, centered);
+        }
+    }
+
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    writer.write_image_data(&canvas.pixels).map_err(|e| e.to_string())?;
+    writer.finish().map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
+
+// An image whose layout has been computed; finish() draws and writes it, and needs no access
+// to the tree
+pub struct PendingImage {
+    layout: layout::Layout,
+    format: ImageFormat,
+    labels: bool,
+    destination: PathBuf,
+}
+
+impl PendingImage {
+    pub fn finish(self) -> Result<(), String> {
+        let bytes = match self.format {
+            ImageFormat::Svg => render_svg(&self.layout, self.labels).into_bytes(),
+            ImageFormat::Png => render_png(&self.layout, self.labels)?,
+        };
+        fs::write(&self.destination, bytes).map_err(|e| format!("無法寫入圖片: {}", e))?;
+        println!("🖼️ Rendered {} shapes to {}", self.layout.items.len(), self.destination.display());
+        Ok(())
+    }
+}
+
+// Lay out the image for `destination`; the caller draws and writes it with finish()
+pub fn render(tree: &mut ScanTree, index: Option<&Path>, id: NodeId, destination: &Path, options: &RenderOptions) -> Result<PendingImage, String> {
+    let format = options
+        .format
+        .or_else(|| ImageFormat::from_path(destination))
+        .ok_or_else(|| "無法判斷圖片格式，請使用 .svg 或 .png".to_string())?;
+    if options.width == 0 || options.height == 0 || options.width > MAX_DIMENSION || options.height > MAX_DIMENSION {
+        return Err(format!("圖片尺寸必須介於 1 到 {} 像素之間", MAX_DIMENSION));
+    }
+    if format == ImageFormat::Png && options.width as u64 * options.height as u64 > MAX_PNG_PIXELS {
+        return Err(format!("PNG 圖片最多 {} 萬像素，請縮小尺寸或改用 SVG", MAX_PNG_PIXELS / 10_000));
+    }
+
+    let request = LayoutRequest {
+        kind: options.kind,
+        width: options.width as f64,
+        height: options.height as f64,
+        min_area: 16.0,
+        max_depth: options.max_depth,
+    };
+    Ok(PendingImage {
+        layout: layout::compute(tree, index, id, &request)?,
+        format,
+        labels: options.labels,
+        destination: destination.to_path_buf(),
+    })
+}
+
+// 5x7 bitmap font for the PNG labels: printable ASCII, one byte per column, lowest bit on top
+const GLYPH_WIDTH: usize = 5;
+const GLYPH_HEIGHT: usize = 7;
+const FONT: [[u8; GLYPH_WIDTH]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], [0x00, 0x00, 0x5f, 0x00, 0x00], [0x00, 0x07, 0x00, 0x07, 0x00], [0x14, 0x7f, 0x14, 0x7f, 0x14],
+    [0x24, 0x2a, 0x7f, 0x2a, 0x12], [0x23, 0x13, 0x08, 0x64, 0x62], [0x36, 0x49, 0x55, 0x22, 0x50], [0x00, 0x05, 0x03, 0x00, 0x00],
+    [0x00, 0x1c, 0x22, 0x41, 0x00], [0x00, 0x41, 0x22, 0x1c, 0x00], [0x08, 0x2a, 0x1c, 0x2a, 0x08], [0x08, 0x08, 0x3e, 0x08, 0x08],
+    [0x00, 0x50, 0x30, 0x00, 0x00], [0x08, 0x08, 0x08, 0x08, 0x08], [0x00, 0x60, 0x60, 0x00, 0x00], [0x20, 0x10, 0x08, 0x04, 0x02],
+    [0x3e, 0x51, 0x49, 0x45, 0x3e], [0x00, 0x42, 0x7f, 0x40, 0x00], [0x42, 0x61, 0x51, 0x49, 0x46], [0x21, 0x41, 0x45, 0x4b, 0x31],
+    [0x18, 0x14, 0x12, 0x7f, 0x10], [0x27, 0x45, 0x45, 0x45, 0x39], [0x3c, 0x4a, 0x49, 0x49, 0x30], [0x01, 0x71, 0x09, 0x05, 0x03],
+    [0x36, 0x49, 0x49, 0x49, 0x36], [0x06, 0x49, 0x49, 0x29, 0x1e], [0x00, 0x36, 0x36, 0x00, 0x00], [0x00, 0x56, 0x36, 0x00, 0x00],
+    [0x08, 0x14, 0x22, 0x41, 0x00], [0x14, 0x14, 0x14, 0x14, 0x14], [0x00, 0x41, 0x22, 0x14, 0x08], [0x02, 0x01, 0x51, 0x09, 0x06],
+    [0x32, 0x49, 0x79, 0x41, 0x3e], [0x7e, 0x11, 0x11, 0x11, 0x7e], [0x7f, 0x49, 0x49, 0x49, 0x36], [0x3e, 0x41, 0x41, 0x41, 0x22],
+    [0x7f, 0x41, 0x41, 0x22, 0x1c], [0x7f, 0x49, 0x49, 0x49, 0x41], [0x7f, 0x09, 0x09, 0x09, 0x01], [0x3e, 0x41, 0x49, 0x49, 0x7a],
+    [0x7f, 0x08, 0x08, 0x08, 0x7f], [0x00, 0x41, 0x7f, 0x41, 0x00], [0x20, 0x40, 0x41, 0x3f, 0x01], [0x7f, 0x08, 0x14, 0x22, 0x41],
+    [0x7f, 0x40, 0x40, 0x40, 0x40], [0x7f, 0x02, 0x0c, 0x02, 0x7f], [0x7f, 0x04, 0x08, 0x10, 0x7f], [0x3e, 0x41, 0x41, 0x41, 0x3e],
+    [0x7f, 0x09, 0x09, 0x09, 0x06], [0x3e, 0x41, 0x51, 0x21, 0x5e], [0x7f, 0x09, 0x19, 0x29, 0x46], [0x46, 0x49, 0x49, 0x49, 0x31],
+    [0x01, 0x01, 0x7f, 0x01, 0x01], [0x3f, 0x40, 0x40, 0x40, 0x3f], [0x1f, 0x20, 0x40, 0x20, 0x1f], [0x3f, 0x40, 0x38, 0x40, 0x3f],
+    [0x63, 0x14, 0x08, 0x14, 0x63], [0x07, 0x08, 0x70, 0x08, 0x07], [0x61, 0x51, 0x49, 0x45, 0x43], [0x00, 0x7f, 0x41, 0x41, 0x00],
+    [0x02, 0x04, 0x08, 0x10, 0x20], [0x00, 0x41, 0x41, 0x7f, 0x00], [0x04, 0x02, 0x01, 0x02, 0x04], [0x40, 0x40, 0x40, 0x40, 0x40],
+    [0x00, 0x01, 0x02, 0x04, 0x00], [0x20, 0x54, 0x54, 0x54, 0x78], [0x7f, 0x48, 0x44, 0x44, 0x38], [0x38, 0x44, 0x44, 0x44, 0x20],
+    [0x38, 0x44, 0x44, 0x48, 0x7f], [0x38, 0x54, 0x54, 0x54, 0x18], [0x08, 0x7e, 0x09, 0x01, 0x02], [0x0c, 0x52, 0x52, 0x52, 0x3e],
+    [0x7f, 0x08, 0x04, 0x04, 0x78], [0x00, 0x44, 0x7d, 0x40, 0x00], [0x20, 0x40, 0x44, 0x3d, 0x00], [0x7f, 0x10, 0x28, 0x44, 0x00],
+    [0x00, 0x41, 0x7f, 0x40, 0x00], [0x7c, 0x04, 0x18, 0x04, 0x78], [0x7c, 0x08, 0x04, 0x04, 0x78], [0x38, 0x44, 0x44, 0x44, 0x38],
+    [0x7c, 0x14, 0x14, 0x14, 0x08], [0x08, 0x14, 0x14, 0x18, 0x7c], [0x7c, 0x08, 0x04, 0x04, 0x08], [0x48, 0x54, 0x54, 0x54, 0x20],
+    [0x04, 0x3f, 0x44, 0x40, 0x20], [0x3c, 0x40, 0x40, 0x20, 0x7c], [0x1c, 0x20, 0x40, 0x20, 0x1c], [0x3c, 0x40, 0x30, 0x40, 0x3c],
+    [0x44, 0x28, 0x10, 0x28, 0x44], [0x0c, 0x50, 0x50, 0x50, 0x3c], [0x44, 0x64, 0x54, 0x4c, 0x44], [0x00, 0x08, 0x36, 0x41, 0x00],
+    [0x00, 0x00, 0x7f, 0x00, 0x00], [0x00, 0x41, 0x36, 0x08, 0x00], [0x08, 0x04, 0x08, 0x10, 0x08],
+];
+const ELLIPSIS: [u8; GLYPH_WIDTH] = [0x40, 0x00, 0x40, 0x00, 0x40];
+
+fn glyph(c: char) -> Option<&'static [u8; GLYPH_WIDTH]> {
+    match c {
+        ' '..='~' => Some(&FONT[c as usize - ' ' as usize]),
+        '…' => Some(&ELLIPSIS),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labelled(name: &str, id: Option<NodeId>, folded: Option<u64>) -> LayoutItem {
+        LayoutItem {
+            id,
+            name: name.to_string(),
+            size: 1,
+            depth: 1,
+            is_directory: false,
+            category: None,
+            folded,
+            shape: Shape::Rect { x: 0.0, y: 0.0, width: 100.0, height: 20.0 },
+            label: Some(name.to_string()),
+        }
+    }
+
+    #[test]
+    fn png_labels_skip_names_the_font_cannot_draw() {
+        assert_eq!(png_label(&labelled("movie.mkv", Some(1), None)).as_deref(), Some("movie.mkv"));
+        assert_eq!(png_label(&labelled("long name…", Some(1), None)).as_deref(), Some("long name…"));
+        assert_eq!(png_label(&labelled("照片", Some(1), None)), None);
+        assert_eq!(png_label(&labelled("12 個較小項目", None, Some(12))).as_deref(), Some("+12"));
+    }
+}
@@ -0,0 +1,662 @@
+// Self-contained HTML report of a scan: treemap, largest files and directories, type
+// breakdown, size histogram, errors and disk usage, with the data embedded as JSON and
+// no external assets, so the file can be attached to a ticket and opened anywhere.
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::classify::{Category, TypeBreakdown};
+use crate::errors::ErrorLog;
+use crate::layout::{self, Rect, HEADER, PADDING};
+use crate::osname;
+use crate::spill::{IndexReader, SpillEntry};
+use crate::topn::SizedPath;
+use crate::tree::{NodeId, ScanTree, ROOT_NODE};
+
+const DEFAULT_REPORT_DEPTH: usize = 8;
+const REPORT_CHILDREN: usize = 40; // Per directory; the rest is folded into one entry
+const REPORT_NODE_BUDGET: usize = 50_000; // Keeps the embedded tree to a few MB
+// The treemap is laid out for this box and scaled to the page, which keeps its aspect ratio
+const MAP_WIDTH: f64 = 1152.0;
+const MAP_HEIGHT: f64 = 520.0;
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ReportOptions {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+    #[serde(default)]
+    pub locale: ReportLocale,
+}
+
+// Same language tags as the app's translations
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+pub enum ReportLocale {
+    #[default]
+    #[serde(rename = "zh-Hant")]
+    ZhHant,
+    #[serde(rename = "en-US")]
+    EnUs,
+    #[serde(rename = "ja-JP")]
+    JaJp,
+}
+
+impl ReportLocale {
+    pub fn parse(tag: &str) -> Option<Self> {
+        match tag {
+            "zh-Hant" => Some(Self::ZhHant),
+            "en-US" => Some(Self::EnUs),
+            "ja-JP" => Some(Self::JaJp),
+            _ => None,
+        }
+    }
+
+    fn tag(self) -> &'static str {
+        match self {
+            Self::ZhHant => "zh-Hant",
+            Self::EnUs => "en-US",
+            Self::JaJp => "ja-JP",
+        }
+    }
+
+    fn strings(self) -> &'static ReportStrings {
+        match self {
+            Self::ZhHant => &ZH_HANT,
+            Self::EnUs => &EN_US,
+            Self::JaJp => &JA_JP,
+        }
+    }
+}
+
+// Text of the report page; "{n}" stands for a number
+#[derive(Serialize)]
+struct ReportStrings {
+    title: &'static str,
+    other_items: &'static str,
+    distribution: &'static str,
+    largest_files: &'static str,
+    largest_dirs: &'static str,
+    file_types: &'static str,
+    extensions: &'static str,
+    size_histogram: &'static str,
+    errors: &'static str,
+    total_size: &'static str,
+    apparent_size: &'static str,
+    file_count: &'static str,
+    disk_usage: &'static str,
+    available: &'static str,
+    path: &'static str,
+    size: &'static str,
+    category: &'static str,
+    count: &'static str,
+    median: &'static str,
+    extension: &'static str,
+    largest_file: &'static str,
+    no_extension: &'static str,
+    files: &'static str,
+    no_errors: &'static str,
+    unreadable: &'static str,
+    first_listed: &'static str,
+}
+
+const ZH_HANT: ReportStrings = ReportStrings {
+    title: "StorViz 報告 - {n}",
+    other_items: "其他 {n} 項",
+    distribution: "空間分佈",
+    largest_files: "最大檔案",
+    largest_dirs: "最大資料夾",
+    file_types: "檔案類型",
+    extensions: "副檔名",
+    size_histogram: "檔案大小分佈",
+    errors: "錯誤",
+    total_size: "總大小",
+    apparent_size: "實際大小",
+    file_count: "檔案數",
+    disk_usage: "磁碟使用",
+    available: "可用",
+    path: "路徑",
+    size: "大小",
+    category: "類型",
+    count: "數量",
+    median: "中位數（約）",
+    extension: "副檔名",
+    largest_file: "最大檔案",
+    no_extension: "(無)",
+    files: "{n} 個檔案",
+    no_errors: "掃描過程中沒有錯誤",
+    unreadable: "{n} 個路徑無法讀取",
+    first_listed: "（列出前 {n} 個）",
+};
+
+const EN_US: ReportStrings = ReportStrings {
+    title: "StorViz Report - {n}",
+    other_items: "{n} other items",
+    distribution: "Space Distribution",
+    largest_files: "Largest Files",
+    largest_dirs: "Largest Folders",
+    file_types: "File Types",
+    extensions: "Extensions",
+    size_histogram: "File Size Distribution",
+    errors: "Errors",
+    total_size: "Total size",
+    apparent_size: "Apparent size",
+    file_count: "Files",
+    disk_usage: "Disk usage",
+    available: "Available",
+    path: "Path",
+    size: "Size",
+    category: "Type",
+    count: "Count",
+    median: "Median (approx.)",
+    extension: "Extension",
+    largest_file: "Largest file",
+    no_extension: "(none)",
+    files: "{n} files",
+    no_errors: "No errors during the scan",
+    unreadable: "{n} paths could not be read",
+    first_listed: " (first {n} listed)",
+};
+
+const JA_JP: ReportStrings = ReportStrings {
+    title: "StorViz レポート - {n}",
+    other_items: "その他 {n} 項目",
+    distribution: "容量の分布",
+    largest_files: "最大のファイル",
+    largest_dirs: "最大のフォルダー",
+    file_types: "ファイルの種類",
+    extensions: "拡張子",
+    size_histogram: "ファイルサイズの分布",
+    errors: "エラー",
+    total_size: "合計サイズ",
+    apparent_size: "実サイズ",
+    file_count: "ファイル数",
+    disk_usage: "ディスク使用量",
+    available: "空き",
+    path: "パス",
+    size: "サイズ",
+    category: "種類",
+    count: "数",
+    median: "中央値（概算）",
+    extension: "拡張子",
+    largest_file: "最大のファイル",
+    no_extension: "(なし)",
+    files: "{n} 個のファイル",
+    no_errors: "スキャン中のエラーはありません",
+    unreadable: "{n} 個のパスを読み取れませんでした",
+    first_listed: "（最初の {n} 個を表示）",
+};
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DiskSummary {
+    pub total_space: u64,
+    pub available_space: u64,
+    pub used_space: u64,
+}
+
+pub struct ReportInput<'a> {
+    pub tree: &'a ScanTree,
+    pub spill_index: Option<&'a Path>,
+    pub largest_files: &'a [SizedPath],
+    pub largest_dirs: &'a [SizedPath],
+    pub type_breakdown: &'a TypeBreakdown,
+    pub errors: &'a ErrorLog,
+    pub disk_info: Option<DiskSummary>,
+}
+
+#[derive(Serialize)]
+struct ReportData<'a> {
+    strings: &'static ReportStrings,
+    title: String,
+    root_path: String,
+    generated_at: u64,
+    total_size: u64,
+    apparent_size: u64,
+    disk_info: Option<&'a DiskSummary>,
+    largest_files: &'a [SizedPath],
+    largest_dirs: &'a [SizedPath],
+    types: &'a TypeBreakdown,
+    errors: &'a ErrorLog,
+    tree: ReportNode,
+}
+
+// Same short keys as the compact nodes sent to the app
+#[derive(Serialize)]
+struct ReportNode {
+    #[serde(rename = "n")]
+    name: String,
+    #[serde(rename = "s")]
+    size: u64,
+    #[serde(rename = "c", skip_serializing_if = "Option::is_none")]
+    children: Option<Vec<ReportNode>>,
+    #[serde(rename = "t", skip_serializing_if = "Option::is_none")]
+    category: Option<Category>,
+    #[serde(rename = "f", skip_serializing_if = "Option::is_none")]
+    folded: Option<u64>, // Number of entries folded into this one
+    // Treemap cell when the parent fills the map, and inside the parent's cell when the
+    // grandparent does; [x, y, width, height] as fractions of the area they're laid out in
+    #[serde(rename = "r", skip_serializing_if = "Option::is_none")]
+    rect: Option<[f32; 4]>,
+    #[serde(rename = "p", skip_serializing_if = "Option::is_none")]
+    preview: Option<[f32; 4]>,
+}
+
+// A child while picking which ones make it into the report
+struct Candidate {
+    name: String,
+    size: u64,
+    is_directory: bool,
+    category: Category,
+    id: Option<NodeId>,
+    spill_offset: Option<u64>,
+}
+
+struct TreeBuilder<'a> {
+    tree: &'a ScanTree,
+    index: Option<IndexReader>,
+    max_depth: usize,
+    budget: usize,
+    strings: &'static ReportStrings,
+}
+
+// Cells of the treemap computed by layout::squarify, relative to the area, by child index
+fn layout_cells(sizes: &[u64], width: f64, height: f64) -> Vec<Option<[f32; 4]>> {
+    let mut cells = vec![None; sizes.len()];
+    if width <= 0.0 || height <= 0.0 {
+        return cells;
+    }
+    let mut order: Vec<usize> = (0..sizes.len()).filter(|&i| sizes[i] > 0).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(sizes[i]));
+
+    let area = Rect { x: 0.0, y: 0.0, width, height };
+    for (&i, rect) in layout::squarify(&order, |&i| sizes[i], area) {
+        // Four decimals are finer than a pixel and keep the embedded JSON small
+        let fraction = |value: f64, of: f64| ((value / of * 10000.0).round() / 10000.0) as f32;
+        cells[i] = Some([fraction(rect.x, width), fraction(rect.y, height), fraction(rect.width, width), fraction(rect.height, height)]);
+    }
+    cells
+}
+
+// Room for the children inside a directory's cell of the given size
+fn inner_box(cell: [f32; 4], width: f64, height: f64) -> (f64, f64) {
+    (cell[2] as f64 * width - 2.0 * PADDING, cell[3] as f64 * height - HEADER - PADDING)
+}
+
+impl TreeBuilder<'_> {
+    fn children(&mut self, id: Option<NodeId>, spill_offset: Option<u64>) -> Vec<Candidate> {
+        let tree = self.tree;
+        if let Some(offset) = spill_offset {
+            let entries: Vec<SpillEntry> = match self.index.as_mut().map(|index| index.read(offset)) {
+                Some(Ok(entries)) => entries,
+                _ => return Vec::new(),
+            };
+            return entries
+                .into_iter()
+                .map(|entry| Candidate {
+                    size: entry.size,
+                    is_directory: entry.is_directory,
+                    category: entry.category,
+                    id: None,
+                    spill_offset: entry.offset,
+                    name: entry.name,
+                })
+                .collect();
+        }
+        let Some(id) = id else {
+            return Vec::new();
+        };
+        tree.children(id)
+            .map(|child| {
+                let node = tree.node(child);
+                Candidate {
+                    name: tree.name(child).to_string(),
+                    size: node.size,
+                    is_directory: node.is_directory,
+                    category: node.meta.category,
+                    id: Some(child),
+                    spill_offset: tree.spilled_offset(child),
+                }
+            })
+            .collect()
+    }
+
+    // `preview` is the room for this directory's children inside its cell when its parent
+    // fills the map
+    fn build(&mut self, entry: Candidate, depth: usize, preview: (f64, f64)) -> ReportNode {
+        let category = (!entry.is_directory && entry.category != Category::Other).then_some(entry.category);
+        let mut node = ReportNode { name: entry.name, size: entry.size, children: None, category, folded: None, rect: None, preview: None };
+        if !entry.is_directory || depth >= self.max_depth || self.budget == 0 {
+            return node;
+        }
+
+        let mut candidates = self.children(entry.id, entry.spill_offset);
+        candidates.sort_by_key(|candidate| std::cmp::Reverse(candidate.size));
+
+        let shown = candidates.len().min(REPORT_CHILDREN).min(self.budget);
+        self.budget -= shown;
+        let rest = candidates.split_off(shown);
+        let rest_size: u64 = rest.iter().map(|candidate| candidate.size).sum();
+
+        let mut sizes: Vec<u64> = candidates.iter().map(|candidate| candidate.size).collect();
+        if !rest.is_empty() {
+            sizes.push(rest_size);
+        }
+        let cells = layout_cells(&sizes, MAP_WIDTH, MAP_HEIGHT);
+        let previews = layout_cells(&sizes, preview.0, preview.1);
+
+        let mut children: Vec<ReportNode> = candidates
+            .into_iter()
+            .enumerate()
+            .map(|(i, child)| {
+                let inner = cells[i].map_or((0.0, 0.0), |cell| inner_box(cell, MAP_WIDTH, MAP_HEIGHT));
+                ReportNode { rect: cells[i], preview: previews[i], ..self.build(child, depth + 1, inner) }
+            })
+            .collect();
+        if !rest.is_empty() {
+            children.push(ReportNode {
+                name: self.strings.other_items.replace("{n}", &rest.len().to_string()),
+                size: rest_size,
+                children: None,
+                category: None,
+                folded: Some(rest.len() as u64),
+                rect: cells[sizes.len() - 1],
+                preview: previews[sizes.len() - 1],
+            });
+        }
+
+        node.children = Some(children);
+        node
+    }
+}
+
+// A report whose data has been gathered from the tree; finish() writes the HTML file, and needs
+// no access to the tree
+pub struct PendingReport {
+    destination: PathBuf,
+    lang: &'static str,
+    title: String,
+    json: String,
+    nodes: usize,
+}
+
+impl PendingReport {
+    pub fn finish(self) -> Result<(), String> {
+        let destination = &self.destination;
+        let file = File::create(destination).map_err(|e| format!("無法建立報告 {}: {}", destination.display(), e))?;
+        let mut out = BufWriter::new(file);
+        let (head, tail) = REPORT_TEMPLATE.split_once("/*DATA*/").unwrap_or((REPORT_TEMPLATE, ""));
+        let head = head.replace("{{LANG}}", self.lang).replace("{{TITLE}}", &self.title);
+        out.write_all(head.as_bytes())
+            .and_then(|_| out.write_all(self.json.as_bytes()))
+            .and_then(|_| out.write_all(tail.as_bytes()))
+            .and_then(|_| out.flush())
+            .map_err(|e| e.to_string())?;
+
+        println!("📄 Report written to {} ({} nodes embedded)", destination.display(), self.nodes);
+        Ok(())
+    }
+}
+
+// Gather the report data for `destination`; the caller writes it with finish()
+pub fn prepare_report(destination: &Path, input: &ReportInput, options: &ReportOptions) -> Result<PendingReport, String> {
+    let tree = input.tree;
+    let index = match input.spill_index {
+        Some(index_path) => Some(IndexReader::open(index_path).map_err(|e| e.to_string())?),
+        None => None,
+    };
+    let strings = options.locale.strings();
+    let mut builder = TreeBuilder {
+        tree,
+        index,
+        max_depth: options.max_depth.unwrap_or(DEFAULT_REPORT_DEPTH),
+        budget: REPORT_NODE_BUDGET,
+        strings,
+    };
+
+    let root = tree.node(ROOT_NODE);
+    let root_path = osname::encode_path(tree.root_path());
+    let report_tree = builder.build(
+        Candidate {
+            name: tree.name(ROOT_NODE).to_string(),
+            size: root.size,
+            is_directory: root.is_directory,
+            category: Category::Other,
+            id: Some(ROOT_NODE),
+            spill_offset: tree.spilled_offset(ROOT_NODE),
+        },
+        0,
+        (0.0, 0.0),
+    );
+
+    let data = ReportData {
+        strings,
+        title: options.title.clone().unwrap_or_else(|| strings.title.replace("{n}", &root_path)),
+        root_path,
+        generated_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        total_size: root.size,
+        apparent_size: root.meta.apparent_size,
+        disk_info: input.disk_info.as_ref(),
+        largest_files: input.largest_files,
+        largest_dirs: input.largest_dirs,
+        types: input.type_breakdown,
+        errors: input.errors,
+        tree: report_tree,
+    };
+
+    // "</" would end the script element early
+    let json = serde_json::to_string(&data).map_err(|e| e.to_string())?.replace("</", "<\\/");
+    Ok(PendingReport {
+        destination: destination.to_path_buf(),
+        lang: options.locale.tag(),
+        title: html_escape(&data.title),
+        json,
+        nodes: REPORT_NODE_BUDGET - builder.budget + 1,
+    })
+}
+
+fn html_escape(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+const REPORT_TEMPLATE: &str = r##"<!DOCTYPE html>
+<html lang="{{LANG}}">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{{TITLE}}</title>
+<style>
+  body { margin: 0; font-family: -apple-system, "Segoe UI", "Noto Sans TC", "Noto Sans JP", sans-serif; background: #0f172a; color: #e2e8f0; }
+  main { max-width: 1200px; margin: 0 auto; padding: 24px; }
+  h1 { font-size: 22px; margin: 0 0 4px; }
+  h2 { font-size: 16px; margin: 28px 0 10px; color: #93c5fd; }
+  .muted { color: #94a3b8; font-size: 13px; }
+  .cards { display: flex; gap: 12px; flex-wrap: wrap; margin-top: 16px; }
+  .card { background: #1e293b; border-radius: 8px; padding: 12px 16px; min-width: 160px; }
+  .card b { display: block; font-size: 20px; margin-top: 4px; }
+  .bar { height: 8px; background: #334155; border-radius: 4px; overflow: hidden; margin-top: 8px; }
+  .bar i { display: block; height: 100%; background: #3b82f6; }
+  #crumbs { font-size: 13px; margin-bottom: 8px; }
+  #crumbs a { color: #93c5fd; cursor: pointer; }
+  #map { position: relative; aspect-ratio: 1152 / 520; background: #1e293b; border-radius: 8px; overflow: hidden; }
+  .cell { position: absolute; box-sizing: border-box; border: 1px solid #0f172a; overflow: hidden; font-size: 11px; color: #fff; }
+  .cell.dir { cursor: pointer; }
+  .cell.dir:hover { outline: 2px solid #fff; outline-offset: -2px; z-index: 1; }
+  .cell span { position: absolute; left: 3px; top: 1px; right: 3px; white-space: nowrap; overflow: hidden; text-overflow: ellipsis; pointer-events: none; text-shadow: 0 1px 2px #000; }
+  .cols { display: grid; grid-template-columns: 1fr 1fr; gap: 16px; }
+  table { width: 100%; border-collapse: collapse; font-size: 13px; }
+  th, td { text-align: left; padding: 4px 6px; border-bottom: 1px solid #1e293b; }
+  td.num, th.num { text-align: right; white-space: nowrap; }
+  td.path { word-break: break-all; }
+  .hist { display: flex; align-items: flex-end; gap: 3px; height: 160px; }
+  .hist div { flex: 1; background: #3b82f6; min-height: 1px; position: relative; }
+  .hist-labels { display: flex; gap: 3px; font-size: 10px; color: #94a3b8; }
+  .hist-labels span { flex: 1; text-align: center; overflow: hidden; }
+  details { background: #1e293b; border-radius: 8px; padding: 8px 12px; }
+  @media (max-width: 800px) { .cols { grid-template-columns: 1fr; } }
+</style>
+</head>
+<body>
+<main>
+  <h1 id="title"></h1>
+  <div class="muted" id="subtitle"></div>
+  <div class="cards" id="cards"></div>
+
+  <h2 data-text="distribution"></h2>
+  <div id="crumbs"></div>
+  <div id="map"></div>
+
+  <div class="cols">
+    <div><h2 data-text="largest_files"></h2><table id="files"></table></div>
+    <div><h2 data-text="largest_dirs"></h2><table id="dirs"></table></div>
+  </div>
+
+  <div class="cols">
+    <div><h2 data-text="file_types"></h2><table id="categories"></table></div>
+    <div><h2 data-text="extensions"></h2><table id="extensions"></table></div>
+  </div>
+
+  <h2 data-text="size_histogram"></h2>
+  <div class="hist" id="hist"></div>
+  <div class="hist-labels" id="hist-labels"></div>
+
+  <h2 data-text="errors"></h2>
+  <div id="errors"></div>
+</main>
+<script type="application/json" id="data">/*DATA*/</script>
+<script>
+const D = JSON.parse(document.getElementById('data').textContent);
+const S = D.strings;
+const PADDING = 2, HEADER = 16; // As in layout.rs
+const COLORS = { video: '#e11d48', image: '#f59e0b', audio: '#a855f7', archive: '#84cc16', disk_image: '#14b8a6',
+  vm_image: '#0ea5e9', package: '#6366f1', database: '#f97316', code: '#22c55e', document: '#eab308',
+  executable: '#ef4444', font: '#ec4899', log: '#64748b' };
+
+function fmt(bytes) {
+  const units = ['B', 'KB', 'MB', 'GB', 'TB', 'PB'];
+  let i = 0;
+  while (bytes >= 1024 && i < units.length - 1) { bytes /= 1024; i++; }
+  return (i ? bytes.toFixed(1) : bytes) + ' ' + units[i];
+}
+function esc(value) {
+  return String(value).replace(/[&<>"]/g, c => ({ '&': '&amp;', '<': '&lt;', '>': '&gt;', '"': '&quot;' }[c]));
+}
+function el(tag, attrs, html) {
+  const node = document.createElement(tag);
+  Object.assign(node, attrs || {});
+  if (html !== undefined) node.innerHTML = html;
+  return node;
+}
+
+function text(key, n) {
+  return S[key].replace('{n}', n);
+}
+document.querySelectorAll('[data-text]').forEach(node => { node.textContent = S[node.dataset.text]; });
+
+// Header
+document.getElementById('title').textContent = D.title;
+document.getElementById('subtitle').textContent = D.root_path + ' · ' + new Date(D.generated_at * 1000).toLocaleString(document.documentElement.lang);
+const cards = document.getElementById('cards');
+cards.appendChild(el('div', { className: 'card' }, esc(S.total_size) + '<b>' + fmt(D.total_size) + '</b>'));
+cards.appendChild(el('div', { className: 'card' }, esc(S.apparent_size) + '<b>' + fmt(D.apparent_size) + '</b>'));
+cards.appendChild(el('div', { className: 'card' }, esc(S.file_count) + '<b>' + D.types.categories.reduce((a, c) => a + c.count, 0).toLocaleString() + '</b>'));
+if (D.disk_info) {
+  const d = D.disk_info, pct = d.total_space ? d.used_space / d.total_space * 100 : 0;
+  cards.appendChild(el('div', { className: 'card' }, esc(S.disk_usage) + ' ' + pct.toFixed(1) + '%<b>' + fmt(d.used_space) + ' / ' + fmt(d.total_space) +
+    '</b><div class="bar"><i style="width:' + pct + '%"></i></div><span class="muted">' + esc(S.available) + ' ' + fmt(d.available_space) + '</span>'));
+}
+
+// Treemap of the current directory, two levels deep. The cells come laid out from the scan
+// (layout.rs): "r" places a node when its parent fills the map, "p" inside the parent's cell.
+function place(cell, x, y, w, h) {
+  return { x: x + cell[0] * w, y: y + cell[1] * h, w: cell[2] * w, h: cell[3] * h };
+}
+function color(node, index) {
+  if (node.c) return 'hsl(' + (index * 47 % 360) + ', 45%, 38%)';
+  if (node.f) return '#475569';
+  return COLORS[node.t] || '#64748b';
+}
+const stack = [D.tree];
+function render() {
+  const map = document.getElementById('map');
+  map.innerHTML = '';
+  const current = stack[stack.length - 1];
+  const crumbs = document.getElementById('crumbs');
+  crumbs.innerHTML = '';
+  stack.forEach((node, i) => {
+    if (i) crumbs.appendChild(document.createTextNode(' / '));
+    const link = el('a', {}, esc(node.n || D.root_path));
+    link.onclick = () => { stack.length = i + 1; render(); };
+    crumbs.appendChild(link);
+  });
+  crumbs.appendChild(document.createTextNode(' · ' + fmt(current.s)));
+
+  const children = (current.c || []).filter(n => n.r);
+  children.forEach((node, i) => {
+    const rect = place(node.r, 0, 0, map.clientWidth, map.clientHeight);
+    const cell = el('div', { className: 'cell' + (node.c ? ' dir' : ''), title: node.n + '\n' + fmt(node.s) });
+    Object.assign(cell.style, { left: rect.x + 'px', top: rect.y + 'px', width: rect.w + 'px', height: rect.h + 'px', background: color(node, i) });
+    if (rect.w > 40 && rect.h > 14) cell.appendChild(el('span', {}, esc(node.n) + ' ' + fmt(node.s)));
+    if (node.c) {
+      cell.onclick = () => { stack.push(node); render(); };
+      if (rect.w > 60 && rect.h > 40) {
+        node.c.filter(n => n.p).forEach((child, j) => {
+          const sub = place(child.p, PADDING, HEADER, rect.w - 2 * PADDING, rect.h - HEADER - PADDING);
+          const box = el('div', { className: 'cell', title: node.n + '/' + child.n + '\n' + fmt(child.s) });
+          Object.assign(box.style, { left: sub.x + 'px', top: sub.y + 'px', width: sub.w + 'px', height: sub.h + 'px',
+            background: color(child, i + j + 1), opacity: 0.85 });
+          if (sub.w > 50 && sub.h > 14) box.appendChild(el('span', {}, esc(child.n)));
+          cell.appendChild(box);
+        });
+      }
+    }
+    map.appendChild(cell);
+  });
+}
+window.addEventListener('resize', render);
+render();
+
+// Tables
+function table(id, head, rows) {
+  const t = document.getElementById(id);
+  t.innerHTML = '<tr>' + head.map(h => '<th' + (h[1] ? ' class="num"' : '') + '>' + h[0] + '</th>').join('') + '</tr>' +
+    rows.map(r => '<tr>' + r.map((v, i) => '<td class="' + (head[i][1] ? 'num' : 'path') + '">' + v + '</td>').join('') + '</tr>').join('');
+}
+table('files', [[S.path], [S.size, 1]], D.largest_files.map(f => [esc(f.path), fmt(f.size)]));
+table('dirs', [[S.path], [S.size, 1]], D.largest_dirs.map(f => [esc(f.path), fmt(f.size)]));
+table('categories', [[S.category], [S.count, 1], [S.size, 1], [S.median, 1]],
+  D.types.categories.map(c => ['<span style="color:' + (COLORS[c.category] || '#94a3b8') + '">■</span> ' + esc(c.category),
+    c.count.toLocaleString(), fmt(c.bytes), fmt(c.median_size)]));
+table('extensions', [[S.extension], [S.count, 1], [S.size, 1], [S.largest_file]],
+  D.types.extensions.slice(0, 30).map(e => [esc(e.extension || S.no_extension), e.count.toLocaleString(), fmt(e.bytes),
+    e.largest_file ? esc(e.largest_file.path) + ' (' + fmt(e.largest_file.size) + ')' : '']));
+
+// Histogram
+const hist = D.types.size_histogram;
+const maxCount = Math.max(1, ...hist.map(b => b.count));
+hist.forEach(b => {
+  document.getElementById('hist').appendChild(el('div', { title: fmt(b.min_size) + ' – ' + fmt(b.max_size) + '\n' + text('files', b.count.toLocaleString()) + ' · ' + fmt(b.bytes) },
+    '')).style.height = (b.count / maxCount * 100) + '%';
+  document.getElementById('hist-labels').appendChild(el('span', {}, fmt(b.min_size)));
+});
+
+// Errors
+const errors = document.getElementById('errors');
+if (!D.errors.total) {
+  errors.innerHTML = '<span class="muted">' + esc(S.no_errors) + '</span>';
+} else {
+  const details = el('details', {}, '<summary>' + esc(text('unreadable', D.errors.total.toLocaleString()) +
+    (D.errors.total > D.errors.entries.length ? text('first_listed', D.errors.entries.length) : '')) + '</summary>');
+  const t = el('table');
+  t.innerHTML = D.errors.entries.map(e => '<tr><td class="path">' + esc(e.path) + '</td><td>' + esc(e.message) + '</td></tr>').join('');
+  details.appendChild(t);
+  errors.appendChild(details);
+}
+</script>
+</body>
+</html>
+"##;
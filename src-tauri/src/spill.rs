@@ -0,0 +1,247 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::classify::Category;
+use crate::tree::{EntryMeta, NodeId, ScanTree};
+
+// Spill-to-disk settings: everything deeper than `keep_depth` or smaller than `min_size`
+// is written to the index once complete, leaving only a summary node in memory
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpillOptions {
+    #[serde(default = "default_keep_depth")]
+    pub keep_depth: usize,
+    #[serde(default)]
+    pub min_size: u64,
+    #[serde(default)]
+    pub index_path: Option<String>,
+}
+
+fn default_keep_depth() -> usize {
+    3
+}
+
+impl SpillOptions {
+    pub fn should_spill(&self, depth: usize, size: u64) -> bool {
+        // The scan root always stays in memory
+        depth > 0 && (depth > self.keep_depth || size < self.min_size)
+    }
+}
+
+// One child of a spilled directory; `o` points at the child's own record for directories
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpillEntry {
+    #[serde(rename = "n")]
+    pub name: String,
+    #[serde(rename = "s")]
+    pub size: u64,
+    #[serde(rename = "d")]
+    pub is_directory: bool,
+    #[serde(rename = "o", skip_serializing_if = "Option::is_none", default)]
+    pub offset: Option<u64>,
+    #[serde(rename = "m", default)]
+    pub mtime: i64,
+    #[serde(rename = "u", default)]
+    pub uid: u32,
+    #[serde(rename = "t", default)]
+    pub category: Category,
+    #[serde(rename = "a", default)]
+    pub apparent_size: u64,
+}
+
+impl SpillEntry {
+    pub fn from_node(tree: &ScanTree, id: NodeId) -> Self {
+        let node = tree.node(id);
+        SpillEntry {
+            name: tree.name(id).to_string(),
+            size: node.size,
+            is_directory: node.is_directory,
+            offset: tree.spilled_offset(id),
+            mtime: node.meta.mtime,
+            uid: node.meta.uid,
+            category: node.meta.category,
+            apparent_size: node.meta.apparent_size,
+        }
+    }
+
+    pub fn meta(&self) -> EntryMeta {
+        EntryMeta {
+            mtime: self.mtime,
+            uid: self.uid,
+            category: self.category,
+            apparent_size: self.apparent_size,
+        }
+    }
+}
+
+// Append-only index of spilled directories, one JSON line per directory.
+// Children are always written before their parent, so records only point backwards.
+// The offsets only mean something to the tree they were written from, so the file is removed
+// together with its scan.
+pub struct SpillIndex {
+    path: PathBuf,
+    writer: Mutex<Option<(BufWriter<File>, u64)>>, // None once removed
+}
+
+impl SpillIndex {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer: Mutex::new(Some((BufWriter::new(file), 0))),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Append one directory's children and return the offset of their record
+    pub fn write_record(&self, entries: &[SpillEntry]) -> io::Result<u64> {
+        let line = serde_json::to_vec(entries).map_err(io::Error::other)?;
+        let mut guard = self.writer.lock().map_err(|_| io::Error::other("spill index lock poisoned"))?;
+        let (writer, position) = guard.as_mut().ok_or_else(|| io::Error::other("spill index removed"))?;
+        let offset = *position;
+        writer.write_all(&line)?;
+        writer.write_all(b"\n")?;
+        *position += line.len() as u64 + 1;
+        Ok(offset)
+    }
+
+    pub fn flush(&self) -> io::Result<()> {
+        let mut guard = self.writer.lock().map_err(|_| io::Error::other("spill index lock poisoned"))?;
+        match guard.as_mut() {
+            Some((writer, _)) => writer.flush(),
+            None => Ok(()),
+        }
+    }
+
+    // Close and delete the file; later writes fail and reads find nothing
+    pub fn remove(&self) {
+        let Ok(mut guard) = self.writer.lock() else { return };
+        // Closed first: Windows won't remove a file that is still open
+        if guard.take().is_none() {
+            return;
+        }
+        if let Err(e) = fs::remove_file(&self.path) {
+            eprintln!("❌ Failed to remove spill index {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+impl Drop for SpillIndex {
+    fn drop(&mut self) {
+        self.remove();
+    }
+}
+
+// The children of a completed directory as they are written to the index. Collected under
+// the tree lock; the write itself happens after it is released.
+pub fn spill_entries(tree: &ScanTree, id: NodeId) -> Vec<SpillEntry> {
+    tree.children(id).map(|child| SpillEntry::from_node(tree, child)).collect()
+}
+
+// Drop the written children from the tree, leaving the directory pointing at their record
+pub fn release_spilled(tree: &mut ScanTree, id: NodeId, offset: u64) {
+    tree.release_children(id);
+    tree.mark_spilled(id, offset);
+}
+
+// Keeps the index open for walks that read many records in a row
+pub struct IndexReader {
+    reader: BufReader<File>,
+    line: String,
+}
+
+impl IndexReader {
+    pub fn open(index_path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            reader: BufReader::new(File::open(index_path)?),
+            line: String::new(),
+        })
+    }
+
+    pub fn read(&mut self, offset: u64) -> io::Result<Vec<SpillEntry>> {
+        self.reader.seek(SeekFrom::Start(offset))?;
+        self.line.clear();
+        self.reader.read_line(&mut self.line)?;
+        serde_json::from_str(&self.line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+// Read the children of a spilled directory back from the index
+pub fn read_record(index_path: &Path, offset: u64) -> io::Result<Vec<SpillEntry>> {
+    IndexReader::open(index_path)?.read(offset)
+}
+
+// Bring a spilled directory's children back into the tree so they get regular node IDs.
+// Grandchildren stay on disk until they are asked for in turn.
+pub fn ensure_loaded(tree: &mut ScanTree, index_path: Option<&Path>, id: NodeId) -> io::Result<()> {
+    let (Some(index_path), Some(offset)) = (index_path, tree.spilled_offset(id)) else {
+        return Ok(());
+    };
+
+    for entry in read_record(index_path, offset)? {
+        let child = tree.add_node(id, &entry.name, entry.is_directory, entry.size, entry.meta()).map_err(io::Error::other)?;
+        if let Some(child_offset) = entry.offset {
+            tree.mark_spilled(child, child_offset);
+        }
+    }
+    tree.clear_spilled(id);
+    Ok(())
+}
+
+pub fn default_index_path() -> PathBuf {
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    std::env::temp_dir().join(format!("storviz-{}-{}.idx", std::process::id(), stamp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, size: u64) -> SpillEntry {
+        SpillEntry {
+            name: name.to_string(),
+            size,
+            is_directory: false,
+            offset: None,
+            mtime: 1_700_000_000,
+            uid: 0,
+            category: Category::Other,
+            apparent_size: size,
+        }
+    }
+
+    #[test]
+    fn records_read_back_and_index_is_removed_on_drop() {
+        let path = std::env::temp_dir().join(format!("storviz-test-{}.idx", std::process::id()));
+        let index = SpillIndex::create(&path).unwrap();
+        let first = index.write_record(&[entry("a.txt", 10), entry("b.txt", 20)]).unwrap();
+        let second = index.write_record(&[entry("c.txt", 30)]).unwrap();
+        index.flush().unwrap();
+
+        let names = |entries: Vec<SpillEntry>| entries.into_iter().map(|e| e.name).collect::<Vec<_>>();
+        let mut reader = IndexReader::open(&path).unwrap();
+        assert_eq!(names(reader.read(second).unwrap()), ["c.txt"]);
+        assert_eq!(names(reader.read(first).unwrap()), ["a.txt", "b.txt"]);
+
+        drop(reader);
+        drop(index);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn removed_index_refuses_writes() {
+        let path = std::env::temp_dir().join(format!("storviz-test-removed-{}.idx", std::process::id()));
+        let index = SpillIndex::create(&path).unwrap();
+        index.remove();
+        assert!(!path.exists());
+        assert!(index.write_record(&[entry("a.txt", 1)]).is_err());
+    }
+}
@@ -0,0 +1,216 @@
+// Size statistics for a group of files: totals, a log-scale histogram (which also yields
+// the median without keeping every size around), the largest file and the directories
+// holding the most bytes of the group.
+
+use serde::Serialize;
+
+use crate::topn::{SizedPath, TopN};
+
+const TOP_DIRS_PER_GROUP: usize = 5;
+
+// Four buckets per power of two, so a median read from the histogram is typically within ~12%
+// and never off by more than a quarter, the widest a bucket gets relative to its sizes.
+// Sizes below 4 get a bucket each; after that a bucket is the highest bit plus the next two.
+fn bucket_index(size: u64) -> usize {
+    if size < 4 {
+        return size as usize;
+    }
+    let high_bit = 63 - size.leading_zeros();
+    let sub = (size >> (high_bit - 2)) & 3;
+    ((high_bit - 1) * 4) as usize + sub as usize
+}
+
+// Inclusive lower and exclusive upper size of a bucket; the last one ends at u64::MAX inclusive
+fn bucket_bounds(index: usize) -> (u64, u64) {
+    if index < 4 {
+        return (index as u64, index as u64 + 1);
+    }
+    let shift = (index / 4 - 1) as u32;
+    let sub = (index % 4) as u128;
+    let lower = (4 + sub) << shift;
+    let upper = ((5 + sub) << shift).min(u64::MAX as u128);
+    (lower as u64, upper as u64)
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SizeHistogram {
+    counts: Vec<u64>,
+    bytes: Vec<u64>,
+}
+
+impl SizeHistogram {
+    pub fn add(&mut self, size: u64) {
+        let index = bucket_index(size);
+        if index >= self.counts.len() {
+            self.counts.resize(index + 1, 0);
+            self.bytes.resize(index + 1, 0);
+        }
+        self.counts[index] += 1;
+        self.bytes[index] += size;
+    }
+
+    // Interpolated within the bucket holding the middle entry
+    pub fn median(&self) -> u64 {
+        let total: u64 = self.counts.iter().sum();
+        if total == 0 {
+            return 0;
+        }
+        let mut rank = total.div_ceil(2);
+        for (index, &count) in self.counts.iter().enumerate() {
+            if rank > count {
+                rank -= count;
+                continue;
+            }
+            let (lower, upper) = bucket_bounds(index);
+            let offset = (upper - lower) as u128 * (2 * rank - 1) as u128 / (2 * count) as u128;
+            return lower + offset as u64;
+        }
+        0
+    }
+
+    // Non-empty power-of-two ranges, smallest first
+    pub fn octaves(&self) -> Vec<HistogramBucket> {
+        let mut octaves: Vec<HistogramBucket> = Vec::new();
+        for (index, (&count, &bytes)) in self.counts.iter().zip(&self.bytes).enumerate() {
+            if count == 0 {
+                continue;
+            }
+            let (lower, _) = bucket_bounds(index);
+            let octave = 64 - lower.leading_zeros();
+            let min_size = if octave == 0 { 0 } else { 1u64 << (octave - 1) };
+            let max_size = if octave == 64 { u64::MAX } else { (1u64 << octave) - 1 };
+
+            match octaves.last_mut() {
+                Some(last) if last.min_size == min_size => {
+                    last.count += count;
+                    last.bytes += bytes;
+                }
+                _ => octaves.push(HistogramBucket { min_size, max_size, count, bytes }),
+            }
+        }
+        octaves
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct HistogramBucket {
+    pub min_size: u64,
+    pub max_size: u64, // Inclusive
+    pub count: u64,
+    pub bytes: u64,
+}
+
+pub struct SizeStats {
+    bytes: u64,
+    count: u64,
+    histogram: SizeHistogram,
+    largest: Option<SizedPath>,
+    top_dirs: TopN,
+}
+
+impl Default for SizeStats {
+    fn default() -> Self {
+        Self {
+            bytes: 0,
+            count: 0,
+            histogram: SizeHistogram::default(),
+            largest: None,
+            top_dirs: TopN::new(TOP_DIRS_PER_GROUP),
+        }
+    }
+}
+
+impl SizeStats {
+    pub fn add_file(&mut self, size: u64, path: impl FnOnce() -> String) {
+        self.bytes += size;
+        self.count += 1;
+        self.histogram.add(size);
+        if self.largest.as_ref().is_none_or(|largest| size > largest.size) {
+            self.largest = Some(SizedPath { size, path: path() });
+        }
+    }
+
+    // Bytes of this group sitting directly inside one directory
+    pub fn add_directory(&mut self, bytes: u64, path: impl FnOnce() -> String) {
+        self.top_dirs.offer(bytes, path);
+    }
+
+    pub fn summary(&self) -> SizeSummary {
+        SizeSummary {
+            bytes: self.bytes,
+            count: self.count,
+            // Never above the largest file, which also makes single-file groups exact
+            median_size: self.histogram.median().min(self.largest.as_ref().map_or(0, |largest| largest.size)),
+            largest_file: self.largest.clone(),
+            top_dirs: self.top_dirs.sorted(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SizeSummary {
+    pub bytes: u64,
+    pub count: u64,
+    pub median_size: u64, // Approximate: read from the histogram, so shown as such
+    pub largest_file: Option<SizedPath>,
+    pub top_dirs: Vec<SizedPath>, // Directories holding the most bytes of this type directly
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_hold_their_sizes() {
+        let mut sizes = vec![0, 1, 2, 3, u64::MAX];
+        for bit in 2..64 {
+            sizes.extend([(1u64 << bit) - 1, 1u64 << bit, (1u64 << bit) + 1]);
+        }
+        for &size in &sizes {
+            let (lower, upper) = bucket_bounds(bucket_index(size));
+            assert!(lower <= size && (size < upper || upper == u64::MAX), "{} in [{}, {})", size, lower, upper);
+        }
+        for bit in 2..64 {
+            assert_eq!(bucket_bounds(bucket_index(1u64 << bit)).0, 1u64 << bit);
+        }
+        assert_eq!((0..4).map(bucket_index).collect::<Vec<_>>(), [0, 1, 2, 3]);
+        assert_eq!(bucket_bounds(bucket_index(u64::MAX)).1, u64::MAX);
+    }
+
+    #[test]
+    fn median_is_within_a_quarter_of_the_exact_one() {
+        let sets: Vec<Vec<u64>> = vec![
+            vec![5],
+            vec![0, 1, 2],
+            vec![100, 200, 300, 400],
+            (1..=1000).map(|i| i * i * 37).collect(),
+            (0..999).map(|i| (i * 7919 % 1000) * 4096 + 1).collect(),
+            vec![1 << 62, (1 << 62) + 12345, 1 << 61],
+        ];
+        for mut sizes in sets {
+            let mut histogram = SizeHistogram::default();
+            for &size in &sizes {
+                histogram.add(size);
+            }
+            sizes.sort_unstable();
+            let exact = sizes[sizes.len().div_ceil(2) - 1];
+            let median = histogram.median();
+            assert!(median.abs_diff(exact) <= exact / 4, "median {} vs exact {}", median, exact);
+        }
+        assert_eq!(SizeHistogram::default().median(), 0);
+    }
+
+    #[test]
+    fn octaves_merge_buckets_by_power_of_two() {
+        let mut histogram = SizeHistogram::default();
+        for size in [0, 1, 2, 3, 4, 5, 7, 8, u64::MAX] {
+            histogram.add(size);
+        }
+        let octaves: Vec<(u64, u64, u64, u64)> =
+            histogram.octaves().iter().map(|bucket| (bucket.min_size, bucket.max_size, bucket.count, bucket.bytes)).collect();
+        assert_eq!(
+            octaves,
+            [(0, 0, 1, 0), (1, 1, 1, 1), (2, 3, 2, 5), (4, 7, 3, 16), (8, 15, 1, 8), (1 << 63, u64::MAX, 1, u64::MAX)]
+        );
+    }
+}
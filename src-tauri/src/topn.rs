@@ -0,0 +1,125 @@
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+#[derive(Clone, Debug, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SizedPath {
+    pub size: u64,
+    pub path: String,
+}
+
+// Keeps the N largest entries seen so far in a min-heap, so each offer is O(log N)
+pub struct TopN {
+    capacity: usize,
+    heap: BinaryHeap<Reverse<SizedPath>>,
+    changed: bool,
+}
+
+impl TopN {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            heap: BinaryHeap::with_capacity(capacity + 1),
+            changed: false,
+        }
+    }
+
+    // The path is only built when the entry actually makes it into the list
+    pub fn offer(&mut self, size: u64, path: impl FnOnce() -> String) {
+        if self.capacity == 0 || size == 0 {
+            return;
+        }
+        if self.heap.len() >= self.capacity {
+            match self.heap.peek() {
+                Some(Reverse(smallest)) if size <= smallest.size => return,
+                _ => {}
+            }
+        }
+
+        self.heap.push(Reverse(SizedPath { size, path: path() }));
+        if self.heap.len() > self.capacity {
+            self.heap.pop();
+        }
+        self.changed = true;
+    }
+
+    // Largest first
+    pub fn sorted(&self) -> Vec<SizedPath> {
+        let mut entries: Vec<SizedPath> = self.heap.iter().map(|Reverse(entry)| entry.clone()).collect();
+        entries.sort_by(|a, b| b.cmp(a));
+        entries
+    }
+
+    // Sorted snapshot, but only if something changed since the last one was taken
+    pub fn take_update(&mut self) -> Option<Vec<SizedPath>> {
+        if !self.changed {
+            return None;
+        }
+        self.changed = false;
+        Some(self.sorted())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offer(top: &mut TopN, size: u64, path: &str) {
+        top.offer(size, || path.to_string());
+    }
+
+    fn paths(entries: Vec<SizedPath>) -> Vec<String> {
+        entries.into_iter().map(|entry| entry.path).collect()
+    }
+
+    #[test]
+    fn keeps_the_largest_entries_sorted() {
+        let mut top = TopN::new(3);
+        for (size, path) in [(10, "a"), (50, "b"), (30, "c"), (5, "d"), (40, "e"), (20, "f")] {
+            offer(&mut top, size, path);
+        }
+        assert_eq!(paths(top.sorted()), ["b", "e", "c"]);
+        assert_eq!(top.sorted().iter().map(|entry| entry.size).collect::<Vec<_>>(), [50, 40, 30]);
+    }
+
+    #[test]
+    fn stays_within_capacity_and_skips_empty_entries() {
+        let mut top = TopN::new(2);
+        for size in 1..=100 {
+            offer(&mut top, size, "x");
+        }
+        assert_eq!(top.sorted().len(), 2);
+
+        let mut none = TopN::new(0);
+        offer(&mut none, 100, "x");
+        offer(&mut top, 0, "zero");
+        assert!(none.sorted().is_empty());
+        assert!(!paths(top.sorted()).contains(&"zero".to_string()));
+    }
+
+    #[test]
+    fn ties_with_the_smallest_kept_entry_are_not_taken() {
+        let mut top = TopN::new(2);
+        offer(&mut top, 10, "first");
+        offer(&mut top, 20, "big");
+        top.offer(10, || panic!("the path of a rejected entry is never built"));
+        assert_eq!(paths(top.sorted()), ["big", "first"]);
+
+        // Below capacity ties are all kept, ordered by path for equal sizes
+        let mut top = TopN::new(3);
+        offer(&mut top, 10, "a");
+        offer(&mut top, 10, "b");
+        assert_eq!(paths(top.sorted()), ["b", "a"]);
+    }
+
+    #[test]
+    fn updates_are_only_taken_after_a_change() {
+        let mut top = TopN::new(1);
+        assert!(top.take_update().is_none());
+        offer(&mut top, 10, "a");
+        assert_eq!(paths(top.take_update().unwrap()), ["a"]);
+        assert!(top.take_update().is_none());
+        offer(&mut top, 5, "b");
+        assert!(top.take_update().is_none());
+    }
+}
@@ -0,0 +1,580 @@
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Serialize, Serializer};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::classify::Category;
+use crate::estimate::{Estimate, Spread};
+use crate::fold::{Folded, FoldOptions};
+use crate::osname;
+
+// A node ID is its arena slot in the low 32 bits and the slot's generation above them. A slot
+// reused after its node was freed gets the next generation, so an ID still held for the old
+// node stops resolving (contains() is false) instead of naming whatever took its place.
+// Generations stay below 2^16, keeping IDs exact as JavaScript numbers.
+pub type NodeId = u64;
+type Slot = u32;
+type NameId = u32;
+
+pub const NO_NODE: NodeId = u64::MAX;
+pub const ROOT_NODE: NodeId = 0;
+const NO_SLOT: Slot = u32::MAX;
+const FREED_NAME: NameId = u32::MAX;
+const MAX_GENERATION: u16 = u16::MAX; // A slot freed this often is retired instead of reused
+
+fn slot(id: NodeId) -> usize {
+    (id & 0xFFFF_FFFF) as usize
+}
+
+// Per-entry attributes used by filters and exports
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EntryMeta {
+    pub mtime: i64, // Seconds since the Unix epoch
+    pub uid: u32,
+    pub category: Category,
+    pub apparent_size: u64, // Logical length; summed over children for directories
+}
+
+// What the tree doesn't show below a directory: everything past the scan's depth limit, or
+// past the depth a subtree was sent with
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Truncated {
+    pub hidden_size: u64,
+    pub hidden_entries: u64,
+}
+
+impl EntryMeta {
+    pub fn from_metadata(metadata: &fs::Metadata) -> Self {
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs() as i64)
+            .unwrap_or(0);
+
+        #[cfg(unix)]
+        let uid = metadata.uid();
+        #[cfg(not(unix))]
+        let uid = 0;
+
+        let apparent_size = if metadata.is_dir() { 0 } else { metadata.len() };
+
+        Self { mtime, uid, category: Category::Other, apparent_size }
+    }
+
+    pub fn with_category(self, category: Category) -> Self {
+        Self { category, ..self }
+    }
+}
+
+// One entry of the scan tree; names and links are indices so each node is a small fixed cost
+#[derive(Debug, Clone)]
+pub struct TreeNode {
+    name: NameId,
+    parent: Slot,
+    first_child: Slot,
+    next_sibling: Slot,
+    generation: u16,
+    pub size: u64,
+    pub is_directory: bool,
+    pub meta: EntryMeta,
+}
+
+// Deduplicates file names (node_modules, index.js, .DS_Store, ...) across the whole tree
+// Names are reference counted so slots can be recycled when nodes are released
+#[derive(Default)]
+struct NameInterner {
+    lookup: HashMap<Arc<str>, NameId>,
+    names: Vec<(Arc<str>, u32)>,
+    free: Vec<NameId>,
+}
+
+impl NameInterner {
+    fn intern(&mut self, name: &str) -> NameId {
+        if let Some(&id) = self.lookup.get(name) {
+            self.names[id as usize].1 += 1;
+            return id;
+        }
+        let name: Arc<str> = Arc::from(name);
+        let id = match self.free.pop() {
+            Some(id) => {
+                self.names[id as usize] = (name.clone(), 1);
+                id
+            }
+            None => {
+                self.names.push((name.clone(), 1));
+                (self.names.len() - 1) as NameId
+            }
+        };
+        self.lookup.insert(name, id);
+        id
+    }
+
+    fn release(&mut self, id: NameId) {
+        let entry = &mut self.names[id as usize];
+        entry.1 -= 1;
+        if entry.1 == 0 {
+            let name = std::mem::replace(&mut entry.0, Arc::from(""));
+            self.lookup.remove(&name);
+            self.free.push(id);
+        }
+    }
+
+    fn get(&self, id: NameId) -> &str {
+        &self.names[id as usize].0
+    }
+}
+
+// Arena-backed scan tree: nodes live in one Vec, paths are rebuilt from parent links on demand
+pub struct ScanTree {
+    root_path: PathBuf,
+    nodes: Vec<TreeNode>,
+    names: NameInterner,
+    free: Vec<Slot>,
+    spilled: HashMap<NodeId, u64>, // Directories whose children live in the spill index (offset of their record)
+    spreads: HashMap<NodeId, Spread>, // Directory statistics of an estimate-mode scan
+    truncated: HashMap<NodeId, u64>, // Directories measured but not walked (entries below them)
+    partial: HashSet<NodeId>, // Directories a scan limit cut short; their sizes are lower bounds
+}
+
+impl ScanTree {
+    pub fn new(root_path: impl Into<PathBuf>) -> Self {
+        Self {
+            root_path: root_path.into(),
+            nodes: Vec::new(),
+            names: NameInterner::default(),
+            free: Vec::new(),
+            spilled: HashMap::new(),
+            spreads: HashMap::new(),
+            truncated: HashMap::new(),
+            partial: HashSet::new(),
+        }
+    }
+
+    // Append a node and link it under its parent (pass NO_NODE for the root)
+    // Slots of released nodes are reused before the arena grows
+    pub fn add_node(&mut self, parent: NodeId, name: &str, is_directory: bool, size: u64, meta: EntryMeta) -> Result<NodeId, String> {
+        let (id, generation) = match self.free.last() {
+            Some(&id) => (id, self.nodes[id as usize].generation + 1),
+            // NO_SLOT is the last slot, so the arena is full one before it
+            None => {
+                let id = Slot::try_from(self.nodes.len())
+                    .ok()
+                    .filter(|&id| id != NO_SLOT)
+                    .ok_or_else(|| "掃描項目過多，已超過可記錄的上限".to_string())?;
+                (id, 0)
+            }
+        };
+        let name = self.names.intern(name);
+
+        let (parent, next_sibling) = if parent != NO_NODE {
+            let parent = slot(parent);
+            (parent as Slot, std::mem::replace(&mut self.nodes[parent].first_child, id))
+        } else {
+            (NO_SLOT, NO_SLOT)
+        };
+
+        let node = TreeNode {
+            name,
+            parent,
+            first_child: NO_SLOT,
+            next_sibling,
+            generation,
+            size,
+            is_directory,
+            meta,
+        };
+        if self.free.pop().is_some() {
+            self.nodes[id as usize] = node;
+        } else {
+            self.nodes.push(node);
+        }
+        Ok(self.id(id))
+    }
+
+    fn id(&self, slot: Slot) -> NodeId {
+        (self.nodes[slot as usize].generation as NodeId) << 32 | slot as NodeId
+    }
+
+    // Drop every descendant of a node from memory; the node itself stays as a summary
+    pub fn release_children(&mut self, id: NodeId) {
+        let mut pending: Vec<NodeId> = self.children(id).collect();
+        self.nodes[slot(id)].first_child = NO_SLOT;
+
+        while let Some(child) = pending.pop() {
+            pending.extend(self.children(child));
+            self.free_node(child);
+        }
+    }
+
+    fn free_node(&mut self, id: NodeId) {
+        self.spilled.remove(&id);
+        self.spreads.remove(&id);
+        self.truncated.remove(&id);
+        self.partial.remove(&id);
+        let node = &mut self.nodes[slot(id)];
+        let name = std::mem::replace(&mut node.name, FREED_NAME);
+        let retired = node.generation == MAX_GENERATION;
+        self.names.release(name);
+        if !retired {
+            self.free.push(slot(id) as Slot);
+        }
+    }
+
+    pub fn mark_spilled(&mut self, id: NodeId, offset: u64) {
+        self.spilled.insert(id, offset);
+    }
+
+    pub fn spilled_offset(&self, id: NodeId) -> Option<u64> {
+        self.spilled.get(&id).copied()
+    }
+
+    pub fn clear_spilled(&mut self, id: NodeId) {
+        self.spilled.remove(&id);
+    }
+
+    pub fn set_spread(&mut self, id: NodeId, spread: Spread) {
+        self.spreads.insert(id, spread);
+    }
+
+    pub fn spread(&self, id: NodeId) -> Option<Spread> {
+        self.spreads.get(&id).copied()
+    }
+
+    pub fn clear_spreads(&mut self) {
+        self.spreads.clear();
+    }
+
+    // Size and entry count with confidence intervals, for nodes whose size is extrapolated
+    pub fn estimate(&self, id: NodeId) -> Option<Estimate> {
+        let spread = self.spreads.get(&id).filter(|spread| spread.estimated)?;
+        Some(Estimate::new(self.node(id).size, spread))
+    }
+
+    pub fn mark_truncated(&mut self, id: NodeId, hidden_entries: u64) {
+        self.truncated.insert(id, hidden_entries);
+    }
+
+    // A scan limit stopped the scan inside this directory: some of its entries were never read
+    pub fn mark_partial(&mut self, id: NodeId) {
+        self.partial.insert(id);
+    }
+
+    pub fn is_partial(&self, id: NodeId) -> bool {
+        self.partial.contains(&id)
+    }
+
+    // Set for directories past the scan's depth limit; their whole size is hidden
+    pub fn truncation(&self, id: NodeId) -> Option<Truncated> {
+        let hidden_entries = *self.truncated.get(&id)?;
+        Some(Truncated { hidden_size: self.node(id).size, hidden_entries })
+    }
+
+    // As truncation(), and also for a directory sent without children at the end of `depth_left`
+    pub fn hidden(&self, id: NodeId, depth_left: usize) -> Option<Truncated> {
+        if let Some(truncated) = self.truncation(id) {
+            return Some(truncated);
+        }
+        if depth_left > 0 || self.children(id).next().is_none() {
+            return None;
+        }
+
+        let mut hidden_entries = 0;
+        let mut pending: Vec<NodeId> = self.children(id).collect();
+        while let Some(child) = pending.pop() {
+            hidden_entries += 1 + self.truncated.get(&child).copied().unwrap_or(0);
+            pending.extend(self.children(child));
+        }
+        Some(Truncated { hidden_size: self.node(id).size, hidden_entries })
+    }
+
+    // Unlink a node from its parent, subtract its size from every ancestor and free its subtree
+    pub fn remove_node(&mut self, id: NodeId) {
+        let Some(parent) = self.parent(id) else {
+            return;
+        };
+
+        let (index, parent) = (slot(id), slot(parent));
+        let next = self.nodes[index].next_sibling;
+        if self.nodes[parent].first_child as usize == index {
+            self.nodes[parent].first_child = next;
+        } else {
+            let mut previous = self.nodes[parent].first_child;
+            while previous != NO_SLOT && self.nodes[previous as usize].next_sibling as usize != index {
+                previous = self.nodes[previous as usize].next_sibling;
+            }
+            if previous != NO_SLOT {
+                self.nodes[previous as usize].next_sibling = next;
+            }
+        }
+
+        let size = self.nodes[index].size;
+        let apparent_size = self.nodes[index].meta.apparent_size;
+        let mut ancestor = parent as Slot;
+        while ancestor != NO_SLOT {
+            let node = &mut self.nodes[ancestor as usize];
+            node.size = node.size.saturating_sub(size);
+            node.meta.apparent_size = node.meta.apparent_size.saturating_sub(apparent_size);
+            ancestor = node.parent;
+        }
+
+        self.release_children(id);
+        self.free_node(id);
+    }
+
+    pub fn root_path(&self) -> &Path {
+        &self.root_path
+    }
+
+    // Imports only learn the scanned path from their input
+    pub fn set_root_path(&mut self, root_path: &Path) {
+        self.root_path = root_path.to_path_buf();
+    }
+
+    pub fn find_child(&self, id: NodeId, name: &str) -> Option<NodeId> {
+        self.children(id).find(|&child| self.name(child) == name)
+    }
+
+    // Whether an ID names a live node: the slot is in use and still holds the same generation
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.nodes
+            .get(slot(id))
+            .is_some_and(|node| node.name != FREED_NAME && node.generation as NodeId == id >> 32)
+    }
+
+    pub fn set_size(&mut self, id: NodeId, size: u64, apparent_size: u64) {
+        let node = &mut self.nodes[slot(id)];
+        node.size = size;
+        node.meta.apparent_size = apparent_size;
+    }
+
+    pub fn node(&self, id: NodeId) -> &TreeNode {
+        &self.nodes[slot(id)]
+    }
+
+    pub fn name(&self, id: NodeId) -> &str {
+        self.names.get(self.nodes[slot(id)].name)
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        let parent = self.nodes[slot(id)].parent;
+        (parent != NO_SLOT).then(|| self.id(parent))
+    }
+
+    pub fn children(&self, id: NodeId) -> Children<'_> {
+        Children {
+            tree: self,
+            next: self.nodes[slot(id)].first_child,
+        }
+    }
+
+    // Rebuild the full path of a node by walking up to the root
+    pub fn path(&self, id: NodeId) -> PathBuf {
+        let mut names = Vec::new();
+        let mut current = id;
+        while let Some(parent) = self.parent(current) {
+            names.push(self.name(current));
+            current = parent;
+        }
+
+        let mut path = self.root_path.clone();
+        for name in names.iter().rev() {
+            path.push(osname::decode(name));
+        }
+        path
+    }
+}
+
+pub struct Children<'a> {
+    tree: &'a ScanTree,
+    next: Slot,
+}
+
+impl Iterator for Children<'_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        if self.next == NO_SLOT {
+            return None;
+        }
+        let id = self.tree.id(self.next);
+        self.next = self.tree.nodes[self.next as usize].next_sibling;
+        Some(id)
+    }
+}
+
+// Serializes a node straight out of the arena in the compact wire format ({i, n, s, c, d, t, o, e, h}),
+// with tiny children folded into a {i, n, s, d, f} bucket when fold options are given
+pub struct CompactNode<'a> {
+    tree: &'a ScanTree,
+    id: NodeId,
+    depth_left: usize,
+    fold: Option<FoldOptions>,
+}
+
+impl<'a> CompactNode<'a> {
+    pub fn new(tree: &'a ScanTree, id: NodeId, max_depth: usize, fold: Option<FoldOptions>) -> Self {
+        Self { tree, id, depth_left: max_depth, fold }
+    }
+}
+
+// The bucket of a directory's folded children; `i` is the directory, `f` is [count, offset]
+struct CompactBucket(Folded);
+
+impl Serialize for CompactBucket {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(5))?;
+        map.serialize_entry("i", &self.0.parent)?;
+        map.serialize_entry("n", &self.0.label())?;
+        map.serialize_entry("s", &self.0.size)?;
+        map.serialize_entry("d", &false)?;
+        map.serialize_entry("f", &[self.0.count, self.0.offset as u64])?;
+        map.end()
+    }
+}
+
+impl Serialize for CompactNode<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let node = self.tree.node(self.id);
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("i", &self.id)?;
+        map.serialize_entry("n", self.tree.name(self.id))?;
+        map.serialize_entry("s", &node.size)?;
+        if node.is_directory {
+            map.serialize_entry("c", &CompactChildren(self))?;
+        }
+        map.serialize_entry("d", &node.is_directory)?;
+        if !node.is_directory && node.meta.category != Category::Other {
+            map.serialize_entry("t", &node.meta.category)?;
+        }
+        // Spilled directories carry the offset of their record so the UI can drill down later
+        if let Some(offset) = self.tree.spilled_offset(self.id) {
+            map.serialize_entry("o", &offset)?;
+        }
+        // Extrapolated sizes carry their 95% confidence interval
+        if let Some(estimate) = self.tree.estimate(self.id) {
+            map.serialize_entry("e", &[estimate.size_low, estimate.size_high])?;
+        }
+        // Directories cut off by a depth limit carry what they hide
+        if let Some(hidden) = self.tree.hidden(self.id, self.depth_left) {
+            map.serialize_entry("h", &[hidden.hidden_size, hidden.hidden_entries])?;
+        }
+        // ... and ones a scan limit cut short are flagged, as their size is only what was read
+        if self.tree.is_partial(self.id) {
+            map.serialize_entry("p", &true)?;
+        }
+        map.end()
+    }
+}
+
+struct CompactChildren<'a, 'b>(&'b CompactNode<'a>);
+
+impl Serialize for CompactChildren<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let parent = self.0;
+        let mut seq = serializer.serialize_seq(None)?;
+        // Anything below the depth limit is sent as an empty directory
+        if parent.depth_left > 0 {
+            let (children, folded) = match &parent.fold {
+                Some(fold) => fold.split(parent.tree, parent.id),
+                None => (parent.tree.children(parent.id).collect(), None),
+            };
+            for child in children {
+                seq.serialize_element(&CompactNode::new(parent.tree, child, parent.depth_left - 1, parent.fold))?;
+            }
+            if let Some(folded) = folded {
+                seq.serialize_element(&CompactBucket(folded))?;
+            }
+        }
+        seq.end()
+    }
+}
+
+// An owned handle to a subtree of a shared tree; the tree is only borrowed while serializing
+#[derive(Clone)]
+pub struct CompactSubtree {
+    tree: Arc<Mutex<ScanTree>>,
+    id: NodeId,
+    max_depth: usize,
+    fold: Option<FoldOptions>,
+}
+
+impl CompactSubtree {
+    pub fn new(tree: Arc<Mutex<ScanTree>>, id: NodeId, max_depth: usize, fold: Option<FoldOptions>) -> Self {
+        Self { tree, id, max_depth, fold }
+    }
+
+    // Run `f` on the locked tree, for encoders that walk the subtree themselves
+    pub fn with_tree<R>(&self, f: impl FnOnce(&ScanTree, NodeId, usize, Option<FoldOptions>) -> R) -> Result<R, String> {
+        let tree = self.tree.lock().map_err(|_| "scan tree lock poisoned".to_string())?;
+        Ok(f(&tree, self.id, self.max_depth, self.fold))
+    }
+}
+
+impl Serialize for CompactSubtree {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let tree = self.tree.lock().map_err(|_| serde::ser::Error::custom("scan tree lock poisoned"))?;
+        CompactNode::new(&tree, self.id, self.max_depth, self.fold).serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add(tree: &mut ScanTree, parent: NodeId, name: &str, size: u64) -> NodeId {
+        tree.add_node(parent, name, parent == NO_NODE, size, EntryMeta::default()).unwrap()
+    }
+
+    #[test]
+    fn reused_slot_gets_a_new_id() {
+        let mut tree = ScanTree::new("/scan");
+        let root = add(&mut tree, NO_NODE, "scan", 0);
+        let old = add(&mut tree, root, "old.txt", 10);
+        tree.remove_node(old);
+        assert!(!tree.contains(old));
+
+        let new = add(&mut tree, root, "new.txt", 20);
+        assert_eq!(slot(new), slot(old));
+        assert_ne!(new, old);
+        assert!(tree.contains(new));
+        assert!(!tree.contains(old));
+        assert_eq!(tree.name(new), "new.txt");
+        assert_eq!(tree.parent(new), Some(root));
+    }
+
+    #[test]
+    fn remove_node_unlinks_and_subtracts_sizes() {
+        let mut tree = ScanTree::new("/scan");
+        let root = add(&mut tree, NO_NODE, "scan", 0);
+        let dir = tree.add_node(root, "dir", true, 0, EntryMeta::default()).unwrap();
+        let a = add(&mut tree, dir, "a", 5);
+        let b = add(&mut tree, dir, "b", 7);
+        tree.set_size(dir, 12, 12);
+        tree.set_size(root, 12, 12);
+
+        tree.remove_node(a);
+        assert_eq!(tree.children(dir).collect::<Vec<_>>(), [b]);
+        assert_eq!(tree.node(dir).size, 7);
+        assert_eq!(tree.node(root).size, 7);
+        assert_eq!(tree.path(b), Path::new("/scan/dir/b"));
+    }
+
+    #[test]
+    fn retired_slot_is_not_reused() {
+        let mut tree = ScanTree::new("/scan");
+        let root = add(&mut tree, NO_NODE, "scan", 0);
+        let child = add(&mut tree, root, "f", 1);
+        tree.nodes[slot(child)].generation = MAX_GENERATION;
+        let child = tree.id(slot(child) as Slot);
+        tree.remove_node(child);
+
+        let next = add(&mut tree, root, "g", 1);
+        assert_ne!(slot(next), slot(child));
+        assert!(!tree.contains(child));
+    }
+}
@@ -0,0 +1,365 @@
+// Binary encoding of scan batches, sent as raw bytes over the channel instead of JSON.
+//
+// Layout (integers are unsigned LEB128 varints unless noted):
+//   "SVZB"  magic, 4 bytes
+//   u8      format version (WIRE_VERSION)
+//   u8      flags; bit 0 = everything after this byte is zlib-compressed
+//   header  length + UTF-8 JSON of the subtree event without its nodes
+//   names   count, then length + UTF-8 bytes for each distinct name
+//   nodes   count, then one row per node in pre-order:
+//             id, name index, size, parent row + 1 (0 for a subtree root),
+//             u8 flags (1 = directory, 2 = spill offset follows, 4 = category follows,
+//             8 = estimate interval follows, 16 = folded bucket follows, 32 = truncation follows,
+//             64 = cut short by a scan limit),
+//             category name index, spill offset, low, high, folded count, folded offset,
+//             hidden size, hidden entries
+// src/lib/scanWire.ts rebuilds the same {i, n, s, c, d, t, o, e, f, h, p} objects the JSON path sends.
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+
+use crate::classify::Category;
+use crate::fold::{Folded, FoldOptions};
+use crate::tree::{CompactSubtree, NodeId, ScanTree};
+
+const MAGIC: &[u8; 4] = b"SVZB";
+const WIRE_VERSION: u8 = 6;
+const FLAG_DEFLATE: u8 = 1;
+const COMPRESS_ABOVE: usize = 64 * 1024; // Small batches aren't worth the CPU
+
+const NODE_DIRECTORY: u8 = 1;
+const NODE_SPILLED: u8 = 2;
+const NODE_CATEGORY: u8 = 4;
+const NODE_ESTIMATED: u8 = 8;
+const NODE_FOLDED: u8 = 16;
+const NODE_TRUNCATED: u8 = 32;
+const NODE_PARTIAL: u8 = 64;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WireFormat {
+    #[default]
+    Json,
+    Binary,
+}
+
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    put_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+enum Row {
+    Node(NodeId),
+    Bucket(Folded),
+}
+
+#[derive(Default)]
+struct NodeTable {
+    names: Vec<String>,
+    name_ids: HashMap<String, u32>,
+    rows: Vec<u8>,
+    count: u64,
+}
+
+impl NodeTable {
+    fn name(&mut self, name: &str) -> u32 {
+        if let Some(&id) = self.name_ids.get(name) {
+            return id;
+        }
+        let id = self.names.len() as u32;
+        self.names.push(name.to_string());
+        self.name_ids.insert(name.to_string(), id);
+        id
+    }
+
+    // The "N smaller items" bucket of a directory: a leaf row with its own name
+    fn add_bucket(&mut self, folded: &Folded, parent_row: u64) {
+        let name = self.name(&folded.label());
+        self.count += 1;
+        put_varint(&mut self.rows, folded.parent);
+        put_varint(&mut self.rows, name as u64);
+        put_varint(&mut self.rows, folded.size);
+        put_varint(&mut self.rows, parent_row);
+        self.rows.push(NODE_FOLDED);
+        put_varint(&mut self.rows, folded.count);
+        put_varint(&mut self.rows, folded.offset as u64);
+    }
+
+    // Flatten a subtree without recursion; directories past `max_depth` go out without children
+    fn add_subtree(&mut self, tree: &ScanTree, root: NodeId, max_depth: usize, fold: Option<FoldOptions>) {
+        let mut pending: Vec<(Row, u64, usize)> = vec![(Row::Node(root), 0, max_depth)];
+        while let Some((item, parent_row, depth_left)) = pending.pop() {
+            let id = match item {
+                Row::Node(id) => id,
+                Row::Bucket(folded) => {
+                    self.add_bucket(&folded, parent_row);
+                    continue;
+                }
+            };
+            let node = tree.node(id);
+            let row = self.count + 1;
+            self.count += 1;
+
+            let name = self.name(tree.name(id));
+            let category = (!node.is_directory && node.meta.category != Category::Other).then(|| self.name(node.meta.category.as_str()));
+            let offset = tree.spilled_offset(id);
+            let estimate = tree.estimate(id);
+            let hidden = node.is_directory.then(|| tree.hidden(id, depth_left)).flatten();
+
+            let mut flags = 0;
+            if node.is_directory {
+                flags |= NODE_DIRECTORY;
+            }
+            if offset.is_some() {
+                flags |= NODE_SPILLED;
+            }
+            if category.is_some() {
+                flags |= NODE_CATEGORY;
+            }
+            if estimate.is_some() {
+                flags |= NODE_ESTIMATED;
+            }
+            if hidden.is_some() {
+                flags |= NODE_TRUNCATED;
+            }
+            if tree.is_partial(id) {
+                flags |= NODE_PARTIAL;
+            }
+
+            put_varint(&mut self.rows, id);
+            put_varint(&mut self.rows, name as u64);
+            put_varint(&mut self.rows, node.size);
+            put_varint(&mut self.rows, parent_row);
+            self.rows.push(flags);
+            if let Some(category) = category {
+                put_varint(&mut self.rows, category as u64);
+            }
+            if let Some(offset) = offset {
+                put_varint(&mut self.rows, offset);
+            }
+            if let Some(estimate) = estimate {
+                put_varint(&mut self.rows, estimate.size_low);
+                put_varint(&mut self.rows, estimate.size_high);
+            }
+            if let Some(hidden) = hidden {
+                put_varint(&mut self.rows, hidden.hidden_size);
+                put_varint(&mut self.rows, hidden.hidden_entries);
+            }
+
+            if node.is_directory && depth_left > 0 {
+                // Pushed in reverse so children come out in the same order as the JSON path
+                let (children, folded) = match &fold {
+                    Some(fold) => fold.split(tree, id),
+                    None => (tree.children(id).collect(), None),
+                };
+                pending.extend(folded.map(|folded| (Row::Bucket(folded), row, 0)));
+                pending.extend(children.into_iter().rev().map(|child| (Row::Node(child), row, depth_left - 1)));
+            }
+        }
+    }
+}
+
+// Encode a subtree event; `header` is the event with its nodes taken out
+pub fn encode(header: &impl Serialize, subtrees: &[CompactSubtree]) -> Result<Vec<u8>, String> {
+    let mut table = NodeTable::default();
+    for subtree in subtrees {
+        subtree.with_tree(|tree, id, max_depth, fold| table.add_subtree(tree, id, max_depth, fold))?;
+    }
+
+    let mut body = Vec::with_capacity(table.rows.len() + 1024);
+    put_bytes(&mut body, &serde_json::to_vec(header).map_err(|e| e.to_string())?);
+    put_varint(&mut body, table.names.len() as u64);
+    for name in &table.names {
+        put_bytes(&mut body, name.as_bytes());
+    }
+    put_varint(&mut body, table.count);
+    body.extend_from_slice(&table.rows);
+
+    let mut message = Vec::with_capacity(body.len() + 6);
+    message.extend_from_slice(MAGIC);
+    message.push(WIRE_VERSION);
+    if body.len() > COMPRESS_ABOVE {
+        message.push(FLAG_DEFLATE);
+        let mut encoder = ZlibEncoder::new(message, Compression::fast());
+        encoder.write_all(&body).map_err(|e| e.to_string())?;
+        encoder.finish().map_err(|e| e.to_string())
+    } else {
+        message.push(0);
+        message.extend_from_slice(&body);
+        Ok(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::{EntryMeta, NO_NODE};
+    use flate2::read::ZlibDecoder;
+    use std::io::Read;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug)]
+    struct DecodedRow {
+        id: NodeId,
+        name: String,
+        size: u64,
+        parent: u64,
+        flags: u8,
+        category: Option<String>,
+        extra: Vec<u64>,
+    }
+
+    struct Cursor<'a>(&'a [u8]);
+
+    impl Cursor<'_> {
+        fn byte(&mut self) -> u8 {
+            let (&byte, rest) = self.0.split_first().expect("truncated message");
+            self.0 = rest;
+            byte
+        }
+
+        fn varint(&mut self) -> u64 {
+            let mut value = 0;
+            let mut shift = 0;
+            loop {
+                let byte = self.byte();
+                value |= u64::from(byte & 0x7f) << shift;
+                if byte < 0x80 {
+                    return value;
+                }
+                shift += 7;
+            }
+        }
+
+        fn text(&mut self) -> String {
+            let length = self.varint() as usize;
+            let (text, rest) = self.0.split_at(length);
+            self.0 = rest;
+            String::from_utf8(text.to_vec()).unwrap()
+        }
+    }
+
+    // Mirror of the reader in src/lib/scanWire.ts
+    fn decode(message: &[u8]) -> (serde_json::Value, Vec<DecodedRow>) {
+        assert_eq!(&message[..4], MAGIC);
+        assert_eq!(message[4], WIRE_VERSION);
+        let mut body = message[6..].to_vec();
+        if message[5] & FLAG_DEFLATE != 0 {
+            let mut inflated = Vec::new();
+            ZlibDecoder::new(&message[6..]).read_to_end(&mut inflated).unwrap();
+            body = inflated;
+        }
+
+        let mut cursor = Cursor(&body);
+        let header = serde_json::from_str(&cursor.text()).unwrap();
+        let names: Vec<String> = (0..cursor.varint()).map(|_| cursor.text()).collect();
+        let mut rows = Vec::new();
+        for _ in 0..cursor.varint() {
+            let id = cursor.varint();
+            let name = names[cursor.varint() as usize].clone();
+            let size = cursor.varint();
+            let parent = cursor.varint();
+            let flags = cursor.byte();
+            let category = (flags & NODE_CATEGORY != 0).then(|| names[cursor.varint() as usize].clone());
+            let mut extra = Vec::new();
+            let pairs = [NODE_ESTIMATED, NODE_FOLDED, NODE_TRUNCATED].iter().filter(|&&flag| flags & flag != 0).count();
+            if flags & NODE_SPILLED != 0 {
+                extra.push(cursor.varint());
+            }
+            for _ in 0..pairs * 2 {
+                extra.push(cursor.varint());
+            }
+            rows.push(DecodedRow { id, name, size, parent, flags, category, extra });
+        }
+        assert!(cursor.0.is_empty(), "trailing bytes");
+        (header, rows)
+    }
+
+    fn sample_tree() -> (Arc<Mutex<ScanTree>>, NodeId) {
+        let mut tree = ScanTree::new("/scan");
+        let root = tree.add_node(NO_NODE, "scan", true, 0, EntryMeta::default()).unwrap();
+        let sub = tree.add_node(root, "sub", true, 5_000_000_000_123, EntryMeta::default()).unwrap();
+        let video = EntryMeta::default().with_category(Category::Video);
+        tree.add_node(sub, "movie.mp4", false, 5_000_000_000_123, video).unwrap();
+        tree.add_node(root, "readme", false, 7, EntryMeta::default()).unwrap();
+        (Arc::new(Mutex::new(tree)), root)
+    }
+
+    #[test]
+    fn subtree_round_trips() {
+        let (tree, root) = sample_tree();
+        {
+            let mut tree = tree.lock().unwrap();
+            let sub = tree.children(root).find(|&child| tree.name(child) == "sub").unwrap();
+            tree.mark_partial(sub);
+        }
+        let header = serde_json::json!({ "event": "subtree", "seq": 3 });
+        let message = encode(&header, &[CompactSubtree::new(tree.clone(), root, 5, None)]).unwrap();
+        let (decoded_header, rows) = decode(&message);
+
+        assert_eq!(decoded_header, header);
+        assert_eq!(rows.len(), 4);
+        assert_eq!((rows[0].id, rows[0].name.as_str(), rows[0].parent), (root, "scan", 0));
+        assert_eq!(rows[0].flags, NODE_DIRECTORY);
+
+        let tree = tree.lock().unwrap();
+        for row in &rows {
+            assert_eq!(tree.name(row.id), row.name);
+            assert_eq!(tree.node(row.id).size, row.size);
+            if row.parent > 0 {
+                assert_eq!(tree.parent(row.id), Some(rows[row.parent as usize - 1].id));
+            }
+        }
+        let movie = rows.iter().find(|row| row.name == "movie.mp4").unwrap();
+        assert_eq!(movie.size, 5_000_000_000_123);
+        assert_eq!(movie.category.as_deref(), Some("video"));
+        assert_eq!(movie.flags, NODE_CATEGORY);
+        let sub = rows.iter().find(|row| row.name == "sub").unwrap();
+        assert_eq!(sub.flags, NODE_DIRECTORY | NODE_PARTIAL);
+    }
+
+    #[test]
+    fn depth_limit_and_folding_are_encoded() {
+        let (tree, root) = sample_tree();
+        let message = encode(&serde_json::json!({}), &[CompactSubtree::new(tree.clone(), root, 0, None)]).unwrap();
+        let (_, rows) = decode(&message);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].flags, NODE_DIRECTORY | NODE_TRUNCATED);
+        assert_eq!(rows[0].extra[1], 3); // Entries below the root
+
+        let fold = FoldOptions { min_size: 100, min_fraction: 0.0, min_items: 1 };
+        let message = encode(&serde_json::json!({}), &[CompactSubtree::new(tree.clone(), root, 5, Some(fold))]).unwrap();
+        let (_, rows) = decode(&message);
+        let bucket = rows.iter().find(|row| row.flags == NODE_FOLDED).unwrap();
+        assert_eq!(bucket.id, root);
+        assert_eq!(bucket.name, crate::fold::folded_label(1));
+        assert_eq!((bucket.size, bucket.extra[0], bucket.extra[1]), (7, 1, 1)); // "readme", after "sub"
+    }
+
+    #[test]
+    fn large_batches_are_compressed() {
+        let mut tree = ScanTree::new("/scan");
+        let root = tree.add_node(NO_NODE, "scan", true, 0, EntryMeta::default()).unwrap();
+        for i in 0..10_000u64 {
+            tree.add_node(root, &format!("file-{:05}.dat", i), false, i * 4096, EntryMeta::default()).unwrap();
+        }
+        let tree = Arc::new(Mutex::new(tree));
+
+        let message = encode(&serde_json::json!({}), &[CompactSubtree::new(tree, root, 1, None)]).unwrap();
+        assert_eq!(message[5], FLAG_DEFLATE);
+        let (_, rows) = decode(&message);
+        assert_eq!(rows.len(), 10_001);
+        assert!(rows[1..].iter().all(|row| row.parent == 1));
+    }
+}
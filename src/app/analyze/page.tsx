@@ -7,6 +7,8 @@ import { invoke, Channel } from '@tauri-apps/api/core'
 import { Dialog, DialogContent, DialogDescription, DialogFooter, DialogHeader, DialogTitle } from '@/components/ui/dialog'
 import { getFileTypeInfo } from '@/lib/fileTypeUtils'
 import { updateStats, updateDeleteStats } from '@/lib/statsStorage'
+import { decodeScanBatch, supportsBinaryWire, type CompactNode } from '@/lib/scanWire'
+import { joinNodePath } from '@/lib/nodePath'
 import { useVirtualizer } from '@tanstack/react-virtual'
 import { toast } from 'sonner'
 import { Toaster } from '@/components/ui/sonner'
@@ -18,8 +20,107 @@ interface FileNode {
   path: string
   children?: FileNode[]
   isDirectory: boolean
+  category?: string // File type decided by the scan (see Category in src-tauri/src/classify.rs)
+  estimate?: [number, number] // 95% interval when the size is extrapolated by estimate mode
+  folded?: { parent: number; count: number; offset: number } // "N smaller items" bucket, expanded with get_node_children
+  truncated?: [number, number] // Hidden size and entry count of a folder past the scan's depth limit
+  partial?: boolean // A scan limit stopped the scan inside this folder; its size is a lower bound
 }
 
+// Entries below 0.1% of their folder arrive folded into one "N smaller items" node
+const FOLD_OPTIONS = { min_fraction: 0.001 }
+
+// Listing returned by get_node_children (see NodeInfo in src-tauri/src/query.rs)
+interface NodeInfoPayload {
+  id: number
+  name: string
+  size: number
+  is_directory: boolean
+  category?: string
+  estimate?: { size_low: number; size_high: number }
+  folded?: { parent: number; count: number; offset: number }
+  truncated?: { hidden_size: number; hidden_entries: number }
+  partial?: boolean
+  children?: NodeInfoPayload[]
+}
+
+function nodeInfoToFileNode(info: NodeInfoPayload, parentPath: string): FileNode {
+  const nodePath = joinNodePath(parentPath, info.name)
+  return {
+    name: info.name,
+    size: info.size,
+    path: nodePath,
+    isDirectory: info.is_directory,
+    ...(info.category ? { category: info.category } : {}),
+    children: (info.children || []).map(child => nodeInfoToFileNode(child, nodePath)),
+    ...(info.estimate ? { estimate: [info.estimate.size_low, info.estimate.size_high] as [number, number] } : {}),
+    ...(info.folded ? { folded: info.folded } : {}),
+    ...(info.truncated ? { truncated: [info.truncated.hidden_size, info.truncated.hidden_entries] as [number, number] } : {}),
+    ...(info.partial ? { partial: true } : {})
+  }
+}
+
+// Scan events streamed by scan_directory_streaming (see ScanEvent in src-tauri/src/main.rs)
+const SCAN_PROTOCOL_VERSION = 2
+
+interface DiskInfoPayload {
+  total_space: number
+  available_space: number
+  used_space: number
+}
+
+interface ScanSummaryPayload {
+  root: { id: number; name: string; path: string; size: number }
+  total_scanned: number
+  total_size: number
+  disk_info: DiskInfoPayload | null
+  estimate: { size: number; size_low: number; size_high: number; entries: number } | null
+  refining: boolean
+  stopped?: 'time_limit' | 'entry_limit' // A scan limit ended the scan early; totals are partial
+}
+
+// A directory refined during the scan: provisional size while scanning, final once complete
+interface DirectoryUpdate {
+  id: number
+  parent: number
+  name: string
+  depth: number
+  size: number
+  complete: boolean
+}
+
+// Quick overview of the top level before the full scan (see src-tauri/src/overview.rs)
+interface OverviewPayload {
+  total_size: number
+  total_basis: 'exact' | 'volume' | 'sampled'
+  entries: { name: string; size: number; is_directory: boolean; basis: 'exact' | 'volume' | 'sampled' }[]
+  depth: number
+  elapsed_ms: number
+}
+
+type SubtreeEvent = { v: number; event: 'subtree'; seq: number; nodes: CompactNode[]; total_scanned: number; total_size: number }
+
+type ScanEvent = { v: number } & (
+  | { event: 'started'; root_path: string; disk_info: DiskInfoPayload | null; resumed?: { directories: number; pending: number } | null }
+  | { event: 'overview'; overview: OverviewPayload }
+  | {
+      event: 'progress'
+      total_scanned: number
+      total_size: number
+      current_path: string
+      directories?: DirectoryUpdate[]
+      files_per_second: number
+      bytes_per_second: number
+      target_basis: 'volume' | 'snapshot' | 'overview' | null // What fraction is measured against; null when nothing knows the totals
+      fraction: number | null // Against statvfs totals, the last scan of this path or the quick overview
+      eta_seconds: number | null
+    }
+  | Omit<SubtreeEvent, 'v'>
+  | { event: 'error'; path: string; message: string; fatal: boolean }
+  | { event: 'completed'; summary: ScanSummaryPayload }
+  | { event: 'cancelled'; partial: ScanSummaryPayload | null }
+)
+
 interface ChartData {
   name: string
   value: number
@@ -238,12 +339,7 @@ function rebuildTreeFromCompactNodes(rootNode: FileNode, compactNodes: any[]): F
 }
 
 function expandAndAddToMap(compactNode: any, parentPath: string, nodeMap: Map<string, FileNode>) {
-  let nodePath: string
-  if (parentPath.endsWith('\\') || parentPath.endsWith('/')) {
-    nodePath = `${parentPath}${compactNode.n}`
-  } else {
-    nodePath = `${parentPath}\\${compactNode.n}`
-  }
+  const nodePath = joinNodePath(parentPath, compactNode.n)
 
   const normalizedPath = nodePath.toLowerCase().replace(/\\/g, '/').replace(/\/+$/, '')
 
@@ -252,7 +348,12 @@ function expandAndAddToMap(compactNode: any, parentPath: string, nodeMap: Map<st
     size: compactNode.s,
     path: nodePath,
     isDirectory: compactNode.d,
-    children: []
+    children: [],
+    ...(compactNode.t ? { category: compactNode.t } : {}),
+    ...(compactNode.e ? { estimate: compactNode.e } : {}),
+    ...(compactNode.f ? { folded: { parent: compactNode.i, count: compactNode.f[0], offset: compactNode.f[1] } } : {}),
+    ...(compactNode.h ? { truncated: compactNode.h } : {}),
+    ...(compactNode.p ? { partial: true } : {})
   }
 
   nodeMap.set(normalizedPath, node)
@@ -274,14 +375,25 @@ function AnalyzeContent() {
   const [currentLevel, setCurrentLevel] = useState<FileNode | null>(null)
   const [breadcrumb, setBreadcrumb] = useState<FileNode[]>([])
   const [isLoading, setIsLoading] = useState(true)
-  const [scanProgress, setScanProgress] = useState<{ currentPath: string; filesScanned: number; scannedSize: number; estimatedTotal: number } | null>(null)
+  const [scanProgress, setScanProgress] = useState<{
+    currentPath: string
+    filesScanned: number
+    scannedSize: number
+    estimatedTotal: number
+    targetBasis?: string | null
+    fraction?: number | null
+    etaSeconds?: number | null
+    filesPerSecond?: number
+    bytesPerSecond?: number
+  } | null>(null)
   const [diskInfo, setDiskInfo] = useState<{ totalSpace: number; availableSpace: number; usedSpace: number } | null>(null)
   const [mousePosition, setMousePosition] = useState({ x: 0, y: 0 })
   const [scanStartTime, setScanStartTime] = useState<number | null>(null)
   const [scanElapsedTime, setScanElapsedTime] = useState<number>(0)
   const [scanCompleteTime, setScanCompleteTime] = useState<number | null>(null)
   const [showSummary, setShowSummary] = useState(false)
-  const [scanSummary, setScanSummary] = useState<{ filesScanned: number; totalSize: number; duration: number } | null>(null)
+  const [scanSummary, setScanSummary] = useState<{ filesScanned: number; totalSize: number; duration: number; estimate?: [number, number]; refining?: boolean; stopped?: string } | null>(null)
+  const refiningRef = useRef(false)
 
   // File selection state
   const [selectedFiles, setSelectedFiles] = useState<Set<string>>(new Set())
@@ -300,6 +412,9 @@ function AnalyzeContent() {
 
   // Cache compact nodes from batches
   const compactNodesCache = useRef<any[]>([])
+  const liveDirectories = useRef(new Map<number, DirectoryUpdate>())
+  const [liveTopDirectories, setLiveTopDirectories] = useState<DirectoryUpdate[]>([])
+  const [quickOverview, setQuickOverview] = useState<OverviewPayload | null>(null)
 
   const svgRef = useRef<SVGSVGElement>(null)
   const [hoveredSectorId, setHoveredSectorId] = useState<string | null>(null)
@@ -647,8 +762,10 @@ function AnalyzeContent() {
     const scanFolder = async () => {
       try {
         scanningRef.current = true
+        refiningRef.current = false
         setIsLoading(true)
         setScanProgress({ currentPath: path, filesScanned: 0, scannedSize: 0, estimatedTotal: 0 })
+        setQuickOverview(null)
 
         // Start timer
         const startTime = Date.now()
@@ -664,81 +781,183 @@ function AnalyzeContent() {
           setScanElapsedTime(Date.now() - startTimeRef.current)
         }, 100)
 
-        // Create channel for streaming batches
-        const onBatch = new Channel<{
-          nodes: FileNode[];
-          compact_nodes?: any[];  // Batch of compact nodes
-          total_scanned: number;
-          total_size: number;
-          is_complete: boolean;
-          root_node?: FileNode;
-          compact_root?: any;  // Compact format from backend
-          disk_info?: { total_space: number; available_space: number; used_space: number };
-          current_path?: string
-        }>()
-        onBatch.onmessage = (message) => {
-          // Cache compact nodes from batches
-          if (message.compact_nodes && message.compact_nodes.length > 0) {
-            compactNodesCache.current.push(...message.compact_nodes)
+        const stopTimer = () => {
+          if (elapsedTimeIntervalRef.current) {
+            clearInterval(elapsedTimeIntervalRef.current)
+            elapsedTimeIntervalRef.current = null
           }
+        }
 
-          // Update progress with disk_info if available
-          setScanProgress(prev => ({
-            currentPath: message.current_path || path,
-            filesScanned: message.total_scanned,
-            scannedSize: message.total_size,
-            estimatedTotal: message.disk_info ? message.disk_info.used_space : (prev?.estimatedTotal || 0)
-          }))
+        // Create channel for streaming scan events
+        const onBatch = new Channel<ScanEvent | ArrayBuffer>()
+        const handleEvent = (message: ScanEvent) => {
+          if (message.v !== SCAN_PROTOCOL_VERSION) {
+            console.warn(`Scan protocol v${message.v}, expected v${SCAN_PROTOCOL_VERSION}`)
+          }
 
-          // If complete, rebuild tree from cached compact nodes
-          if (message.is_complete) {
-            if (!message.root_node) {
-              console.error('ERROR: is_complete=true but no root_node!')
-              return
-            }
+          switch (message.event) {
+            case 'started':
+              // disk_info is only present for whole-disk scans
+              setScanProgress(prev => ({
+                currentPath: message.root_path,
+                filesScanned: prev?.filesScanned || 0,
+                scannedSize: prev?.scannedSize || 0,
+                estimatedTotal: message.disk_info ? message.disk_info.used_space : 0
+              }))
+              // An interrupted scan of the same folder left a checkpoint to continue from
+              if (message.resumed) {
+                toast.info('從上次中斷的進度繼續掃描', {
+                  description: `已完成的 ${message.resumed.directories.toLocaleString()} 個資料夾不必重新掃描`
+                })
+              }
+              break
+
+            case 'overview':
+              // Approximate sizes arrive first; the full scan refines them afterwards
+              setQuickOverview(message.overview)
+              break
+
+            case 'progress':
+              setScanProgress(prev => ({
+                currentPath: message.current_path || path,
+                filesScanned: message.total_scanned,
+                scannedSize: message.total_size,
+                estimatedTotal: prev?.estimatedTotal || 0,
+                targetBasis: message.target_basis,
+                fraction: message.fraction,
+                etaSeconds: message.eta_seconds,
+                filesPerSecond: message.files_per_second,
+                bytesPerSecond: message.bytes_per_second
+              }))
+
+              // Keep the largest top-level folders up to date while they are being scanned
+              if (message.directories && message.directories.length > 0) {
+                message.directories.forEach(update => liveDirectories.current.set(update.id, update))
+                setLiveTopDirectories(
+                  Array.from(liveDirectories.current.values())
+                    .filter(update => update.depth === 1)
+                    .sort((a, b) => b.size - a.size)
+                    .slice(0, 8)
+                )
+              }
+              break
+
+            case 'subtree':
+              // Cache compact nodes until the scan completes, then let the backend send the next batch
+              compactNodesCache.current.push(...message.nodes)
+              invoke('ack_scan_batch', { seq: message.seq }).catch(() => {})
+              setScanProgress(prev => prev && ({
+                ...prev,
+                filesScanned: message.total_scanned,
+                scannedSize: message.total_size
+              }))
+              break
+
+            case 'error':
+              if (message.fatal) {
+                console.error('Scan failed:', message.path, message.message)
+                stopTimer()
+                compactNodesCache.current = []
+                liveDirectories.current.clear()
+                setIsLoading(false)
+                setScanProgress(null)
+              } else {
+                console.warn('Cannot read:', message.path, message.message)
+              }
+              break
+
+            case 'cancelled':
+              stopTimer()
+              compactNodesCache.current = []
+              liveDirectories.current.clear()
+              break
+
+            case 'completed': {
+              const { summary } = message
+
+              // Stop timer and record completion time
+              stopTimer()
+              const completionTime = Date.now() - startTimeRef.current
+              setScanCompleteTime(completionTime)
+              setScanElapsedTime(completionTime)
+
+              // Rebuild tree from cached compact nodes
+              const rootNode: FileNode = {
+                name: summary.root.name,
+                size: summary.root.size,
+                path: summary.root.path,
+                children: [],
+                isDirectory: true
+              }
+              const finalTree = rebuildTreeFromCompactNodes(rootNode, compactNodesCache.current)
+
+              setData(finalTree)
+              setCurrentLevel(finalTree)
+              setBreadcrumb([finalTree])
+              setDiskInfo(summary.disk_info ? {
+                totalSpace: summary.disk_info.total_space,
+                availableSpace: summary.disk_info.available_space,
+                usedSpace: summary.disk_info.used_space
+              } : null)
+
+              // 更新累計統計數據
+              updateStats(summary.total_scanned, summary.total_size)
+
+              // Clear cache
+              compactNodesCache.current = []
+              liveDirectories.current.clear()
+
+              // A second completed event carries the exact values of a refined estimate
+              const refined = refiningRef.current
+              refiningRef.current = summary.refining
+              if (refined) {
+                setScanSummary(prev => prev && { ...prev, filesScanned: summary.total_scanned, totalSize: summary.total_size, estimate: undefined, refining: false })
+                toast.success('估計值已精算為精確大小')
+                break
+              }
 
-            // Stop timer and record completion time
-            if (elapsedTimeIntervalRef.current) {
-              clearInterval(elapsedTimeIntervalRef.current)
-              elapsedTimeIntervalRef.current = null
+              // Show summary screen
+              setScanSummary({
+                filesScanned: summary.total_scanned,
+                totalSize: summary.estimate ? summary.estimate.size : summary.total_size,
+                duration: completionTime,
+                estimate: summary.estimate ? [summary.estimate.size_low, summary.estimate.size_high] : undefined,
+                refining: summary.refining,
+                stopped: summary.stopped
+              })
+              setShowSummary(true)
+              setScanProgress(null)
+              break
             }
 
-            // Calculate final completion time directly
-            const completionTime = Date.now() - startTimeRef.current
-            setScanCompleteTime(completionTime)
-            setScanElapsedTime(completionTime)
-
-            // Rebuild tree from cached compact nodes
-            const finalTree = rebuildTreeFromCompactNodes(message.root_node, compactNodesCache.current)
-
-            setData(finalTree)
-            setCurrentLevel(finalTree)
-            setBreadcrumb([finalTree])
-            setDiskInfo(message.disk_info ? {
-              totalSpace: message.disk_info.total_space,
-              availableSpace: message.disk_info.available_space,
-              usedSpace: message.disk_info.used_space
-            } : null)
-
-            // 更新累計統計數據
-            updateStats(message.total_scanned, message.total_size)
-
-            // Clear cache
-            compactNodesCache.current = []
-
-            // Show summary screen
-            setScanSummary({
-              filesScanned: message.total_scanned,
-              totalSize: message.total_size,
-              duration: completionTime
-            })
-            setShowSummary(true)
-            setScanProgress(null)
+            default:
+              // Events added by newer backends are ignored
+              break
           }
         }
 
+        // Binary subtrees decode asynchronously, so chain events to keep them in order
+        let pendingEvents = Promise.resolve()
+        onBatch.onmessage = (data) => {
+          pendingEvents = pendingEvents
+            .then(async () => handleEvent(data instanceof ArrayBuffer ? await decodeScanBatch<SubtreeEvent>(data) : data))
+            .catch(error => console.error('Failed to decode scan event:', error))
+        }
+
         // Start streaming scan (returns immediately, scanning in background)
-        await invoke('scan_directory_streaming', { path, onBatch })
+        const options = {
+          strategy: 'quick_overview',
+          backpressure: true,
+          fold: FOLD_OPTIONS,
+          // Resumable after a crash; estimate scans are cheap to redo and can't be checkpointed
+          checkpoint: searchParams.get('estimate') !== '1',
+          ...(supportsBinaryWire() ? { wire_format: 'binary' } : {}),
+          // Quick triage: sample very large directories, then refine to exact values in the background
+          ...(searchParams.get('estimate') === '1' ? { estimate: { refine: true } } : {}),
+          // Folders deeper than this are measured but not expanded
+          ...(searchParams.get('maxDepth') ? { max_depth: Number(searchParams.get('maxDepth')) } : {})
+        }
+        await invoke('scan_directory_streaming', { path, onBatch, options })
       } catch (error) {
         console.error('Scan failed:', error)
         setIsLoading(false)
@@ -773,6 +992,10 @@ function AnalyzeContent() {
 
   // Calculate estimated remaining time based on progress
   const calculateRemainingTime = (): string | null => {
+    // The backend's ETA works for every path, not only whole disks
+    if (scanProgress?.etaSeconds != null) {
+      return formatTime(scanProgress.etaSeconds * 1000)
+    }
     if (!scanProgress || !scanStartTime || scanProgress.estimatedTotal === 0 || scanProgress.scannedSize === 0) {
       return null
     }
@@ -1110,8 +1333,50 @@ function AnalyzeContent() {
     return null
   }
 
+  // Replace a "N smaller items" bucket with the entries it stands for
+  const expandFoldedNode = async (bucket: FileNode) => {
+    if (!data || !bucket.folded) return
+    const parentPath = bucket.path.slice(0, bucket.path.length - bucket.name.length).replace(/[\\/]$/, '')
+
+    try {
+      const page = await invoke<{ total: number; children: NodeInfoPayload[] }>('get_node_children', {
+        nodeId: bucket.folded.parent,
+        query: { offset: bucket.folded.offset, limit: bucket.folded.count, depth: 3, fold: FOLD_OPTIONS, bucket: true }
+      })
+      const expanded = page.children.map(child => nodeInfoToFileNode(child, parentPath))
+
+      const replaceBucket = (node: FileNode): FileNode => {
+        if (!node.children) return node
+        if (node.children.includes(bucket)) {
+          return { ...node, children: node.children.flatMap(child => child === bucket ? expanded : [child]) }
+        }
+        return { ...node, children: node.children.map(replaceBucket) }
+      }
+      const updatedTree = replaceBucket(data)
+      setData(updatedTree)
+
+      if (currentLevel) {
+        const updatedCurrentLevel = findNodePath(updatedTree, currentLevel.path)
+        if (updatedCurrentLevel) {
+          setCurrentLevel(updatedCurrentLevel[updatedCurrentLevel.length - 1])
+          setBreadcrumb(breadcrumb.map(breadcrumbNode => {
+            const updatedBreadcrumbPath = findNodePath(updatedTree, breadcrumbNode.path)
+            return updatedBreadcrumbPath ? updatedBreadcrumbPath[updatedBreadcrumbPath.length - 1] : breadcrumbNode
+          }))
+        }
+      }
+    } catch (error) {
+      console.error('Failed to expand folded items:', error)
+      toast.error('無法展開較小項目', { description: String(error) })
+    }
+  }
+
   // Helper function to navigate to a node (used by both chart and file list)
   const navigateToNode = (targetNode: FileNode) => {
+    if (targetNode.folded) {
+      expandFoldedNode(targetNode)
+      return
+    }
     if (!targetNode.isDirectory || !targetNode.children) return
 
     // Check if node is a direct child of current level (most common case)
@@ -1184,8 +1449,14 @@ function AnalyzeContent() {
               <div className="text-center space-y-2 p-4 rounded-lg bg-muted/30">
                 <p className="text-xs text-muted-foreground">總大小</p>
                 <p className="text-3xl font-bold text-foreground font-mono">
-                  {formatBytes(scanSummary.totalSize)}
+                  {scanSummary.estimate ? '~' : ''}{formatBytes(scanSummary.totalSize)}
                 </p>
+                {scanSummary.estimate && (
+                  <p className="text-[10px] text-muted-foreground font-mono">
+                    95%：{formatBytes(scanSummary.estimate[0])} – {formatBytes(scanSummary.estimate[1])}
+                    {scanSummary.refining && <span className="ml-1 animate-pulse">・背景精算中</span>}
+                  </p>
+                )}
               </div>
               <div className="text-center space-y-2 p-4 rounded-lg bg-muted/30">
                 <p className="text-xs text-muted-foreground">耗時</p>
@@ -1195,6 +1466,13 @@ function AnalyzeContent() {
               </div>
             </div>
 
+            {/* Partial result of a scan that hit one of its limits */}
+            {scanSummary.stopped && (
+              <p className="text-xs text-amber-500 text-center relative z-10">
+                已達掃描{scanSummary.stopped === 'time_limit' ? '時間' : '項目數'}上限，以下為部分結果
+              </p>
+            )}
+
             {/* Disk Info */}
             {diskInfo && (
               <div className="pt-4 border-t border-border/50 relative z-10">
@@ -1282,9 +1560,11 @@ function AnalyzeContent() {
             <div className="bg-card/60 backdrop-blur-md rounded-lg border border-border/50 p-6 space-y-4 hover:bg-card/80 hover:border-primary/30 transition-all duration-300 hover:shadow-lg group relative overflow-hidden">
               <div className="absolute inset-0 bg-gradient-to-br from-primary/5 to-transparent rounded-lg opacity-0 group-hover:opacity-100 transition-opacity duration-300"></div>
 
-              {/* Progress bar - only show for root directory scans */}
-              {scanProgress.estimatedTotal > 0 && (() => {
-                const percentage = (scanProgress.scannedSize / scanProgress.estimatedTotal) * 100;
+              {/* Progress bar - whenever the expected total is known */}
+              {(scanProgress.fraction != null || scanProgress.estimatedTotal > 0) && (() => {
+                const percentage = scanProgress.fraction != null
+                  ? scanProgress.fraction * 100
+                  : (scanProgress.scannedSize / scanProgress.estimatedTotal) * 100;
                 const displayPercentage = Math.min(100, percentage);
                 return (
                   <div className="space-y-2 relative z-10">
@@ -1302,13 +1582,17 @@ function AnalyzeContent() {
                         aria-label="掃描進度"
                       />
                     </div>
-                    {scanProgress.scannedSize > scanProgress.estimatedTotal && (
+                    {scanProgress.fraction == null && scanProgress.scannedSize > scanProgress.estimatedTotal && (
                       <p className="text-xs text-muted-foreground">注意：實際大小可能因硬連結等因素超過預估</p>
                     )}
                   </div>
                 );
               })()}
 
+              {scanProgress.targetBasis === null && scanProgress.estimatedTotal === 0 && (
+                <p className="text-xs text-muted-foreground relative z-10">此資料夾的總量無法預先得知，僅顯示掃描速度</p>
+              )}
+
               {/* Stats */}
               <div className="grid grid-cols-2 gap-4 relative z-10">
                 <div className="space-y-1">
@@ -1325,6 +1609,54 @@ function AnalyzeContent() {
                 </div>
               </div>
 
+              {/* Throughput */}
+              {!!scanProgress.filesPerSecond && (
+                <p className="text-xs text-muted-foreground font-mono relative z-10">
+                  {Math.round(scanProgress.filesPerSecond).toLocaleString()} 項/秒・{formatBytes(scanProgress.bytesPerSecond || 0)}/秒
+                </p>
+              )}
+
+              {/* Quick overview: estimated sizes of the top level before the full scan gets there */}
+              {quickOverview && quickOverview.entries.length > 0 && liveTopDirectories.length === 0 && (
+                <div className="pt-2 border-t border-border/50 space-y-2 relative z-10">
+                  <p className="text-xs text-muted-foreground">
+                    快速概覽（估計，共 {quickOverview.total_basis === 'exact' ? '' : '約 '}{formatBytes(quickOverview.total_size)}）
+                  </p>
+                  {quickOverview.entries.slice(0, 8).map(entry => (
+                    <div key={entry.name} className="flex justify-between items-center text-xs gap-2">
+                      <span className="font-mono text-foreground truncate" title={entry.name}>{entry.name}</span>
+                      <span className="font-mono text-muted-foreground shrink-0">
+                        {entry.basis === 'exact' ? '' : '~'}{formatBytes(entry.size)}
+                      </span>
+                    </div>
+                  ))}
+                </div>
+              )}
+
+              {/* Largest folders so far, refined live as they are scanned */}
+              {liveTopDirectories.length > 0 && (
+                <div className="pt-2 border-t border-border/50 space-y-2 relative z-10">
+                  <p className="text-xs text-muted-foreground">目前最大的資料夾</p>
+                  {liveTopDirectories.map(directory => (
+                    <div key={directory.id} className="space-y-1">
+                      <div className="flex justify-between items-center text-xs gap-2">
+                        <span className="font-mono text-foreground truncate" title={directory.name}>
+                          {directory.name}
+                          {!directory.complete && <span className="ml-2 text-muted-foreground animate-pulse">掃描中</span>}
+                        </span>
+                        <span className="font-mono text-muted-foreground shrink-0">{formatBytes(directory.size)}</span>
+                      </div>
+                      <div className="h-1.5 w-full bg-muted rounded-full overflow-hidden">
+                        <div
+                          className={`h-full rounded-full transition-all duration-300 ${directory.complete ? 'bg-primary' : 'bg-primary/50'}`}
+                          style={{ width: `${(directory.size / liveTopDirectories[0].size) * 100}%` }}
+                        />
+                      </div>
+                    </div>
+                  ))}
+                </div>
+              )}
+
               {/* Time stats */}
               <div className="grid grid-cols-2 gap-4 relative z-10 pt-2 border-t border-border/50">
                 <div className="space-y-1">
@@ -1616,7 +1948,7 @@ function AnalyzeContent() {
                                     className="chart-sector"
                                     data-sector-id={sectorId}
                                     onMouseEnter={(e) => {
-                                      const fileTypeInfo = getFileTypeInfo(item.name, item.node.isDirectory)
+                                      const fileTypeInfo = getFileTypeInfo(item.name, item.node.isDirectory, item.node.category)
                                       handleHover(sectorId, e, fileTypeInfo.label, item.name, formatBytes(item.value), fileTypeInfo.icon, fileTypeInfo.color)
                                     }}
                                     onMouseMove={handleMouseMove}
@@ -1670,7 +2002,7 @@ function AnalyzeContent() {
                                 className="chart-sector"
                                 data-sector-id={sectorId}
                                 onMouseEnter={(e) => {
-                                  const fileTypeInfo = getFileTypeInfo(item.name, item.node.isDirectory)
+                                  const fileTypeInfo = getFileTypeInfo(item.name, item.node.isDirectory, item.node.category)
                                   handleHover(sectorId, e, fileTypeInfo.label, item.name, formatBytes(item.value), fileTypeInfo.icon, fileTypeInfo.color)
                                 }}
                                 onMouseMove={handleMouseMove}
@@ -1751,7 +2083,7 @@ function AnalyzeContent() {
               {rowVirtualizer.getVirtualItems().map((virtualRow) => {
                 const item = listData[virtualRow.index]
                 const sectorId = item.isTinyNode ? generateSectorId('__others__', 0) : generateSectorId(item.path, 0)
-                const fileTypeInfo = getFileTypeInfo(item.name, item.node.isDirectory)
+                const fileTypeInfo = getFileTypeInfo(item.name, item.node.isDirectory, item.node.category)
                 const IconComponent = fileTypeInfo.icon
                 const isSelected = selectedFiles.has(item.path)
                 const parentSelected = hasSelectedParent(item.path)
@@ -1799,7 +2131,7 @@ function AnalyzeContent() {
                               ? 'bg-muted-foreground/30 border-muted-foreground/50'
                               : 'border-muted-foreground/30 hover:border-primary/50'
                           }`}
-                          onClick={(e) => toggleFileSelection(item.path, e)}
+                          onClick={(e) => item.node.folded ? e.stopPropagation() : toggleFileSelection(item.path, e)}
                         >
                           {(isSelected || willBeDeleted || isIndeterminate) && (
                             <svg className={`w-3 h-3 ${isSelected ? 'text-white' : willBeDeleted ? 'text-white' : 'text-muted-foreground'}`} fill="none" stroke="currentColor" viewBox="0 0 24 24">
@@ -1812,10 +2144,29 @@ function AnalyzeContent() {
                           style={{ color: fileTypeInfo.color }}
                         />
                         <span className="text-xs font-medium truncate">{item.name}</span>
+                        {item.node.truncated && (
+                          <span
+                            className="text-[10px] text-amber-500 flex-shrink-0"
+                            title={`超過掃描深度上限，未展開 ${item.node.truncated[1].toLocaleString()} 個項目（${formatBytes(item.node.truncated[0])}）`}
+                          >
+                            深度上限
+                          </span>
+                        )}
+                        {item.node.partial && (
+                          <span
+                            className="text-[10px] text-amber-500 flex-shrink-0"
+                            title="掃描限制在此資料夾內停止，大小只含已讀到的部分"
+                          >
+                            未完整
+                          </span>
+                        )}
                       </div>
                       <div className="flex items-center gap-2 flex-shrink-0 relative z-10">
-                        <span className="text-[10px] font-mono text-muted-foreground">
-                          {formatBytes(item.value)}
+                        <span
+                          className="text-[10px] font-mono text-muted-foreground"
+                          title={item.node.estimate ? `估計值，95% 信賴區間 ${formatBytes(item.node.estimate[0])} – ${formatBytes(item.node.estimate[1])}` : undefined}
+                        >
+                          {item.node.estimate ? '~' : ''}{formatBytes(item.value)}
                         </span>
                         <div
                           className="w-3 h-3 rounded-sm flex-shrink-0"
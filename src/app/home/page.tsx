@@ -38,6 +38,7 @@ export default function HomePage() {
   const router = useRouter()
   const { t } = useTranslation()
   const [selectedPath, setSelectedPath] = useState<string>('')
+  const [quickEstimate, setQuickEstimate] = useState(false)
   const [isLoading, setIsLoading] = useState(false)
   const [mousePosition, setMousePosition] = useState({ x: 0, y: 0 })
 
@@ -133,7 +134,7 @@ export default function HomePage() {
       playAudio('2.mp3')
 
       setIsLoading(true)
-      router.push(`/analyze?path=${encodeURIComponent(selectedPath)}`)
+      router.push(`/analyze?path=${encodeURIComponent(selectedPath)}${quickEstimate ? '&estimate=1' : ''}`)
     }
   }
 
@@ -245,6 +246,16 @@ export default function HomePage() {
                 </div>
               )}
 
+              <label className="flex items-center gap-2 text-[11px] text-muted-foreground cursor-pointer select-none">
+                <input
+                  type="checkbox"
+                  checked={quickEstimate}
+                  onChange={(e) => setQuickEstimate(e.target.checked)}
+                  className="accent-primary"
+                />
+                {t('home.quickEstimate')}
+              </label>
+
               <div style={{ display: 'flex', justifyContent: 'center' }}>
                 <button
                   onClick={handleAnalyze}
@@ -0,0 +1,17 @@
+// joinNodePath 測試（bun test）；刪除時後端依這些路徑找檔案
+import { describe, expect, test } from 'bun:test'
+import { joinNodePath } from './nodePath'
+
+describe('joinNodePath', () => {
+  test('Unix 路徑用 / 接上，名稱中的反斜線保留', () => {
+    expect(joinNodePath('/home/user', 'docs')).toBe('/home/user/docs')
+    expect(joinNodePath(joinNodePath('/home/user', 'docs'), 'a\\b.txt')).toBe('/home/user/docs/a\\b.txt')
+    expect(joinNodePath('/', 'etc')).toBe('/etc')
+  })
+
+  test('Windows 路徑用反斜線接上', () => {
+    expect(joinNodePath('C:\\Users', 'me')).toBe('C:\\Users\\me')
+    expect(joinNodePath('C:\\', 'Users')).toBe('C:\\Users')
+    expect(joinNodePath('\\\\server\\share', 'file.txt')).toBe('\\\\server\\share\\file.txt')
+  })
+})
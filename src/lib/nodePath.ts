@@ -0,0 +1,9 @@
+// 節點路徑：以掃描根目錄所用的分隔符號接上子項名稱，刪除時後端才能找到同一個檔案
+// Unix 路徑以 / 開頭，名稱裡的反斜線是名稱的一部分；其餘（Windows）使用反斜線
+export function joinNodePath(parentPath: string, name: string) {
+  if (parentPath.endsWith('\\') || parentPath.endsWith('/')) {
+    return `${parentPath}${name}`
+  }
+  const separator = parentPath.startsWith('/') ? '/' : '\\'
+  return `${parentPath}${separator}${name}`
+}
@@ -0,0 +1,94 @@
+// scanWire 解碼器測試（bun test）；訊息依 src-tauri/src/wire.rs 的格式在此組出
+import { describe, expect, test } from 'bun:test'
+import { decodeScanBatch, type CompactNode } from './scanWire'
+
+function varint(out: number[], value: number) {
+  while (value >= 0x80) {
+    out.push((value % 0x80) | 0x80)
+    value = Math.floor(value / 0x80)
+  }
+  out.push(value)
+}
+
+function text(out: number[], value: string) {
+  const bytes = new TextEncoder().encode(value)
+  varint(out, bytes.length)
+  out.push(...bytes)
+}
+
+interface Row {
+  id: number
+  name: number
+  size: number
+  parent: number
+  flags: number
+  extra?: number[]
+}
+
+function body(header: object, names: string[], rows: Row[]): number[] {
+  const out: number[] = []
+  text(out, JSON.stringify(header))
+  varint(out, names.length)
+  names.forEach(name => text(out, name))
+  varint(out, rows.length)
+  for (const row of rows) {
+    varint(out, row.id)
+    varint(out, row.name)
+    varint(out, row.size)
+    varint(out, row.parent)
+    out.push(row.flags)
+    ;(row.extra || []).forEach(value => varint(out, value))
+  }
+  return out
+}
+
+function message(bytes: number[], flags = 0): ArrayBuffer {
+  return new Uint8Array([0x53, 0x56, 0x5a, 0x42, 6, flags, ...bytes]).buffer
+}
+
+async function deflate(bytes: number[]): Promise<number[]> {
+  const stream = new Blob([new Uint8Array(bytes)]).stream().pipeThrough(new CompressionStream('deflate'))
+  return Array.from(new Uint8Array(await new Response(stream).arrayBuffer()))
+}
+
+const names = ['scan', 'sub', 'movie.mp4', 'video', '1 個較小項目', 'caf␛E9']
+const rows: Row[] = [
+  { id: 0, name: 0, size: 5_000_000_000_130, parent: 0, flags: 1 },
+  { id: 2 ** 32 + 1, name: 1, size: 5_000_000_000_123, parent: 1, flags: 1 | 8 | 64, extra: [4_000_000_000_000, 6_000_000_000_000] },
+  { id: 3, name: 2, size: 5_000_000_000_123, parent: 2, flags: 4 | 2, extra: [3, 1234] },
+  { id: 0, name: 4, size: 7, parent: 1, flags: 16, extra: [1, 1] },
+  { id: 5, name: 5, size: 0, parent: 1, flags: 1 | 32, extra: [0, 9] }
+]
+
+function check(decoded: { event: string; nodes: CompactNode[] }) {
+  expect(decoded.event).toBe('subtree')
+  expect(decoded.nodes).toHaveLength(1)
+  const [root] = decoded.nodes
+  expect(root).toMatchObject({ i: 0, n: 'scan', s: 5_000_000_000_130, d: true })
+  expect(root.c!.map(node => node.n)).toEqual(['sub', '1 個較小項目', 'caf␛E9'])
+
+  const [sub, bucket, truncated] = root.c!
+  expect(sub).toMatchObject({ i: 2 ** 32 + 1, e: [4_000_000_000_000, 6_000_000_000_000], p: true })
+  expect(sub.c![0]).toEqual({ i: 3, n: 'movie.mp4', s: 5_000_000_000_123, d: false, t: 'video', o: 1234 })
+  expect(bucket).toEqual({ i: 0, n: '1 個較小項目', s: 7, d: false, f: [1, 1] })
+  expect(truncated).toEqual({ i: 5, n: 'caf␛E9', s: 0, d: true, c: [], h: [0, 9] })
+}
+
+describe('decodeScanBatch', () => {
+  test('rebuilds the compact nodes', async () => {
+    check(await decodeScanBatch(message(body({ event: 'subtree' }, names, rows))))
+  })
+
+  test('inflates compressed batches', async () => {
+    check(await decodeScanBatch(message(await deflate(body({ event: 'subtree' }, names, rows)), 1)))
+  })
+
+  test('rejects other messages', async () => {
+    await expect(decodeScanBatch(new Uint8Array([1, 2, 3, 4, 5, 6]).buffer)).rejects.toThrow('Not a scan batch')
+    const wrongVersion = new Uint8Array(message(body({}, [], [])))
+    wrongVersion[4] = 5
+    await expect(decodeScanBatch(wrongVersion.buffer)).rejects.toThrow('Unsupported scan batch version 5')
+    const truncated = new Uint8Array(message(body({ event: 'subtree' }, names, rows))).slice(0, 40)
+    await expect(decodeScanBatch(truncated.buffer)).rejects.toThrow('Truncated scan batch')
+  })
+})
@@ -0,0 +1,117 @@
+// 掃描批次的二進位格式解碼器（對應 src-tauri/src/wire.rs）
+// 還原成與 JSON 路徑相同的 {i, n, s, c, d, t, o, e, f, h, p} 精簡節點
+
+export interface CompactNode {
+  i: number
+  n: string // 非 UTF-8 的檔名位元組以 ␛HH 跳脫（字面的 ␛ 寫成 ␛␛），原樣傳回後端即可刪除
+  s: number
+  c?: CompactNode[]
+  d: boolean
+  t?: string
+  o?: number
+  e?: [number, number] // 估計模式下外推大小的 95% 信賴區間
+  f?: [number, number] // 「N 個較小項目」節點：[項目數, 在父資料夾依大小排序中的起始位置]，i 為父資料夾
+  h?: [number, number] // 超過深度上限而未展開的資料夾：[隱藏的大小, 隱藏的項目數]
+  p?: boolean // 掃描限制中途停止的資料夾，大小只含已讀到的部分
+}
+
+const MAGIC = 'SVZB'
+const WIRE_VERSION = 6
+const FLAG_DEFLATE = 1
+
+const NODE_DIRECTORY = 1
+const NODE_SPILLED = 2
+const NODE_CATEGORY = 4
+const NODE_ESTIMATED = 8
+const NODE_FOLDED = 16
+const NODE_TRUNCATED = 32
+const NODE_PARTIAL = 64
+
+// 後端只在環境支援解壓縮時才使用二進位格式
+export function supportsBinaryWire(): boolean {
+  return typeof DecompressionStream !== 'undefined'
+}
+
+class Reader {
+  private offset = 0
+  private decoder = new TextDecoder()
+
+  constructor(private bytes: Uint8Array) {}
+
+  byte(): number {
+    if (this.offset >= this.bytes.length) throw new Error('Truncated scan batch')
+    return this.bytes[this.offset++]
+  }
+
+  // LEB128；以乘法累加避免 32 位元位移溢位（大小最多到 2^53）
+  varint(): number {
+    let value = 0
+    let scale = 1
+    for (;;) {
+      const byte = this.byte()
+      value += (byte & 0x7f) * scale
+      if (byte < 0x80) return value
+      scale *= 128
+    }
+  }
+
+  string(): string {
+    const length = this.varint()
+    if (this.offset + length > this.bytes.length) throw new Error('Truncated scan batch')
+    const text = this.decoder.decode(this.bytes.subarray(this.offset, this.offset + length))
+    this.offset += length
+    return text
+  }
+}
+
+async function inflate(bytes: Uint8Array): Promise<Uint8Array> {
+  const stream = new Blob([bytes]).stream().pipeThrough(new DecompressionStream('deflate'))
+  return new Uint8Array(await new Response(stream).arrayBuffer())
+}
+
+// 二進位訊息只用於 subtree 事件，節點放回 nodes 欄位
+export async function decodeScanBatch<T extends { nodes: CompactNode[] }>(buffer: ArrayBuffer): Promise<T> {
+  const raw = new Uint8Array(buffer)
+  if (raw.length < 6 || String.fromCharCode(raw[0], raw[1], raw[2], raw[3]) !== MAGIC) {
+    throw new Error('Not a scan batch')
+  }
+  if (raw[4] !== WIRE_VERSION) {
+    throw new Error(`Unsupported scan batch version ${raw[4]}`)
+  }
+  const body = raw[5] & FLAG_DEFLATE ? await inflate(raw.subarray(6)) : raw.subarray(6)
+  const reader = new Reader(body)
+
+  const message = JSON.parse(reader.string()) as T
+
+  const names: string[] = []
+  const nameCount = reader.varint()
+  for (let i = 0; i < nameCount; i++) names.push(reader.string())
+
+  // 節點依前序排列，父節點一定先於子節點出現
+  const roots: CompactNode[] = []
+  const rows: CompactNode[] = []
+  const nodeCount = reader.varint()
+  for (let r = 0; r < nodeCount; r++) {
+    const id = reader.varint()
+    const name = names[reader.varint()]
+    const size = reader.varint()
+    const parent = reader.varint()
+    const flags = reader.byte()
+
+    const node: CompactNode = { i: id, n: name, s: size, d: (flags & NODE_DIRECTORY) !== 0 }
+    if (node.d) node.c = []
+    if (flags & NODE_CATEGORY) node.t = names[reader.varint()]
+    if (flags & NODE_SPILLED) node.o = reader.varint()
+    if (flags & NODE_ESTIMATED) node.e = [reader.varint(), reader.varint()]
+    if (flags & NODE_FOLDED) node.f = [reader.varint(), reader.varint()]
+    if (flags & NODE_TRUNCATED) node.h = [reader.varint(), reader.varint()]
+    if (flags & NODE_PARTIAL) node.p = true
+
+    rows.push(node)
+    if (parent === 0) roots.push(node)
+    else rows[parent - 1].c!.push(node)
+  }
+
+  message.nodes = roots
+  return message
+}